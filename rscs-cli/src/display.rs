@@ -0,0 +1,148 @@
+//! Output formatting
+
+use const_oid::db::DB;
+use der::Encode;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use x509_path::{name::GeneralNameRef, Names, TrustAnchor};
+use x509_verify::x509_cert::{time::Time, Certificate};
+
+/// Subject, issuer, serial number, validity and fingerprint of a certificate
+pub fn summary(cert: &Certificate) -> Value {
+    let tbs = &cert.tbs_certificate;
+    json!({
+        "subject": tbs.subject.to_string(),
+        "issuer": tbs.issuer.to_string(),
+        "serial": hex::encode(tbs.serial_number.as_bytes()),
+        "not_before": time(&tbs.validity.not_before),
+        "not_after": time(&tbs.validity.not_after),
+        "sha256": cert.to_der().map(|der| hex::encode(Sha256::digest(der))).unwrap_or_default(),
+    })
+}
+
+/// Certificates of a path, ordered from the target to the trust anchor
+pub fn path<'a>(certs: impl Iterator<Item = &'a Certificate>) -> Value {
+    json!({ "certificates": certs.map(summary).collect::<Vec<_>>() })
+}
+
+/// Summary of a certificate along with its extensions as parsed by `x509-path`
+pub fn details(cert: &Certificate, parsed: &TrustAnchor<'_>) -> Value {
+    let mut output = summary(cert);
+    output["key_identifier"] = Value::String(hex::encode(parsed.key_id().as_bytes()));
+    if let Some(path_len) = parsed.path_len_constraint() {
+        output["path_len_constraint"] = Value::from(path_len);
+    }
+    if let Some(policies) = parsed.policy_set() {
+        let mut policies: Vec<_> = policies.iter().map(oid_name).collect();
+        policies.sort();
+        output["policies"] = Value::from(policies);
+    }
+    if let Some(constraints) = parsed.name_constraints() {
+        output["name_constraints"] = json!({
+            "permitted": names(constraints.permitted_subtrees()),
+            "excluded": names(constraints.excluded_subtrees()),
+        });
+    }
+    if let Some(extensions) = parsed.extensions() {
+        output["extensions"] = Value::Array(
+            extensions
+                .iter()
+                .map(|e| {
+                    json!({
+                        "id": oid_name(&e.extn_id),
+                        "critical": e.critical,
+                    })
+                })
+                .collect(),
+        );
+    }
+    output
+}
+
+/// Prints a JSON value as indented text
+pub fn print_text(value: &Value, indent: usize) {
+    let pad = "  ".repeat(indent);
+    match value {
+        Value::Object(map) => print_object(map, indent),
+        Value::Array(items) => {
+            for item in items {
+                match item {
+                    Value::Object(map) => {
+                        println!("{}-", pad);
+                        print_object(map, indent + 1);
+                    }
+                    other => println!("{}- {}", pad, scalar(other)),
+                }
+            }
+        }
+        other => println!("{}{}", pad, scalar(other)),
+    }
+}
+
+fn print_object(map: &Map<String, Value>, indent: usize) {
+    let pad = "  ".repeat(indent);
+    for (key, value) in map {
+        match value {
+            Value::Object(_) | Value::Array(_) => {
+                println!("{}{}:", pad, key);
+                print_text(value, indent + 1);
+            }
+            other => println!("{}{}: {}", pad, key, scalar(other)),
+        }
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn time(t: &Time) -> String {
+    t.to_date_time().to_string()
+}
+
+/// Formats an OID with its name, when known
+fn oid_name(oid: &const_oid::ObjectIdentifier) -> String {
+    match DB.by_oid(oid) {
+        Some(name) => format!("{} ({})", name, oid),
+        None => oid.to_string(),
+    }
+}
+
+fn names(names: &Names<'_>) -> Value {
+    Value::from(names.iter().map(general_name).collect::<Vec<_>>())
+}
+
+/// Formats a general name similarly to OpenSSL
+fn general_name(name: &GeneralNameRef<'_>) -> String {
+    match name {
+        GeneralNameRef::OtherName(n) => format!("othername:{}", oid_name(&n.type_id)),
+        GeneralNameRef::Rfc822Name(n) => format!("email:{}", n.as_str()),
+        GeneralNameRef::DnsName(n) => format!("DNS:{}", n.as_str()),
+        GeneralNameRef::DirectoryName(n) => format!("DirName:{}", n),
+        GeneralNameRef::EdiPartyName(_) => String::from("EdiPartyName"),
+        GeneralNameRef::UniformResourceIdentifier(n) => format!("URI:{}", n.as_str()),
+        GeneralNameRef::IpAddress(n) => format!("IP:{}", ip_address(n.as_bytes())),
+        GeneralNameRef::RegisteredId(n) => format!("RID:{}", oid_name(n)),
+    }
+}
+
+/// Formats an address, or an address and mask as used in name constraints
+fn ip_address(bytes: &[u8]) -> String {
+    let v4 = |b: &[u8]| Ipv4Addr::new(b[0], b[1], b[2], b[3]).to_string();
+    let v6 = |b: &[u8]| {
+        let mut octets = [0u8; 16];
+        octets.copy_from_slice(b);
+        Ipv6Addr::from(octets).to_string()
+    };
+    match bytes.len() {
+        4 => v4(bytes),
+        8 => format!("{}/{}", v4(&bytes[..4]), v4(&bytes[4..])),
+        16 => v6(bytes),
+        32 => format!("{}/{}", v6(&bytes[..16]), v6(&bytes[16..])),
+        _ => hex::encode(bytes),
+    }
+}
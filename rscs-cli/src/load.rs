@@ -0,0 +1,65 @@
+//! Certificate loading
+
+use der::{ErrorKind, Length};
+use rscs::{error::Error, watch::parse_certificates, MemCertificateStore};
+use std::{fs, path::Path};
+use x509_verify::x509_cert::Certificate;
+
+/// Reads every certificate from a PEM bundle or DER file. An empty file is a parse error.
+pub fn certificates(path: &Path) -> Result<Vec<Certificate>, Error> {
+    let data = fs::read(path)?;
+    if data.iter().all(u8::is_ascii_whitespace) {
+        return Err(no_certificate());
+    }
+    parse_certificates(&data)
+}
+
+/// Reads the first certificate from a PEM bundle or DER file. A file without any certificate is
+/// a parse error.
+pub fn certificate(path: &Path) -> Result<Certificate, Error> {
+    certificates(path)?
+        .into_iter()
+        .next()
+        .ok_or_else(no_certificate)
+}
+
+/// Parse error for a file holding no certificate, as if it ended before the first one
+fn no_certificate() -> Error {
+    let kind = ErrorKind::Incomplete {
+        expected_len: Length::ONE,
+        actual_len: Length::ZERO,
+    };
+    Error::Der(kind.at(Length::ZERO))
+}
+
+/// Loads a trust directory or bundle file into a certificate store. Unparsable files in a
+/// directory are reported and skipped.
+pub fn store(path: &Path) -> Result<MemCertificateStore, Error> {
+    let mut store = MemCertificateStore::new();
+    if fs::metadata(path)?.is_dir() {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if !hidden && entry.file_type()?.is_file() {
+                entries.push(entry.path());
+            }
+        }
+        entries.sort();
+        for entry in entries {
+            match certificates(&entry) {
+                Ok(certs) => {
+                    for cert in certs {
+                        store.insert::<Error>(cert)?;
+                    }
+                }
+                Err(e) => eprintln!("warning: skipping {}: {}", entry.display(), e),
+            }
+        }
+    } else {
+        for cert in certificates(path)? {
+            store.insert::<Error>(cert)?;
+        }
+    }
+    Ok(store)
+}
@@ -0,0 +1,363 @@
+#![doc = include_str!("../README.md")]
+#![forbid(unsafe_code)]
+#![warn(
+    clippy::mod_module_files,
+    clippy::unwrap_used,
+    missing_docs,
+    rust_2018_idioms,
+    unused_lifetimes,
+    unused_qualifications
+)]
+
+mod display;
+mod load;
+
+use clap::{Args, Parser, Subcommand};
+use const_oid::{db::rfc5280, ObjectIdentifier};
+use core::time::Duration;
+use der::DateTime;
+use rscs::{error::Error, MemCertificateStore};
+use serde_json::{json, Value};
+use std::{fs, path::PathBuf, process::ExitCode, time::SystemTime};
+use x509_path::TrustAnchor;
+
+/// Inspect certificates and verify certificate chains
+#[derive(Debug, Parser)]
+#[command(name = "rscs", version)]
+struct Cli {
+    /// Print machine-readable JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Verify a certificate against the trusted certificates
+    Verify {
+        #[command(flatten)]
+        target: Target,
+
+        /// Validation time as `YYYY-MM-DDTHH:MM:SSZ` or seconds since the UNIX epoch
+        #[arg(long, value_parser = parse_time)]
+        time: Option<Duration>,
+
+        /// Required purpose: `server`, `client`, `email`, `code`, `timestamp`, `ocsp` or an OID
+        #[arg(long, value_parser = parse_purpose)]
+        purpose: Option<ObjectIdentifier>,
+    },
+
+    /// List the trusted certificates
+    List {
+        /// Trust directory or bundle file
+        #[arg(long)]
+        trust: PathBuf,
+    },
+
+    /// Show a certificate's parsed extensions
+    Show {
+        /// PEM or DER certificate file
+        cert: PathBuf,
+    },
+
+    /// Write the trusted certificates as a PKCS#7 bundle
+    Export {
+        /// Trust directory or bundle file
+        #[arg(long)]
+        trust: PathBuf,
+
+        /// Write PEM instead of DER
+        #[arg(long)]
+        pem: bool,
+
+        /// Output file
+        output: PathBuf,
+    },
+
+    /// Write the trusted certificates as a binary store file
+    Pack {
+        /// Trust directory or bundle file
+        #[arg(long)]
+        trust: PathBuf,
+
+        /// Output file
+        output: PathBuf,
+    },
+
+    /// Print every candidate path from a certificate to the trusted certificates
+    BuildPath {
+        #[command(flatten)]
+        target: Target,
+
+        /// Validation time as `YYYY-MM-DDTHH:MM:SSZ` or seconds since the UNIX epoch
+        #[arg(long, value_parser = parse_time)]
+        time: Option<Duration>,
+    },
+}
+
+/// Certificate to validate and the certificates to validate it with
+#[derive(Debug, Args)]
+struct Target {
+    /// PEM or DER certificate file. Only the first certificate is used
+    cert: PathBuf,
+
+    /// Trust directory or bundle file
+    #[arg(long)]
+    trust: PathBuf,
+
+    /// PEM bundle of untrusted intermediate certificates
+    #[arg(long)]
+    chain: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(output) => {
+            print(&output, cli.json);
+            ExitCode::SUCCESS
+        }
+        Err(Failure { output, error }) => {
+            if cli.json {
+                let mut output = output.unwrap_or_else(|| json!({}));
+                output["error"] = Value::String(error.to_string());
+                print(&output, true);
+            } else {
+                if let Some(output) = output {
+                    print(&output, false);
+                }
+                eprintln!("error: {}", error);
+            }
+            ExitCode::from(exit_code(&error))
+        }
+    }
+}
+
+/// Failed command. May carry output which was produced before the failure.
+struct Failure {
+    output: Option<Value>,
+    error: Box<Error>,
+}
+
+impl From<Error> for Failure {
+    fn from(error: Error) -> Self {
+        Self {
+            output: None,
+            error: Box::new(error),
+        }
+    }
+}
+
+/// Maps an error to the documented exit code
+fn exit_code(error: &Error) -> u8 {
+    match error {
+        Error::Verification => 10,
+        Error::Expired => 11,
+        Error::NotYetValid => 12,
+        Error::InvalidPurpose => 13,
+        Error::Revoked => 14,
+        Error::WeakAlgorithm => 15,
+        Error::Transparency => 16,
+        Error::Constraint => 17,
+        Error::Certificate(_) | Error::Der(_) | Error::Integrity | Error::KeyStore => 20,
+        Error::Io(_) => 30,
+        Error::Unavailable => 31,
+    }
+}
+
+fn run(cli: &Cli) -> Result<Value, Failure> {
+    match &cli.command {
+        Command::Verify {
+            target,
+            time,
+            purpose,
+        } => {
+            let (store, cert, chain) = target.load()?;
+            let chain: Vec<_> = chain.iter().collect();
+            let time = time.unwrap_or_else(now);
+            let path = store.verify_at(&cert, &chain, time)?;
+            let output = display::path(path.certificates().iter());
+            if let Some(purpose) = purpose {
+                path.check_purpose(*purpose).map_err(|error| Failure {
+                    output: Some(output.clone()),
+                    error: Box::new(error),
+                })?;
+            }
+            Ok(output)
+        }
+        Command::List { trust } => {
+            let store = load::store(trust)?;
+            let mut certs: Vec<_> = store.iter().map(|(_, c)| c).collect();
+            certs.sort_by_key(|c| c.tbs_certificate.subject.to_string());
+            Ok(Value::Array(
+                certs.into_iter().map(display::summary).collect(),
+            ))
+        }
+        Command::Show { cert } => {
+            let cert = load::certificate(cert)?;
+            let anchor = TrustAnchor::try_from(&cert).map_err(Error::from)?;
+            Ok(display::details(&cert, &anchor))
+        }
+        Command::Export { trust, pem, output } => {
+            let bundle = load::store(trust)?.to_pkcs7();
+            match pem {
+                true => fs::write(output, bundle.to_pem()?),
+                false => fs::write(output, bundle.to_der()?),
+            }
+            .map_err(Error::from)?;
+            Ok(json!({
+                "output": output.display().to_string(),
+                "certificates": bundle.certificates.len(),
+            }))
+        }
+        Command::Pack { trust, output } => {
+            let store = load::store(trust)?;
+            fs::write(output, store.to_store_file()?).map_err(Error::from)?;
+            Ok(json!({
+                "output": output.display().to_string(),
+                "certificates": store.iter().count(),
+            }))
+        }
+        Command::BuildPath { target, time } => {
+            let (store, cert, chain) = target.load()?;
+            let chain: Vec<_> = chain.iter().collect();
+            let time = time.unwrap_or_else(now);
+            let paths = store.candidate_paths(&cert, &chain)?;
+            Ok(Value::Array(
+                paths
+                    .iter()
+                    .map(|p| {
+                        let mut output = display::path(p.iter().copied());
+                        output["valid"] = match store.verify_path_at(p, time) {
+                            Ok(_) => Value::Bool(true),
+                            Err(e) => {
+                                output["error"] = Value::String(e.to_string());
+                                Value::Bool(false)
+                            }
+                        };
+                        output
+                    })
+                    .collect(),
+            ))
+        }
+    }
+}
+
+impl Target {
+    /// Loads the trusted certificates, the target certificate and the untrusted chain
+    fn load(
+        &self,
+    ) -> Result<
+        (
+            MemCertificateStore,
+            x509_verify::x509_cert::Certificate,
+            Vec<x509_verify::x509_cert::Certificate>,
+        ),
+        Error,
+    > {
+        let store = load::store(&self.trust)?;
+        let cert = load::certificate(&self.cert)?;
+        let chain = match &self.chain {
+            Some(chain) => load::certificates(chain)?,
+            None => Vec::new(),
+        };
+        Ok((store, cert, chain))
+    }
+}
+
+/// Prints the output as either JSON or indented text
+fn print(output: &Value, json: bool) {
+    if json {
+        println!("{}", output);
+    } else {
+        display::print_text(output, 0);
+    }
+}
+
+/// Returns the current system time as the duration since the UNIX epoch
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+}
+
+/// Parses `YYYY-MM-DDTHH:MM:SSZ` or seconds since the UNIX epoch
+fn parse_time(s: &str) -> Result<Duration, String> {
+    if let Ok(secs) = s.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    let invalid = || format!("invalid time `{}`, expected YYYY-MM-DDTHH:MM:SSZ", s);
+    let b = s.as_bytes();
+    if b.len() != 20
+        || b[4] != b'-'
+        || b[7] != b'-'
+        || b[10] != b'T'
+        || b[13] != b':'
+        || b[16] != b':'
+        || b[19] != b'Z'
+    {
+        return Err(invalid());
+    }
+    let field = |r: core::ops::Range<usize>| s[r].parse::<u16>().map_err(|_| invalid());
+    let date = DateTime::new(
+        field(0..4)?,
+        field(5..7)? as u8,
+        field(8..10)? as u8,
+        field(11..13)? as u8,
+        field(14..16)? as u8,
+        field(17..19)? as u8,
+    )
+    .map_err(|_| invalid())?;
+    Ok(date.unix_duration())
+}
+
+/// Parses a purpose name or a dotted OID
+fn parse_purpose(s: &str) -> Result<ObjectIdentifier, String> {
+    Ok(match s {
+        "server" => rfc5280::ID_KP_SERVER_AUTH,
+        "client" => rfc5280::ID_KP_CLIENT_AUTH,
+        "email" => rfc5280::ID_KP_EMAIL_PROTECTION,
+        "code" => rfc5280::ID_KP_CODE_SIGNING,
+        "timestamp" => rfc5280::ID_KP_TIME_STAMPING,
+        "ocsp" => rfc5280::ID_KP_OCSP_SIGNING,
+        oid => ObjectIdentifier::new(oid).map_err(|_| format!("invalid purpose `{}`", oid))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{exit_code, load, parse_purpose, parse_time};
+    use const_oid::db::rfc5280::ID_KP_SERVER_AUTH;
+    use core::time::Duration;
+    use rscs::error::Error;
+    use std::fs;
+
+    #[test]
+    fn time_formats() {
+        let expected = Duration::from_secs(1_748_736_000);
+        assert_eq!(parse_time("2025-06-01T00:00:00Z"), Ok(expected));
+        assert_eq!(parse_time("1748736000"), Ok(expected));
+        assert!(parse_time("2025-06-01").is_err());
+        assert!(parse_time("2025-13-01T00:00:00Z").is_err());
+    }
+
+    #[test]
+    fn purposes() {
+        assert_eq!(parse_purpose("server"), Ok(ID_KP_SERVER_AUTH));
+        assert_eq!(parse_purpose("1.3.6.1.5.5.7.3.1"), Ok(ID_KP_SERVER_AUTH));
+        assert!(parse_purpose("nonsense").is_err());
+    }
+
+    #[test]
+    fn empty_input() {
+        let path = std::env::temp_dir().join(format!("rscs-cli-empty-{}.pem", std::process::id()));
+        fs::write(&path, b"\n").expect("temporary file");
+        let error = load::certificate(&path).err();
+        fs::remove_file(&path).expect("temporary file");
+        assert!(matches!(&error, Some(Error::Der(_))));
+        assert_eq!(error.map(|e| exit_code(&e)), Some(20));
+        assert_eq!(exit_code(&Error::Unavailable), 31);
+    }
+}
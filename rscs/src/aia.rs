@@ -0,0 +1,371 @@
+//! Authority Information Access issuer fetching
+//!
+//! When a peer presents an incomplete chain, the missing intermediates can be downloaded from the
+//! `caIssuers` URIs of the Authority Information Access extension ([RFC 5280 Section 4.2.2.1]).
+//! The transport is left to an [`IssuerFetcher`] supplied by the caller: this crate never opens a
+//! connection on its own, and [`Offline`] refuses every fetch.
+//!
+//! [RFC 5280 Section 4.2.2.1]: https://datatracker.ietf.org/doc/html/rfc5280#section-4.2.2.1
+
+use crate::{
+    cache::Lru,
+    error::Error,
+    loader::Loader,
+    path::VerifiedPath,
+    pkcs7::Pkcs7Bundle,
+    store::{search, verify_signature, CertificateStore, IssuerSource},
+};
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use const_oid::db::rfc5280::ID_AD_CA_ISSUERS;
+use core::time::Duration;
+use der::Decode;
+use x509_verify::x509_cert::{
+    ext::pkix::{name::GeneralName, AuthorityInfoAccessSyntax},
+    name::Name,
+    Certificate,
+};
+
+/// Trait for defining the transport used to download `caIssuers` resources
+pub trait IssuerFetcher {
+    /// Fetches the resource at `uri` and returns the response body. The body is expected to be
+    /// either a DER-encoded certificate or a PKCS#7 bundle of certificates.
+    fn fetch(&self, uri: &str) -> Result<Vec<u8>, Error>;
+}
+
+/// [`IssuerFetcher`] which fails every fetch with [`Error::Unavailable`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Offline;
+
+impl IssuerFetcher for Offline {
+    fn fetch(&self, _uri: &str) -> Result<Vec<u8>, Error> {
+        Err(Error::Unavailable)
+    }
+}
+
+/// Completes certification paths with issuers downloaded from `caIssuers` URIs, see
+/// [`CertificateStore::verify_fetching_at`].
+///
+/// Fetching only happens once neither the certificate store nor the presented chain leads to a
+/// verified path from a certificate. At most `max_fetches` resources are downloaded per verification, every URI is
+/// fetched at most once per verification, and a certificate already in the path is never added
+/// again, so that AIA loops end. Decoded responses are cached by URI, evicting the least recently
+/// used entry once the cache is full. Cached responses do not count towards `max_fetches`.
+#[derive(Clone, Debug)]
+pub struct AiaResolver<F> {
+    fetcher: F,
+    max_fetches: usize,
+    cache: Lru<String, Vec<Certificate>>,
+}
+
+impl<F> AiaResolver<F>
+where
+    F: IssuerFetcher,
+{
+    /// Creates an [`AiaResolver`] downloading through `fetcher` at most `max_fetches` resources
+    /// per verification, and caching at most `cache_capacity` responses.
+    pub fn new(fetcher: F, max_fetches: usize, cache_capacity: usize) -> Self {
+        Self {
+            fetcher,
+            max_fetches,
+            cache: Lru::new(cache_capacity),
+        }
+    }
+
+    /// Returns the underlying fetcher
+    pub fn fetcher(&self) -> &F {
+        &self.fetcher
+    }
+
+    /// Removes every cached response
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Returns the certificates at `uri`, from the cache or fetched unless `fetched` already holds
+    /// `uri` or `max_fetches` URIs. Failed fetches and undecodable responses yield none.
+    fn certificates(&mut self, uri: String, fetched: &mut Vec<String>) -> Vec<Certificate> {
+        if let Some(certificates) = self.cache.get(&uri) {
+            return certificates.clone();
+        }
+        if fetched.len() >= self.max_fetches || fetched.contains(&uri) {
+            return Vec::new();
+        }
+        fetched.push(uri.clone());
+        match self.fetcher.fetch(&uri).and_then(|body| decode(&body)) {
+            Ok(certificates) => {
+                self.cache.insert(uri, certificates.clone());
+                certificates
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl<Id, L> CertificateStore<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Verifies `cert` at `time`, fetching missing issuers through `resolver`. See
+    /// [`CertificateStore::verify_at`] for details.
+    ///
+    /// Failed fetches and undecodable responses are skipped: verification then fails with
+    /// [`Error::Verification`] as if no issuer was found.
+    pub fn verify_fetching_at<F>(
+        &self,
+        resolver: &mut AiaResolver<F>,
+        cert: &Certificate,
+        chain: &[&Certificate],
+        time: Duration,
+    ) -> Result<VerifiedPath<Id>, Error>
+    where
+        F: IssuerFetcher,
+        Id: Clone + for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        let mut source = Fetching {
+            store: self,
+            chain,
+            resolver,
+            fetched: Vec::new(),
+        };
+        search(
+            &mut source,
+            &mut vec![Cow::Borrowed(cert)],
+            time,
+            &mut verify_signature,
+        )
+    }
+}
+
+/// [`IssuerSource`] completing the presented chain with the certificates at `caIssuers` URIs
+struct Fetching<'c, 'r, Id, L, F>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    store: &'c CertificateStore<Id, L>,
+    chain: &'c [&'c Certificate],
+    resolver: &'r mut AiaResolver<F>,
+    /// URIs fetched during this verification
+    fetched: Vec<String>,
+}
+
+impl<'c, 'r, Id, L, F> IssuerSource<'c, Id> for Fetching<'c, 'r, Id, L, F>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+    F: IssuerFetcher,
+{
+    fn anchor(&self, id: &Id) -> Option<&Certificate> {
+        self.store.get(id)
+    }
+
+    fn is_anchor(&self, id: &Id, cert: &Certificate) -> bool {
+        self.store.holds(id, cert)
+    }
+
+    fn trusted_issuer(&self, id: &Id, cert: &Certificate) -> Option<&Certificate> {
+        self.store.trusted_issuer(id, cert)
+    }
+
+    fn chain(&self) -> &'c [&'c Certificate] {
+        self.chain
+    }
+
+    /// Returns the certificates at the `index`-th `caIssuers` URI of `cert`
+    fn fetch_issuers(
+        &mut self,
+        cert: &Certificate,
+        index: usize,
+    ) -> Result<Option<Vec<Cow<'c, Certificate>>>, Error> {
+        Ok(ca_issuers(cert)?.into_iter().nth(index).map(|uri| {
+            self.resolver
+                .certificates(uri, &mut self.fetched)
+                .into_iter()
+                .map(Cow::Owned)
+                .collect()
+        }))
+    }
+}
+
+/// Returns the `caIssuers` URIs of `cert`, in order
+pub fn ca_issuers(cert: &Certificate) -> Result<Vec<String>, Error> {
+    let aia = match cert.tbs_certificate.get::<AuthorityInfoAccessSyntax>()? {
+        Some((_, aia)) => aia,
+        None => return Ok(Vec::new()),
+    };
+    Ok(aia
+        .0
+        .iter()
+        .filter(|ad| ad.access_method == ID_AD_CA_ISSUERS)
+        .filter_map(|ad| match &ad.access_location {
+            GeneralName::UniformResourceIdentifier(uri) => Some(uri.to_string()),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Decodes a `caIssuers` response, which is either a DER-encoded certificate or a PKCS#7 bundle
+fn decode(body: &[u8]) -> Result<Vec<Certificate>, Error> {
+    match Certificate::from_der(body) {
+        Ok(cert) => Ok(vec![cert]),
+        Err(_) => Ok(Pkcs7Bundle::decode(body)?.certificates),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ca_issuers, AiaResolver, IssuerFetcher, Offline};
+    use crate::{error::Error, MemCertificateStore};
+    use alloc::{string::String, vec::Vec};
+    use core::{cell::RefCell, time::Duration};
+    use der::{DecodePem, Encode};
+    use hashbrown::HashMap;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf-aia.pem");
+    const CHAIN: &[u8] = include_bytes!("../testdata/chain.p7b");
+    const BRIDGE_CHAIN: &[u8] = include_bytes!("../testdata/bridge-chain.pem");
+    const PARTNER_LEAF: &[u8] = include_bytes!("../testdata/partner-leaf.pem");
+    const ROLLOVER_CHAIN: &[u8] = include_bytes!("../testdata/rollover-chain.pem");
+    const LEAF_ROLLOVER: &[u8] = include_bytes!("../testdata/leaf-rollover.pem");
+
+    const P7C_URI: &str = "http://pki.example.com/intermediate.p7c";
+    const DER_URI: &str = "http://pki.example.com/intermediate.der";
+
+    /// 2025-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+    /// Fetcher answering from memory and recording every requested URI
+    #[derive(Default)]
+    struct MemFetcher {
+        responses: HashMap<&'static str, Vec<u8>>,
+        requests: RefCell<Vec<String>>,
+    }
+
+    impl IssuerFetcher for MemFetcher {
+        fn fetch(&self, uri: &str) -> Result<Vec<u8>, Error> {
+            self.requests.borrow_mut().push(uri.into());
+            self.responses.get(uri).cloned().ok_or(Error::Unavailable)
+        }
+    }
+
+    fn store() -> MemCertificateStore {
+        let mut store = MemCertificateStore::new();
+        store
+            .insert::<Error>(Certificate::from_pem(ROOT).unwrap())
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn ca_issuer_uris() {
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        assert_eq!(ca_issuers(&leaf).unwrap(), [P7C_URI, DER_URI]);
+        let root = Certificate::from_pem(ROOT).unwrap();
+        assert!(ca_issuers(&root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn fetch_pkcs7() {
+        let store = store();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let mut fetcher = MemFetcher::default();
+        fetcher.responses.insert(P7C_URI, CHAIN.to_vec());
+        let mut resolver = AiaResolver::new(fetcher, 4, 8);
+
+        let path = store
+            .verify_fetching_at(&mut resolver, &leaf, &[], NOW)
+            .unwrap();
+        assert_eq!(path.certificates().len(), 3);
+        assert_eq!(
+            path.certificates()[1],
+            Certificate::from_pem(INTERMEDIATE).unwrap()
+        );
+        // The second verification is answered from the cache
+        assert!(store
+            .verify_fetching_at(&mut resolver, &leaf, &[], NOW)
+            .is_ok());
+        assert_eq!(*resolver.fetcher().requests.borrow(), [P7C_URI]);
+    }
+
+    #[test]
+    fn fetch_der() {
+        let store = store();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let mut fetcher = MemFetcher::default();
+        fetcher
+            .responses
+            .insert(DER_URI, intermediate.to_der().unwrap());
+        let mut resolver = AiaResolver::new(fetcher, 4, 8);
+        assert!(store
+            .verify_fetching_at(&mut resolver, &leaf, &[], NOW)
+            .is_ok());
+        assert_eq!(*resolver.fetcher().requests.borrow(), [P7C_URI, DER_URI]);
+
+        // The presented chain is preferred over fetching
+        resolver.clear();
+        assert!(store
+            .verify_fetching_at(&mut resolver, &leaf, &[&intermediate], NOW)
+            .is_ok());
+        assert_eq!(resolver.fetcher().requests.borrow().len(), 2);
+    }
+
+    #[test]
+    fn bounded_fetches() {
+        let store = store();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let mut fetcher = MemFetcher::default();
+        fetcher.responses.insert(
+            DER_URI,
+            Certificate::from_pem(INTERMEDIATE)
+                .unwrap()
+                .to_der()
+                .unwrap(),
+        );
+        let mut resolver = AiaResolver::new(fetcher, 1, 8);
+        assert!(matches!(
+            store.verify_fetching_at(&mut resolver, &leaf, &[], NOW),
+            Err(Error::Verification)
+        ));
+        assert_eq!(*resolver.fetcher().requests.borrow(), [P7C_URI]);
+
+        let mut resolver = AiaResolver::new(Offline, 4, 8);
+        assert!(matches!(
+            store.verify_fetching_at(&mut resolver, &leaf, &[], NOW),
+            Err(Error::Verification)
+        ));
+    }
+
+    #[test]
+    fn same_paths_as_store() {
+        // The first bridge certificate is a dead end, and the rollover chain starts with a
+        // self-signed certificate which must not be used as an intermediate
+        let store = store();
+        let mut resolver = AiaResolver::new(Offline, 4, 8);
+        for (leaf, chain) in [
+            (PARTNER_LEAF, BRIDGE_CHAIN),
+            (LEAF_ROLLOVER, ROLLOVER_CHAIN),
+        ] {
+            let leaf = Certificate::from_pem(leaf).unwrap();
+            let chain = Certificate::load_pem_chain(chain).unwrap();
+            let chain: Vec<&Certificate> = chain.iter().collect();
+            assert_eq!(
+                store
+                    .verify_fetching_at(&mut resolver, &leaf, &chain, NOW)
+                    .unwrap()
+                    .certificates(),
+                store.verify_at(&leaf, &chain, NOW).unwrap().certificates()
+            );
+        }
+    }
+}
@@ -0,0 +1,363 @@
+//! Signature algorithm and key strength policy
+//!
+//! Every algorithm enabled in `x509-verify` is accepted by [`CertificateStore::verify_at`]. An
+//! [`AlgorithmPolicy`] narrows this down to a list of acceptable signature algorithms, elliptic
+//! curves and minimum key sizes. Certificates rejected by the policy fail with
+//! [`Error::WeakAlgorithm`].
+
+use crate::{
+    error::Error,
+    loader::Loader,
+    path::VerifiedPath,
+    store::{verify_signature, CertificateStore},
+};
+use alloc::vec::Vec;
+use const_oid::{
+    db::{
+        rfc5912::{
+            DSA_WITH_SHA_1, DSA_WITH_SHA_224, DSA_WITH_SHA_256, ECDSA_WITH_SHA_224,
+            ECDSA_WITH_SHA_256, ECDSA_WITH_SHA_384, ECDSA_WITH_SHA_512, ID_DSA, ID_EC_PUBLIC_KEY,
+            ID_RSASSA_PSS, RSA_ENCRYPTION, SECP_256_R_1, SECP_384_R_1, SECP_521_R_1,
+            SHA_1_WITH_RSA_ENCRYPTION, SHA_224_WITH_RSA_ENCRYPTION, SHA_256_WITH_RSA_ENCRYPTION,
+            SHA_384_WITH_RSA_ENCRYPTION, SHA_512_WITH_RSA_ENCRYPTION,
+        },
+        rfc8410::{ID_ED_25519, ID_ED_448},
+    },
+    ObjectIdentifier,
+};
+use core::time::Duration;
+use der::{asn1::UintRef, Decode, Sequence};
+use x509_verify::{
+    spki::SubjectPublicKeyInfoOwned,
+    x509_cert::{name::Name, Certificate},
+};
+
+/// `ecdsa-with-SHA1`, which is missing from the OID database
+const ECDSA_WITH_SHA_1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.1");
+
+/// Signature algorithms using SHA-1
+const SHA_1_SIGNATURES: [ObjectIdentifier; 3] =
+    [SHA_1_WITH_RSA_ENCRYPTION, DSA_WITH_SHA_1, ECDSA_WITH_SHA_1];
+
+/// Acceptable signature algorithms and public keys.
+///
+/// The default policy accepts RSA, DSA and ECDSA signatures over SHA-1 and SHA-2 as well as
+/// Ed25519 signatures, RSA and DSA keys of at least 2048 bits, and the P-256, P-384 and P-521
+/// curves. MD2 and MD5 signatures are rejected even with the `legacy_digests` feature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlgorithmPolicy {
+    signature_algorithms: Vec<ObjectIdentifier>,
+    curves: Vec<ObjectIdentifier>,
+    min_rsa_bits: usize,
+    min_dsa_bits: usize,
+    sha1_until: Option<Duration>,
+}
+
+impl Default for AlgorithmPolicy {
+    fn default() -> Self {
+        Self {
+            signature_algorithms: [
+                SHA_1_WITH_RSA_ENCRYPTION,
+                SHA_224_WITH_RSA_ENCRYPTION,
+                SHA_256_WITH_RSA_ENCRYPTION,
+                SHA_384_WITH_RSA_ENCRYPTION,
+                SHA_512_WITH_RSA_ENCRYPTION,
+                DSA_WITH_SHA_1,
+                DSA_WITH_SHA_224,
+                DSA_WITH_SHA_256,
+                ECDSA_WITH_SHA_1,
+                ECDSA_WITH_SHA_224,
+                ECDSA_WITH_SHA_256,
+                ECDSA_WITH_SHA_384,
+                ECDSA_WITH_SHA_512,
+                ID_ED_25519,
+            ]
+            .to_vec(),
+            curves: [SECP_256_R_1, SECP_384_R_1, SECP_521_R_1].to_vec(),
+            min_rsa_bits: 2048,
+            min_dsa_bits: 2048,
+            sha1_until: None,
+        }
+    }
+}
+
+impl AlgorithmPolicy {
+    /// Creates the default [`AlgorithmPolicy`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the acceptable signature algorithms, replacing the defaults
+    pub fn signature_algorithms(mut self, algorithms: &[ObjectIdentifier]) -> Self {
+        self.signature_algorithms = algorithms.to_vec();
+        self
+    }
+
+    /// Sets the acceptable named curves of elliptic curve keys, replacing the defaults
+    pub fn curves(mut self, curves: &[ObjectIdentifier]) -> Self {
+        self.curves = curves.to_vec();
+        self
+    }
+
+    /// Sets the minimum RSA modulus size, in bits
+    pub fn min_rsa_bits(mut self, bits: usize) -> Self {
+        self.min_rsa_bits = bits;
+        self
+    }
+
+    /// Sets the minimum DSA prime size, in bits
+    pub fn min_dsa_bits(mut self, bits: usize) -> Self {
+        self.min_dsa_bits = bits;
+        self
+    }
+
+    /// Rejects SHA-1 signatures when verifying at or after `time`, given as the duration since
+    /// the UNIX epoch. SHA-1 signatures must still be listed in the acceptable algorithms to be
+    /// accepted before `time`.
+    pub fn sha1_until(mut self, time: Duration) -> Self {
+        self.sha1_until = Some(time);
+        self
+    }
+
+    /// Checks the signature algorithm of `cert` when verifying at `time`
+    pub fn check_signature(&self, cert: &Certificate, time: Duration) -> Result<(), Error> {
+        let algorithm = cert.signature_algorithm.oid;
+        if !self.signature_algorithms.contains(&algorithm) {
+            return Err(Error::WeakAlgorithm);
+        }
+        match self.sha1_until {
+            Some(until) if time >= until && SHA_1_SIGNATURES.contains(&algorithm) => {
+                Err(Error::WeakAlgorithm)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the algorithm and the size of `key`. Ed25519 and Ed448 keys are always accepted,
+    /// and keys of any other algorithm than RSA, DSA or elliptic curves are rejected.
+    pub fn check_key(&self, key: &SubjectPublicKeyInfoOwned) -> Result<(), Error> {
+        let accepted = match key.algorithm.oid {
+            RSA_ENCRYPTION | ID_RSASSA_PSS => {
+                let key = RsaPublicKeyRef::from_der(key.subject_public_key.raw_bytes())?;
+                bit_len(key.modulus.as_bytes()) >= self.min_rsa_bits
+            }
+            ID_DSA => match &key.algorithm.parameters {
+                Some(parameters) => {
+                    let parameters = parameters.decode_as::<DssParmsRef<'_>>()?;
+                    bit_len(parameters.p.as_bytes()) >= self.min_dsa_bits
+                }
+                None => false,
+            },
+            ID_EC_PUBLIC_KEY => match &key.algorithm.parameters {
+                Some(parameters) => self
+                    .curves
+                    .contains(&parameters.decode_as::<ObjectIdentifier>()?),
+                None => false,
+            },
+            ID_ED_25519 | ID_ED_448 => true,
+            _ => false,
+        };
+        match accepted {
+            true => Ok(()),
+            false => Err(Error::WeakAlgorithm),
+        }
+    }
+
+    /// Checks the signature of `cert` and the key of its `issuer`
+    fn check_issued(
+        &self,
+        issuer: &Certificate,
+        cert: &Certificate,
+        time: Duration,
+    ) -> Result<(), Error> {
+        self.check_signature(cert, time)?;
+        self.check_key(&issuer.tbs_certificate.subject_public_key_info)
+    }
+}
+
+impl<Id> VerifiedPath<Id> {
+    /// Checks the path against `policy` at `time`: the signature algorithm of every certificate
+    /// but the trust anchor, and the key of every certificate, are checked. The self-signature
+    /// of the trust anchor is never relied upon, so its algorithm does not matter.
+    pub fn check_algorithms(&self, policy: &AlgorithmPolicy, time: Duration) -> Result<(), Error> {
+        policy.check_key(&self.leaf().tbs_certificate.subject_public_key_info)?;
+        for pair in self.certificates().windows(2) {
+            policy.check_issued(&pair[1], &pair[0], time)?;
+        }
+        Ok(())
+    }
+}
+
+impl<Id, L> CertificateStore<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Verifies `cert` at `time` like [`CertificateStore::verify_at`], only accepting the
+    /// signatures and keys allowed by `policy`. Issuers in `chain` which are rejected by the
+    /// policy are skipped, so that a path with acceptable algorithms is preferred.
+    ///
+    /// Fails with [`Error::WeakAlgorithm`] when the key of `cert` is rejected, or when no path
+    /// could be found because of the policy.
+    pub fn verify_with_policy_at(
+        &self,
+        policy: &AlgorithmPolicy,
+        cert: &Certificate,
+        chain: &[&Certificate],
+        time: Duration,
+    ) -> Result<VerifiedPath<Id>, Error>
+    where
+        Id: Clone + for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        policy.check_key(&cert.tbs_certificate.subject_public_key_info)?;
+        let mut rejected = false;
+        let result = self.verify_with(cert, chain, time, &mut |issuer, cert| {
+            if policy.check_issued(issuer, cert, time).is_err() {
+                rejected = true;
+                return Err(Error::WeakAlgorithm);
+            }
+            verify_signature(issuer, cert)
+        });
+        match result {
+            Err(Error::Verification) if rejected => Err(Error::WeakAlgorithm),
+            result => result,
+        }
+    }
+}
+
+/// Returns the size in bits of a big-endian unsigned integer without leading zeros
+fn bit_len(bytes: &[u8]) -> usize {
+    match bytes.first() {
+        Some(first) => bytes.len() * 8 - first.leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+/// ```text
+/// RSAPublicKey ::= SEQUENCE {
+///     modulus           INTEGER,  -- n
+///     publicExponent    INTEGER   -- e
+/// }
+/// ```
+#[derive(Sequence)]
+struct RsaPublicKeyRef<'a> {
+    modulus: UintRef<'a>,
+    #[allow(dead_code)]
+    public_exponent: UintRef<'a>,
+}
+
+/// ```text
+/// Dss-Parms ::= SEQUENCE {
+///     p  INTEGER,
+///     q  INTEGER,
+///     g  INTEGER  }
+/// ```
+#[derive(Sequence)]
+#[allow(dead_code)]
+struct DssParmsRef<'a> {
+    p: UintRef<'a>,
+    q: UintRef<'a>,
+    g: UintRef<'a>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AlgorithmPolicy;
+    use crate::{error::Error, MemCertificateStore};
+    use const_oid::db::{
+        rfc5912::{SHA_1_WITH_RSA_ENCRYPTION, SHA_256_WITH_RSA_ENCRYPTION},
+        rfc8410::ID_ED_25519,
+    };
+    use core::time::Duration;
+    use der::DecodePem;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf.pem");
+    const LEAF_RSA: &[u8] = include_bytes!("../testdata/leaf-rsa1024.pem");
+
+    /// 2025-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+    fn store() -> MemCertificateStore {
+        let mut store = MemCertificateStore::new();
+        store
+            .insert::<Error>(Certificate::from_pem(ROOT).unwrap())
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn signature_algorithms() {
+        let store = store();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let policy = AlgorithmPolicy::new();
+        let path = store
+            .verify_with_policy_at(&policy, &leaf, &[&intermediate], NOW)
+            .unwrap();
+        assert!(path.check_algorithms(&policy, NOW).is_ok());
+
+        let policy = AlgorithmPolicy::new().signature_algorithms(&[SHA_256_WITH_RSA_ENCRYPTION]);
+        assert!(matches!(
+            store.verify_with_policy_at(&policy, &leaf, &[&intermediate], NOW),
+            Err(Error::WeakAlgorithm)
+        ));
+        assert!(matches!(
+            path.check_algorithms(&policy, NOW),
+            Err(Error::WeakAlgorithm)
+        ));
+        // Unrelated failures are not reported as weak algorithms
+        assert!(matches!(
+            store.verify_with_policy_at(&AlgorithmPolicy::new(), &leaf, &[], NOW),
+            Err(Error::Verification)
+        ));
+    }
+
+    #[test]
+    fn sha1_cutoff() {
+        let mut cert = Certificate::from_pem(LEAF).unwrap();
+        cert.signature_algorithm.oid = SHA_1_WITH_RSA_ENCRYPTION;
+        let policy = AlgorithmPolicy::new().sha1_until(NOW);
+        let before = NOW - Duration::from_secs(1);
+        assert!(policy.check_signature(&cert, before).is_ok());
+        assert!(matches!(
+            policy.check_signature(&cert, NOW),
+            Err(Error::WeakAlgorithm)
+        ));
+        let policy = AlgorithmPolicy::new().signature_algorithms(&[ID_ED_25519]);
+        assert!(policy.check_signature(&cert, before).is_err());
+    }
+
+    #[test]
+    fn key_strength() {
+        let store = store();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF_RSA).unwrap();
+        let key = &leaf.tbs_certificate.subject_public_key_info;
+        assert!(matches!(
+            AlgorithmPolicy::new().check_key(key),
+            Err(Error::WeakAlgorithm)
+        ));
+        assert!(AlgorithmPolicy::new()
+            .min_rsa_bits(1024)
+            .check_key(key)
+            .is_ok());
+        assert!(AlgorithmPolicy::new()
+            .min_rsa_bits(1025)
+            .check_key(key)
+            .is_err());
+
+        assert!(store.verify_at(&leaf, &[&intermediate], NOW).is_ok());
+        assert!(matches!(
+            store.verify_with_policy_at(&AlgorithmPolicy::new(), &leaf, &[&intermediate], NOW),
+            Err(Error::WeakAlgorithm)
+        ));
+        let policy = AlgorithmPolicy::new().min_rsa_bits(1024);
+        assert!(store
+            .verify_with_policy_at(&policy, &leaf, &[&intermediate], NOW)
+            .is_ok());
+    }
+}
@@ -0,0 +1,271 @@
+//! Asynchronous certificate loading
+//!
+//! [`AsyncLoader`] is the counterpart of [`Loader`] for backends which
+//! can not hand out references into their own storage, such as a key-value database or an HTTP
+//! certificate repository. Certificates are returned owned, and lookups are awaited during
+//! [`AsyncLoader::verify_at`]. No particular async runtime is required.
+
+use crate::{
+    error::Error,
+    loader::Loader,
+    path::VerifiedPath,
+    store::{search, verify_signature, CertificateStore, IssuerSource},
+};
+use alloc::{borrow::Cow, boxed::Box, vec, vec::Vec};
+use core::{future::Future, pin::Pin, time::Duration};
+use x509_verify::x509_cert::{name::Name, Certificate};
+
+/// Boxed future returned by [`AsyncLoader`]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Trait for defining an asynchronous certificate loader. Unlike [`Loader`], the identifier is
+/// an associated type, since it can not be inferred from the arguments of
+/// [`AsyncLoader::verify_at`].
+pub trait AsyncLoader: Sync {
+    /// Unique identifier of a certificate
+    type Id: Eq + Send + Sync;
+
+    /// Retrieves a certificate. Resolves to `None` if no certificate was found, or to an error
+    /// if the backend could not be queried.
+    fn get<'a>(&'a self, id: &'a Self::Id) -> BoxFuture<'a, Result<Option<Certificate>, Error>>;
+
+    /// Verifies `cert` up its own issuance chain at `time`, given as the duration since the UNIX
+    /// epoch. This is the asynchronous variant of [`CertificateStore::verify_at`], which
+    /// describes the verification in detail.
+    ///
+    /// The trust anchors for the subject and issuer names of `cert` and of every certificate in
+    /// `chain` are looked up first, once per name, before the path is searched. Errors returned
+    /// by [`AsyncLoader::get`] are returned as is.
+    fn verify_at<'a>(
+        &'a self,
+        cert: &'a Certificate,
+        chain: &'a [&'a Certificate],
+        time: Duration,
+    ) -> BoxFuture<'a, Result<VerifiedPath<Self::Id>, Error>>
+    where
+        Self::Id: Clone + for<'b> TryFrom<&'b Name>,
+        Error: for<'b> From<<Self::Id as TryFrom<&'b Name>>::Error>,
+    {
+        Box::pin(async move {
+            let mut anchors = Prefetched {
+                anchors: Vec::new(),
+                chain,
+            };
+            for cert in core::iter::once(cert).chain(chain.iter().copied()) {
+                for name in [&cert.tbs_certificate.subject, &cert.tbs_certificate.issuer] {
+                    let id = <Self::Id as TryFrom<&Name>>::try_from(name)?;
+                    if anchors.anchors.iter().all(|(i, _)| *i != id) {
+                        let anchor = self.get(&id).await?;
+                        anchors.anchors.push((id, anchor));
+                    }
+                }
+            }
+            search(
+                &mut anchors,
+                &mut vec![Cow::Borrowed(cert)],
+                time,
+                &mut verify_signature,
+            )
+        })
+    }
+}
+
+/// [`IssuerSource`] of the trust anchors looked up before a search
+struct Prefetched<'c, Id> {
+    anchors: Vec<(Id, Option<Certificate>)>,
+    chain: &'c [&'c Certificate],
+}
+
+impl<'c, Id> IssuerSource<'c, Id> for Prefetched<'c, Id>
+where
+    Id: Eq,
+{
+    fn anchor(&self, id: &Id) -> Option<&Certificate> {
+        self.anchors
+            .iter()
+            .find(|(i, _)| i == id)
+            .and_then(|(_, anchor)| anchor.as_ref())
+    }
+
+    fn chain(&self) -> &'c [&'c Certificate] {
+        self.chain
+    }
+}
+
+impl<Id, L> AsyncLoader for CertificateStore<Id, L>
+where
+    L: Loader<Id> + Sync,
+    Id: Eq + Send + Sync + for<'a> TryFrom<&'a Certificate>,
+{
+    type Id = Id;
+
+    fn get<'a>(&'a self, id: &'a Id) -> BoxFuture<'a, Result<Option<Certificate>, Error>> {
+        Box::pin(core::future::ready(Ok(
+            CertificateStore::get(self, id).cloned()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsyncLoader, BoxFuture};
+    use crate::{error::Error, name::NameBytes, MemCertificateStore};
+    use alloc::{boxed::Box, sync::Arc, task::Wake, vec::Vec};
+    use core::{
+        future::Future,
+        pin::Pin,
+        sync::atomic::{AtomicUsize, Ordering},
+        task::{Context, Poll, Waker},
+        time::Duration,
+    };
+    use der::DecodePem;
+    use hashbrown::HashMap;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf.pem");
+    const BRIDGE_CHAIN: &[u8] = include_bytes!("../testdata/bridge-chain.pem");
+    const PARTNER_LEAF: &[u8] = include_bytes!("../testdata/partner-leaf.pem");
+    const ROLLOVER_CHAIN: &[u8] = include_bytes!("../testdata/rollover-chain.pem");
+    const LEAF_ROLLOVER: &[u8] = include_bytes!("../testdata/leaf-rollover.pem");
+
+    /// 2025-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+    /// Backend which answers every lookup after yielding once, like a remote call would
+    #[derive(Default)]
+    struct MockBackend {
+        certificates: HashMap<NameBytes, Certificate>,
+        lookups: AtomicUsize,
+        offline: bool,
+    }
+
+    impl AsyncLoader for MockBackend {
+        type Id = NameBytes;
+
+        fn get<'a>(
+            &'a self,
+            id: &'a NameBytes,
+        ) -> BoxFuture<'a, Result<Option<Certificate>, Error>> {
+            Box::pin(async move {
+                YieldOnce(false).await;
+                self.lookups.fetch_add(1, Ordering::Relaxed);
+                match self.offline {
+                    true => Err(Error::Unavailable),
+                    false => Ok(self.certificates.get(id).cloned()),
+                }
+            })
+        }
+    }
+
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn verify_mock_backend() {
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let mut backend = MockBackend::default();
+        backend
+            .certificates
+            .insert(NameBytes::try_from(&root).unwrap(), root.clone());
+
+        let path = block_on(backend.verify_at(&leaf, &[&intermediate], NOW)).unwrap();
+        assert_eq!(path.certificates().len(), 3);
+        assert_eq!(path.anchor(), &root);
+        // The leaf's issuer is the intermediate's subject, which is only looked up once
+        assert_eq!(backend.lookups.load(Ordering::Relaxed), 3);
+
+        assert!(matches!(
+            block_on(backend.verify_at(&leaf, &[], NOW)),
+            Err(Error::Verification)
+        ));
+        backend.offline = true;
+        assert!(matches!(
+            block_on(backend.verify_at(&leaf, &[&intermediate], NOW)),
+            Err(Error::Unavailable)
+        ));
+    }
+
+    #[test]
+    fn verify_store() {
+        let mut store = MemCertificateStore::new();
+        store
+            .insert::<Error>(Certificate::from_pem(ROOT).unwrap())
+            .unwrap();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let path = block_on(<MemCertificateStore as AsyncLoader>::verify_at(
+            &store,
+            &leaf,
+            &[&intermediate],
+            NOW,
+        ))
+        .unwrap();
+        assert_eq!(
+            path.certificates(),
+            store
+                .verify_at(&leaf, &[&intermediate], NOW)
+                .unwrap()
+                .certificates()
+        );
+    }
+
+    #[test]
+    fn same_paths_as_store() {
+        // The first bridge certificate is a dead end, and the rollover chain starts with a
+        // self-signed certificate which must not be used as an intermediate
+        let mut store = MemCertificateStore::new();
+        store
+            .insert::<Error>(Certificate::from_pem(ROOT).unwrap())
+            .unwrap();
+        for (leaf, chain) in [
+            (PARTNER_LEAF, BRIDGE_CHAIN),
+            (LEAF_ROLLOVER, ROLLOVER_CHAIN),
+        ] {
+            let leaf = Certificate::from_pem(leaf).unwrap();
+            let chain = Certificate::load_pem_chain(chain).unwrap();
+            let chain: Vec<&Certificate> = chain.iter().collect();
+            let path = block_on(<MemCertificateStore as AsyncLoader>::verify_at(
+                &store, &leaf, &chain, NOW,
+            ))
+            .unwrap();
+            assert_eq!(
+                path.certificates(),
+                store.verify_at(&leaf, &chain, NOW).unwrap().certificates()
+            );
+        }
+    }
+}
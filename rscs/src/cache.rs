@@ -0,0 +1,323 @@
+//! Verification cache
+//!
+//! Caches successful certification paths and individual signature results so that repeated
+//! verification of the same chains does not repeat the expensive signature checks.
+
+use crate::{
+    error::Error,
+    loader::Loader,
+    path::VerifiedPath,
+    store::{verify_signature, CertificateStore},
+};
+use alloc::collections::BTreeMap;
+use core::{hash::Hash, time::Duration};
+use der::Encode;
+use hashbrown::HashMap;
+use sha2::{Digest, Sha256};
+use x509_verify::x509_cert::{name::Name, Certificate};
+
+/// SHA-256 digest used as a cache key
+type CacheKey = [u8; 32];
+
+/// Hit and miss counters of a [`VerifyCache`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of verifications answered from the path cache
+    pub path_hits: u64,
+
+    /// Number of verifications which had to build the path
+    pub path_misses: u64,
+
+    /// Number of signature checks answered from the signature cache
+    pub signature_hits: u64,
+
+    /// Number of signature checks which had to be computed
+    pub signature_misses: u64,
+}
+
+/// Bounded cache in front of [`CertificateStore::verify_at`].
+///
+/// Successful paths are keyed by the hash of the target certificate and the presented chain. A
+/// cached path is returned until the verification time leaves its effective validity window or
+/// the certificate store is modified. Paths are bound to the store instance which verified them,
+/// so a cache shared between several stores, or between a store and its clones, never returns a
+/// path anchored in another store. Signature results are keyed by the hash of the issuer's
+/// public key and the signed data, and are shared across all chains.
+///
+/// Both caches evict the least recently used entry once they are full.
+#[derive(Clone, Debug)]
+pub struct VerifyCache<Id> {
+    paths: Lru<CacheKey, ((usize, u64), VerifiedPath<Id>)>,
+    signatures: Lru<CacheKey, bool>,
+    stats: CacheStats,
+}
+
+impl<Id> VerifyCache<Id>
+where
+    Id: Clone,
+{
+    /// Creates an empty [`VerifyCache`] holding at most `path_capacity` paths and
+    /// `signature_capacity` signature results.
+    pub fn new(path_capacity: usize, signature_capacity: usize) -> Self {
+        Self {
+            paths: Lru::new(path_capacity),
+            signatures: Lru::new(signature_capacity),
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Verifies `cert` against `store` at `time`, consulting the cache first. See
+    /// [`CertificateStore::verify_at`] for details.
+    pub fn verify_at<L>(
+        &mut self,
+        store: &CertificateStore<Id, L>,
+        cert: &Certificate,
+        chain: &[&Certificate],
+        time: Duration,
+    ) -> Result<VerifiedPath<Id>, Error>
+    where
+        L: Loader<Id>,
+        Id: Eq + for<'a> TryFrom<&'a Certificate> + for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        let key = path_key(cert, chain)?;
+        let state = (store.instance(), store.generation());
+        if let Some((cached, path)) = self.paths.get(&key) {
+            let validity = path.validity();
+            if *cached == state
+                && time >= validity.not_before.to_unix_duration()
+                && time <= validity.not_after.to_unix_duration()
+            {
+                self.stats.path_hits += 1;
+                return Ok(path.clone());
+            }
+            self.paths.remove(&key);
+        }
+        self.stats.path_misses += 1;
+
+        let signatures = &mut self.signatures;
+        let stats = &mut self.stats;
+        let path = store.verify_with(cert, chain, time, &mut |issuer, cert| {
+            let key = signature_key(issuer, cert)?;
+            let valid = match signatures.get(&key) {
+                Some(valid) => {
+                    stats.signature_hits += 1;
+                    *valid
+                }
+                None => {
+                    stats.signature_misses += 1;
+                    let valid = verify_signature(issuer, cert).is_ok();
+                    signatures.insert(key, valid);
+                    valid
+                }
+            };
+            if valid {
+                Ok(())
+            } else {
+                Err(Error::Verification)
+            }
+        })?;
+        self.paths.insert(key, (state, path.clone()));
+        Ok(path)
+    }
+
+    /// Returns the hit and miss counters of the cache.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Removes every entry from the cache. The counters are left untouched.
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.signatures.clear();
+    }
+}
+
+/// Hashes the DER of the target certificate followed by the presented chain
+fn path_key(cert: &Certificate, chain: &[&Certificate]) -> Result<CacheKey, Error> {
+    let mut hasher = Sha256::new();
+    update_prefixed(&mut hasher, &cert.to_der()?);
+    for c in chain {
+        update_prefixed(&mut hasher, &c.to_der()?);
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// Hashes the issuer's public key and everything covered by, and including, the signature
+fn signature_key(issuer: &Certificate, cert: &Certificate) -> Result<CacheKey, Error> {
+    let mut hasher = Sha256::new();
+    update_prefixed(
+        &mut hasher,
+        &issuer.tbs_certificate.subject_public_key_info.to_der()?,
+    );
+    update_prefixed(&mut hasher, &cert.tbs_certificate.to_der()?);
+    update_prefixed(&mut hasher, &cert.signature_algorithm.to_der()?);
+    update_prefixed(&mut hasher, cert.signature.raw_bytes());
+    Ok(hasher.finalize().into())
+}
+
+/// Feeds the length of `data` followed by `data` into the hasher
+fn update_prefixed(hasher: &mut Sha256, data: &[u8]) {
+    hasher.update((data.len() as u64).to_be_bytes());
+    hasher.update(data);
+}
+
+/// Least recently used map with a fixed capacity
+#[derive(Clone, Debug)]
+pub(crate) struct Lru<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (u64, V)>,
+    order: BTreeMap<u64, K>,
+}
+
+impl<K, V> Lru<K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    pub(crate) fn get(&mut self, key: &K) -> Option<&V> {
+        let tick = self.next_tick();
+        let (used, _) = self.entries.get_mut(key)?;
+        self.order.remove(used);
+        self.order.insert(tick, key.clone());
+        *used = tick;
+        self.entries.get(key).map(|(_, v)| v)
+    }
+
+    pub(crate) fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.remove(&key);
+        if self.entries.len() >= self.capacity {
+            if let Some(oldest) = self.order.keys().next().copied() {
+                if let Some(key) = self.order.remove(&oldest) {
+                    self.entries.remove(&key);
+                }
+            }
+        }
+        let tick = self.next_tick();
+        self.order.insert(tick, key.clone());
+        self.entries.insert(key, (tick, value));
+    }
+
+    pub(crate) fn remove(&mut self, key: &K) {
+        if let Some((used, _)) = self.entries.remove(key) {
+            self.order.remove(&used);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lru, VerifyCache};
+    use crate::{error::Error, name::NameBytes, MemCertificateStore};
+    use core::time::Duration;
+    use der::DecodePem;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf.pem");
+
+    /// 2025-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let mut lru = Lru::new(2);
+        lru.insert(1, 'a');
+        lru.insert(2, 'b');
+        assert_eq!(lru.get(&1), Some(&'a'));
+        lru.insert(3, 'c');
+        assert_eq!(lru.get(&2), None);
+        assert_eq!(lru.get(&1), Some(&'a'));
+        assert_eq!(lru.get(&3), Some(&'c'));
+    }
+
+    #[test]
+    fn cache_hits_and_invalidation() {
+        let mut store = MemCertificateStore::new();
+        store
+            .insert::<Error>(Certificate::from_pem(ROOT).unwrap())
+            .unwrap();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let mut cache = VerifyCache::<NameBytes>::new(16, 16);
+
+        cache
+            .verify_at(&store, &leaf, &[&intermediate], NOW)
+            .unwrap();
+        cache
+            .verify_at(&store, &leaf, &[&intermediate], NOW)
+            .unwrap();
+        let stats = cache.stats();
+        assert_eq!(stats.path_hits, 1);
+        assert_eq!(stats.path_misses, 1);
+        assert_eq!(stats.signature_misses, 2);
+
+        // A store mutation invalidates the path, but not the signatures
+        store.insert::<Error>(intermediate.clone()).unwrap();
+        cache
+            .verify_at(&store, &leaf, &[&intermediate], NOW)
+            .unwrap();
+        let stats = cache.stats();
+        assert_eq!(stats.path_misses, 2);
+        assert_eq!(stats.signature_hits, 1);
+    }
+
+    #[test]
+    fn cache_bound_to_store() {
+        let mut store = MemCertificateStore::new();
+        store
+            .insert::<Error>(Certificate::from_pem(ROOT).unwrap())
+            .unwrap();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let mut cache = VerifyCache::<NameBytes>::new(16, 16);
+        cache
+            .verify_at(&store, &leaf, &[&intermediate], NOW)
+            .unwrap();
+
+        // Another store at the same generation does not see the cached path
+        let mut other = MemCertificateStore::new();
+        other.insert::<Error>(intermediate.clone()).unwrap();
+        assert_eq!(other.generation(), store.generation());
+        let path = cache
+            .verify_at(&other, &leaf, &[&intermediate], NOW)
+            .unwrap();
+        assert_eq!(path.certificates().len(), 2);
+
+        // Neither does a clone which has since diverged
+        let mut clone = store.clone();
+        assert_ne!(clone.instance(), store.instance());
+        let root = clone.iter().next().unwrap().0.clone();
+        clone.remove(&root);
+        clone.insert::<Error>(leaf.clone()).unwrap();
+        assert_eq!(clone.generation(), store.generation() + 2);
+        let path = cache
+            .verify_at(&clone, &leaf, &[&intermediate], NOW)
+            .unwrap();
+        assert_eq!(path.certificates().len(), 1);
+        assert_eq!(cache.stats().path_hits, 0);
+    }
+}
@@ -0,0 +1,542 @@
+//! CRL distribution point fetching ([RFC 5280 Section 6.3])
+//!
+//! Certificate revocation lists are requested through a [`CrlFetcher`] supplied by the caller,
+//! for each distribution point of a certificate, and cached by distribution point until their
+//! `nextUpdate`. The `issuingDistributionPoint` of every CRL is matched against the distribution
+//! point it was fetched from, so that a partitioned CRL is only used for the certificates it
+//! covers. Delta and indirect CRLs are not supported, and CRLs with a critical extension other
+//! than `issuingDistributionPoint`, `cRLNumber` or `authorityKeyIdentifier` are not used.
+//!
+//! [RFC 5280 Section 6.3]: https://datatracker.ietf.org/doc/html/rfc5280#section-6.3
+
+use crate::{cache::Lru, error::Error};
+use alloc::vec::Vec;
+use const_oid::{
+    db::rfc5280::{
+        ID_CE_AUTHORITY_KEY_IDENTIFIER, ID_CE_CRL_DISTRIBUTION_POINTS, ID_CE_CRL_NUMBER,
+        ID_CE_ISSUING_DISTRIBUTION_POINT,
+    },
+    ObjectIdentifier,
+};
+use core::time::Duration;
+use der::{referenced::OwnedToRef, Decode, Encode, Sequence};
+use x509_path::name::{DistributionPointNameRef, GeneralNameRef, GeneralNameRefs};
+use x509_verify::{
+    x509_cert::{
+        crl::CertificateList,
+        ext::{
+            pkix::{
+                crl::dp::{ReasonFlags, Reasons},
+                BasicConstraints, KeyUsage,
+            },
+            Extension,
+        },
+        name::Name,
+        Certificate,
+    },
+    Message, Signature, VerifyInfo, VerifyingKey,
+};
+
+/// CRL extensions which are processed, and may therefore be critical
+const KNOWN_CRL_EXTENSIONS: [ObjectIdentifier; 3] = [
+    ID_CE_AUTHORITY_KEY_IDENTIFIER,
+    ID_CE_CRL_NUMBER,
+    ID_CE_ISSUING_DISTRIBUTION_POINT,
+];
+
+/// Trait for defining the transport used to download CRLs
+pub trait CrlFetcher {
+    /// Fetches the CRL published at the distribution point `name`, typically a
+    /// `uniformResourceIdentifier`, and returns its DER encoding.
+    fn fetch(&self, name: &GeneralNameRef<'_>) -> Result<Vec<u8>, Error>;
+}
+
+/// Checks the revocation status of certificates against the CRLs of their distribution points.
+///
+/// CRLs are cached by distribution point name, evicting the least recently used entry once the
+/// cache is full. A cached CRL is dropped, and fetched again, once `nextUpdate` has passed. CRLs
+/// without a `nextUpdate` are never cached.
+#[derive(Clone, Debug)]
+pub struct CrlResolver<F> {
+    fetcher: F,
+    cache: Lru<Vec<u8>, CertificateList>,
+}
+
+impl<F> CrlResolver<F>
+where
+    F: CrlFetcher,
+{
+    /// Creates a [`CrlResolver`] downloading through `fetcher` and caching at most
+    /// `cache_capacity` CRLs.
+    pub fn new(fetcher: F, cache_capacity: usize) -> Self {
+        Self {
+            fetcher,
+            cache: Lru::new(cache_capacity),
+        }
+    }
+
+    /// Returns the underlying fetcher
+    pub fn fetcher(&self) -> &F {
+        &self.fetcher
+    }
+
+    /// Removes every cached CRL
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Checks that `cert`, which was issued by `issuer`, is not revoked at `time`, given as the
+    /// duration since the UNIX epoch.
+    ///
+    /// Distribution points are processed in order until the CRLs found cover every revocation
+    /// reason. For each distribution point, its names are tried in order until a CRL is found
+    /// which is signed by `issuer`, is current at `time` and whose `issuingDistributionPoint`
+    /// matches the distribution point. Failed fetches and unusable CRLs are skipped.
+    ///
+    /// `Error::Revoked` is returned when `cert` is listed on any of the CRLs, and
+    /// `Error::Verification` when its status can not be determined, such as when `cert` has no
+    /// distribution points.
+    pub fn check(
+        &mut self,
+        cert: &Certificate,
+        issuer: &Certificate,
+        time: Duration,
+    ) -> Result<(), Error> {
+        let is_ca = match cert.tbs_certificate.get::<BasicConstraints>()? {
+            Some((_, bc)) => bc.ca,
+            None => false,
+        };
+        let all = ReasonFlags::full() - Reasons::Unused;
+        let mut covered = ReasonFlags::default();
+        for dp in decode_distribution_points(cert)? {
+            let reasons = dp.reasons.map_or(all, |r| r & all);
+            if (reasons - covered).is_empty() {
+                continue;
+            }
+            // Indirect CRLs are not supported, so the CRL issuer must be the certificate issuer
+            let issuer_name = GeneralNameRef::DirectoryName(cert.tbs_certificate.issuer.clone());
+            if matches!(&dp.crl_issuer, Some(names) if !names.contains(&issuer_name)) {
+                continue;
+            }
+            let names = match &dp.distribution_point {
+                Some(name) => full_names(name, &cert.tbs_certificate.issuer),
+                None => continue,
+            };
+            for name in &names {
+                let crl = match self.crl(name, issuer, time) {
+                    Some(crl) => crl,
+                    None => continue,
+                };
+                let only_some_reasons = match scope(&crl, &names, is_ca)? {
+                    Some(only_some_reasons) => only_some_reasons,
+                    None => continue,
+                };
+                let serial = &cert.tbs_certificate.serial_number;
+                if crl
+                    .tbs_cert_list
+                    .revoked_certificates
+                    .iter()
+                    .flatten()
+                    .any(|r| &r.serial_number == serial)
+                {
+                    return Err(Error::Revoked);
+                }
+                covered |= reasons & only_some_reasons.map_or(all, |r| r & all);
+                break;
+            }
+            if covered == all {
+                return Ok(());
+            }
+        }
+        Err(Error::Verification)
+    }
+
+    /// Returns the CRL published at `name` from the cache or the fetcher. Returns `None` if it
+    /// can not be retrieved, is not signed by `issuer` or is not current at `time`.
+    fn crl(
+        &mut self,
+        name: &GeneralNameRef<'_>,
+        issuer: &Certificate,
+        time: Duration,
+    ) -> Option<CertificateList> {
+        let key = name.to_der().ok()?;
+        if let Some(crl) = self.cache.get(&key) {
+            if check_crl(crl, issuer, time).is_ok() {
+                return Some(crl.clone());
+            }
+            self.cache.remove(&key);
+        }
+        let crl = self
+            .fetcher
+            .fetch(name)
+            .and_then(|der| Ok(CertificateList::from_der(&der)?))
+            .ok()?;
+        check_crl(&crl, issuer, time).ok()?;
+        if crl.tbs_cert_list.next_update.is_some() {
+            self.cache.insert(key, crl.clone());
+        }
+        Some(crl)
+    }
+}
+
+/// Returns the names of every distribution point of `cert`. Names relative to the CRL issuer are
+/// resolved to full directory names.
+pub fn distribution_points(cert: &Certificate) -> Result<Vec<GeneralNameRefs<'_>>, Error> {
+    Ok(decode_distribution_points(cert)?
+        .iter()
+        .filter_map(|dp| dp.distribution_point.as_ref())
+        .map(|name| full_names(name, &cert.tbs_certificate.issuer))
+        .collect())
+}
+
+/// Checks that `crl` was signed by `issuer` and is current at `time`
+fn check_crl(crl: &CertificateList, issuer: &Certificate, time: Duration) -> Result<(), Error> {
+    let tbs = &crl.tbs_cert_list;
+    if tbs.issuer != issuer.tbs_certificate.subject {
+        return Err(Error::Verification);
+    }
+    if let Some((_, usage)) = issuer.tbs_certificate.get::<KeyUsage>()? {
+        if !usage.crl_sign() {
+            return Err(Error::Verification);
+        }
+    }
+    // Such as deltaCRLIndicator, as delta CRLs are not supported
+    if tbs
+        .crl_extensions
+        .iter()
+        .flatten()
+        .any(|e| e.critical && !KNOWN_CRL_EXTENSIONS.contains(&e.extn_id))
+    {
+        return Err(Error::Verification);
+    }
+    if tbs.this_update.to_unix_duration() > time {
+        return Err(Error::NotYetValid);
+    }
+    if let Some(next_update) = tbs.next_update {
+        if next_update.to_unix_duration() < time {
+            return Err(Error::Expired);
+        }
+    }
+    let key = VerifyingKey::try_from(
+        issuer
+            .tbs_certificate
+            .subject_public_key_info
+            .owned_to_ref(),
+    )
+    .map_err(|_| Error::Verification)?;
+    key.verify(VerifyInfo::new(
+        Message::from(tbs.to_der()?),
+        Signature::new(&crl.signature_algorithm, crl.signature.raw_bytes()),
+    ))
+    .map_err(|_| Error::Verification)
+}
+
+/// Matches the `issuingDistributionPoint` of `crl` against a distribution point of a certificate,
+/// as described in RFC 5280 Section 6.3.3 (b). `names` are the full names of the distribution
+/// point. Returns the `onlySomeReasons` of the CRL if it covers
+/// the certificate, `None` otherwise.
+fn scope(
+    crl: &CertificateList,
+    names: &[GeneralNameRef<'_>],
+    is_ca: bool,
+) -> Result<Option<Option<ReasonFlags>>, Error> {
+    let extension = crl
+        .tbs_cert_list
+        .crl_extensions
+        .iter()
+        .flatten()
+        .find(|e| e.extn_id == ID_CE_ISSUING_DISTRIBUTION_POINT);
+    let idp = match extension {
+        Some(Extension { extn_value, .. }) => {
+            IssuingDistributionPointRef::from_der(extn_value.as_bytes())?
+        }
+        None => return Ok(Some(None)),
+    };
+    if idp.indirect_crl
+        || idp.only_contains_attribute_certs
+        || (idp.only_contains_user_certs && is_ca)
+        || (idp.only_contains_ca_certs && !is_ca)
+    {
+        return Ok(None);
+    }
+    if let Some(name) = &idp.distribution_point {
+        let idp_names = full_names(name, &crl.tbs_cert_list.issuer);
+        if !idp_names.iter().any(|n| names.contains(n)) {
+            return Ok(None);
+        }
+    }
+    Ok(Some(idp.only_some_reasons))
+}
+
+/// Decodes the CRL distribution points extension of `cert`, borrowing from its encoding
+fn decode_distribution_points(cert: &Certificate) -> Result<Vec<DistributionPointRef<'_>>, Error> {
+    let extension = cert
+        .tbs_certificate
+        .extensions
+        .iter()
+        .flatten()
+        .find(|e| e.extn_id == ID_CE_CRL_DISTRIBUTION_POINTS);
+    match extension {
+        Some(e) => Ok(Vec::<DistributionPointRef<'_>>::from_der(
+            e.extn_value.as_bytes(),
+        )?),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Resolves a distribution point name to its full names. A name relative to the CRL issuer is
+/// appended to `crl_issuer`, as described in RFC 5280 Section 4.2.1.13.
+fn full_names<'a>(name: &DistributionPointNameRef<'a>, crl_issuer: &Name) -> GeneralNameRefs<'a> {
+    match name {
+        DistributionPointNameRef::FullName(names) => names.clone(),
+        DistributionPointNameRef::NameRelativeToCRLIssuer(rdn) => {
+            let mut name = crl_issuer.clone();
+            name.0.push(rdn.clone());
+            alloc::vec![GeneralNameRef::DirectoryName(name)]
+        }
+    }
+}
+
+/// ```text
+/// DistributionPoint ::= SEQUENCE {
+///      distributionPoint       [0]     DistributionPointName OPTIONAL,
+///      reasons                 [1]     ReasonFlags OPTIONAL,
+///      cRLIssuer               [2]     GeneralNames OPTIONAL }
+/// ```
+#[derive(Sequence)]
+struct DistributionPointRef<'a> {
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+    distribution_point: Option<DistributionPointNameRef<'a>>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    reasons: Option<ReasonFlags>,
+    #[asn1(context_specific = "2", tag_mode = "IMPLICIT", optional = "true")]
+    crl_issuer: Option<GeneralNameRefs<'a>>,
+}
+
+/// ```text
+/// IssuingDistributionPoint ::= SEQUENCE {
+///      distributionPoint          [0] DistributionPointName OPTIONAL,
+///      onlyContainsUserCerts      [1] BOOLEAN DEFAULT FALSE,
+///      onlyContainsCACerts        [2] BOOLEAN DEFAULT FALSE,
+///      onlySomeReasons            [3] ReasonFlags OPTIONAL,
+///      indirectCRL                [4] BOOLEAN DEFAULT FALSE,
+///      onlyContainsAttributeCerts [5] BOOLEAN DEFAULT FALSE }
+/// ```
+#[derive(Sequence)]
+struct IssuingDistributionPointRef<'a> {
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+    distribution_point: Option<DistributionPointNameRef<'a>>,
+    #[asn1(
+        context_specific = "1",
+        tag_mode = "IMPLICIT",
+        default = "Default::default"
+    )]
+    only_contains_user_certs: bool,
+    #[asn1(
+        context_specific = "2",
+        tag_mode = "IMPLICIT",
+        default = "Default::default"
+    )]
+    only_contains_ca_certs: bool,
+    #[asn1(context_specific = "3", tag_mode = "IMPLICIT", optional = "true")]
+    only_some_reasons: Option<ReasonFlags>,
+    #[asn1(
+        context_specific = "4",
+        tag_mode = "IMPLICIT",
+        default = "Default::default"
+    )]
+    indirect_crl: bool,
+    #[asn1(
+        context_specific = "5",
+        tag_mode = "IMPLICIT",
+        default = "Default::default"
+    )]
+    only_contains_attribute_certs: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{distribution_points, CrlFetcher, CrlResolver};
+    use crate::error::Error;
+    use alloc::{
+        string::{String, ToString},
+        vec::Vec,
+    };
+    use core::{cell::RefCell, time::Duration};
+    use der::DecodePem;
+    use hashbrown::HashMap;
+    use x509_path::name::GeneralNameRef;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+    const LEAF_AIA: &[u8] = include_bytes!("../testdata/leaf-aia.pem");
+    const LEAF_RDN: &[u8] = include_bytes!("../testdata/leaf-rdn.pem");
+    const CRL: &[u8] = include_bytes!("../testdata/crl.der");
+    const CRL_URI: &[u8] = include_bytes!("../testdata/crl-uri.der");
+    const CRL_CA_ONLY: &[u8] = include_bytes!("../testdata/crl-ca-only.der");
+    const CRL_PARTITION: &[u8] = include_bytes!("../testdata/crl-partition.der");
+    const CRL_DELTA: &[u8] = include_bytes!("../testdata/crl-delta.der");
+    const INTERMEDIATE_NO_CRL_SIGN: &[u8] =
+        include_bytes!("../testdata/intermediate-nocrlsign.pem");
+
+    const URI: &str = "http://pki.example.com/intermediate.crl";
+    const PARTITION: &str = "CN=Partition 1,CN=RSCS Test Intermediate,O=RSCS Test,C=US";
+
+    /// 2027-01-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_798_761_600);
+
+    /// Fetcher answering from memory, keyed by URI or by RFC 4514 directory name, and recording
+    /// every request
+    #[derive(Default)]
+    struct MemFetcher {
+        responses: HashMap<&'static str, &'static [u8]>,
+        requests: RefCell<Vec<String>>,
+    }
+
+    impl MemFetcher {
+        fn with(key: &'static str, crl: &'static [u8]) -> Self {
+            let mut fetcher = Self::default();
+            fetcher.responses.insert(key, crl);
+            fetcher
+        }
+    }
+
+    impl CrlFetcher for MemFetcher {
+        fn fetch(&self, name: &GeneralNameRef<'_>) -> Result<Vec<u8>, Error> {
+            let key = match name {
+                GeneralNameRef::UniformResourceIdentifier(uri) => uri.to_string(),
+                GeneralNameRef::DirectoryName(name) => name.to_string(),
+                _ => return Err(Error::Unavailable),
+            };
+            self.requests.borrow_mut().push(key.clone());
+            self.responses
+                .get(key.as_str())
+                .map(|crl| crl.to_vec())
+                .ok_or(Error::Unavailable)
+        }
+    }
+
+    fn certs(leaf: &[u8]) -> (Certificate, Certificate) {
+        (
+            Certificate::from_pem(leaf).unwrap(),
+            Certificate::from_pem(INTERMEDIATE).unwrap(),
+        )
+    }
+
+    #[test]
+    fn distribution_point_names() {
+        let (leaf, _) = certs(LEAF_AIA);
+        let dps = distribution_points(&leaf).unwrap();
+        assert_eq!(dps.len(), 1);
+        assert!(matches!(
+            &dps[0][..],
+            [GeneralNameRef::UniformResourceIdentifier(uri)] if uri.as_str() == URI
+        ));
+
+        let (leaf, _) = certs(LEAF_RDN);
+        let dps = distribution_points(&leaf).unwrap();
+        assert!(matches!(
+            &dps[0][..],
+            [GeneralNameRef::DirectoryName(name)] if name.to_string() == PARTITION
+        ));
+
+        let root = Certificate::from_pem(ROOT).unwrap();
+        assert!(distribution_points(&root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn full_name_idp() {
+        let (leaf, intermediate) = certs(LEAF_AIA);
+        let mut resolver = CrlResolver::new(MemFetcher::with(URI, CRL_URI), 4);
+        assert!(matches!(
+            resolver.check(&leaf, &intermediate, NOW),
+            Err(Error::Revoked)
+        ));
+        // The second check is answered from the cache
+        assert!(matches!(
+            resolver.check(&leaf, &intermediate, NOW),
+            Err(Error::Revoked)
+        ));
+        assert_eq!(resolver.fetcher().requests.borrow().len(), 1);
+
+        // A CRL without an issuing distribution point covers every certificate of its issuer
+        let mut resolver = CrlResolver::new(MemFetcher::with(URI, CRL), 4);
+        assert!(resolver.check(&leaf, &intermediate, NOW).is_ok());
+    }
+
+    #[test]
+    fn idp_mismatch() {
+        let (leaf, intermediate) = certs(LEAF_AIA);
+        for crl in [CRL_CA_ONLY, CRL_PARTITION] {
+            let mut resolver = CrlResolver::new(MemFetcher::with(URI, crl), 4);
+            assert!(matches!(
+                resolver.check(&leaf, &intermediate, NOW),
+                Err(Error::Verification)
+            ));
+        }
+    }
+
+    #[test]
+    fn relative_name() {
+        let (leaf, intermediate) = certs(LEAF_RDN);
+        let mut resolver = CrlResolver::new(MemFetcher::with(PARTITION, CRL_PARTITION), 4);
+        assert!(resolver.check(&leaf, &intermediate, NOW).is_ok());
+        assert_eq!(*resolver.fetcher().requests.borrow(), [PARTITION]);
+
+        let mut resolver = CrlResolver::new(MemFetcher::with(PARTITION, CRL_URI), 4);
+        assert!(matches!(
+            resolver.check(&leaf, &intermediate, NOW),
+            Err(Error::Verification)
+        ));
+    }
+
+    #[test]
+    fn stale_and_foreign_crls() {
+        let (leaf, intermediate) = certs(LEAF_AIA);
+        let mut resolver = CrlResolver::new(MemFetcher::with(URI, CRL), 4);
+        assert!(resolver.check(&leaf, &intermediate, NOW).is_ok());
+        // Past nextUpdate, the cached CRL is dropped and the fetched one is just as stale
+        let later = Duration::from_secs(2_114_380_800);
+        assert!(matches!(
+            resolver.check(&leaf, &intermediate, later),
+            Err(Error::Verification)
+        ));
+        assert_eq!(resolver.fetcher().requests.borrow().len(), 2);
+
+        // Before thisUpdate
+        let earlier = Duration::from_secs(1_748_736_000);
+        assert!(resolver.check(&leaf, &intermediate, earlier).is_err());
+
+        // Not signed by the issuer
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let mut resolver = CrlResolver::new(MemFetcher::with(URI, CRL), 4);
+        assert!(matches!(
+            resolver.check(&leaf, &root, NOW),
+            Err(Error::Verification)
+        ));
+    }
+
+    #[test]
+    fn unusable_crls() {
+        // A delta CRL, marked by a critical deltaCRLIndicator
+        let (leaf, intermediate) = certs(LEAF_AIA);
+        let mut resolver = CrlResolver::new(MemFetcher::with(URI, CRL_DELTA), 4);
+        assert!(matches!(
+            resolver.check(&leaf, &intermediate, NOW),
+            Err(Error::Verification)
+        ));
+
+        // Signed with the issuer's key, whose key usage does not include cRLSign
+        let issuer = Certificate::from_pem(INTERMEDIATE_NO_CRL_SIGN).unwrap();
+        assert_eq!(
+            issuer.tbs_certificate.subject_public_key_info,
+            intermediate.tbs_certificate.subject_public_key_info
+        );
+        let mut resolver = CrlResolver::new(MemFetcher::with(URI, CRL), 4);
+        assert!(matches!(
+            resolver.check(&leaf, &issuer, NOW),
+            Err(Error::Verification)
+        ));
+    }
+}
@@ -0,0 +1,719 @@
+//! Certificate Transparency ([RFC 6962])
+//!
+//! Signed certificate timestamps (SCTs) are promises by a log to publish a certificate. They are
+//! delivered embedded in the certificate, in a TLS extension or in a stapled OCSP response, and
+//! are checked against a [`LogList`] of known logs. Whether the valid SCTs are sufficient is left
+//! to a [`CtPolicy`], such as [`DistinctOperators`].
+//!
+//! With the `serde` feature, a [`LogList`] deserializes from the JSON log lists published by
+//! browser vendors (version 3 of the schema). Pending and rejected logs are left out.
+//!
+//! [RFC 6962]: https://datatracker.ietf.org/doc/html/rfc6962
+
+use crate::{error::Error, ocsp};
+use alloc::{string::String, vec, vec::Vec};
+use const_oid::{
+    db::{
+        rfc5912::{ECDSA_WITH_SHA_256, SHA_256_WITH_RSA_ENCRYPTION},
+        rfc8410::ID_ED_25519,
+    },
+    ObjectIdentifier,
+};
+use core::time::Duration;
+use der::{asn1::OctetStringRef, referenced::OwnedToRef, Decode, Encode};
+use sha2::{Digest, Sha256};
+use x509_verify::{
+    spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned},
+    x509_cert::Certificate,
+    Message, Signature, VerifyInfo, VerifyingKey,
+};
+
+/// Precertificate SCT list extension
+pub const ID_CT_PRECERT_SCTS: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.11129.2.4.2");
+
+/// OCSP SCT list extension
+pub const ID_CT_OCSP_SCTS: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.11129.2.4.5");
+
+/// Where an SCT was delivered, which determines the data signed by the log
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SctSource {
+    /// Embedded in the certificate, signed over the precertificate
+    Embedded,
+
+    /// Delivered in the `signed_certificate_timestamp` TLS extension, signed over the certificate
+    TlsExtension,
+
+    /// Delivered in a stapled OCSP response, signed over the certificate
+    Ocsp,
+}
+
+/// Version 1 signed certificate timestamp
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedCertificateTimestamp {
+    /// SHA-256 hash of the log's public key
+    pub log_id: [u8; 32],
+
+    /// Time of the SCT in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+
+    /// Opaque SCT extensions
+    pub extensions: Vec<u8>,
+
+    /// TLS `HashAlgorithm` of the signature
+    pub hash_algorithm: u8,
+
+    /// TLS `SignatureAlgorithm` of the signature
+    pub signature_algorithm: u8,
+
+    /// Signature of the log
+    pub signature: Vec<u8>,
+
+    /// Where the SCT was delivered
+    pub source: SctSource,
+}
+
+impl SignedCertificateTimestamp {
+    /// Decodes a `SignedCertificateTimestampList`, as found in the TLS extension. SCTs of unknown
+    /// versions are skipped.
+    pub fn decode_list(list: &[u8], source: SctSource) -> Result<Vec<Self>, Error> {
+        let mut reader = TlsReader(list);
+        let mut entries = TlsReader(reader.vec16()?);
+        reader.finish()?;
+        let mut scts = Vec::new();
+        while !entries.0.is_empty() {
+            if let Some(sct) = Self::decode(entries.vec16()?, source)? {
+                scts.push(sct);
+            }
+        }
+        Ok(scts)
+    }
+
+    /// Returns the SCTs embedded in `cert`, if any
+    pub fn from_certificate(cert: &Certificate) -> Result<Vec<Self>, Error> {
+        match cert
+            .tbs_certificate
+            .extensions
+            .iter()
+            .flatten()
+            .find(|e| e.extn_id == ID_CT_PRECERT_SCTS)
+        {
+            Some(e) => Self::decode_extension(e.extn_value.as_bytes(), SctSource::Embedded),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns the SCTs for `cert`, which was issued by `issuer`, in the DER-encoded OCSP
+    /// `response`, if any. The response itself is not verified: SCTs are signed over the
+    /// certificate, so a forged response can not forge them.
+    pub fn from_ocsp_response(
+        response: &[u8],
+        cert: &Certificate,
+        issuer: &Certificate,
+    ) -> Result<Vec<Self>, Error> {
+        match ocsp::single_extension(response, cert, issuer, ID_CT_OCSP_SCTS)? {
+            Some(value) => Self::decode_extension(&value, SctSource::Ocsp),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Verifies the SCT for `cert`, which was issued by `issuer`, against `log` at `time`, given
+    /// as the duration since the UNIX epoch.
+    ///
+    /// A log with a temporal interval only accepts certificates whose `notAfter` falls within the
+    /// interval. `Error::NotYetValid` is returned when the SCT is newer than `time`. Any other
+    /// failure, including a log whose identifier is not the hash of its key, returns
+    /// `Error::Verification`.
+    pub fn verify(
+        &self,
+        log: &Log,
+        cert: &Certificate,
+        issuer: &Certificate,
+        time: Duration,
+    ) -> Result<(), Error> {
+        if self.log_id != log.id || log.id[..] != Sha256::digest(&log.key)[..] {
+            return Err(Error::Verification);
+        }
+        if Duration::from_millis(self.timestamp) > time {
+            return Err(Error::NotYetValid);
+        }
+        let not_after = cert.tbs_certificate.validity.not_after.to_unix_duration();
+        if log.start.map_or(false, |start| not_after < start)
+            || log.end.map_or(false, |end| not_after >= end)
+        {
+            return Err(Error::Verification);
+        }
+        let key = SubjectPublicKeyInfoOwned::from_der(&log.key)?;
+        let key = VerifyingKey::try_from(key.owned_to_ref()).map_err(|_| Error::Verification)?;
+        let algorithm = self.algorithm()?;
+        key.verify(VerifyInfo::new(
+            Message::from(self.signed_data(cert, issuer)?),
+            Signature::new(&algorithm, &self.signature),
+        ))
+        .map_err(|_| Error::Verification)
+    }
+
+    /// Returns the data signed by the log: the certificate for SCTs delivered separately, and
+    /// the precertificate for embedded SCTs.
+    ///
+    /// ```text
+    /// digitally-signed struct {
+    ///     Version sct_version;
+    ///     SignatureType signature_type = certificate_timestamp;
+    ///     uint64 timestamp;
+    ///     LogEntryType entry_type;
+    ///     select(entry_type) {
+    ///         case x509_entry: ASN.1Cert;
+    ///         case precert_entry: PreCert;
+    ///     } signed_entry;
+    ///     CtExtensions extensions;
+    /// };
+    /// ```
+    pub fn signed_data(&self, cert: &Certificate, issuer: &Certificate) -> Result<Vec<u8>, Error> {
+        let mut data = vec![0, 0];
+        data.extend_from_slice(&self.timestamp.to_be_bytes());
+        match self.source {
+            SctSource::Embedded => {
+                data.extend_from_slice(&[0, 1]);
+                let issuer_key = issuer.tbs_certificate.subject_public_key_info.to_der()?;
+                data.extend_from_slice(&Sha256::digest(issuer_key));
+                push_vec24(&mut data, &precertificate_tbs(cert)?)?;
+            }
+            SctSource::TlsExtension | SctSource::Ocsp => {
+                data.extend_from_slice(&[0, 0]);
+                push_vec24(&mut data, &cert.to_der()?)?;
+            }
+        }
+        let len = u16::try_from(self.extensions.len()).map_err(|_| Error::Transparency)?;
+        data.extend_from_slice(&len.to_be_bytes());
+        data.extend_from_slice(&self.extensions);
+        Ok(data)
+    }
+
+    /// Decodes a single SCT, returning `None` for unknown versions
+    fn decode(sct: &[u8], source: SctSource) -> Result<Option<Self>, Error> {
+        let mut reader = TlsReader(sct);
+        if reader.u8()? != 0 {
+            return Ok(None);
+        }
+        let mut log_id = [0u8; 32];
+        log_id.copy_from_slice(reader.bytes(32)?);
+        let mut timestamp = [0u8; 8];
+        timestamp.copy_from_slice(reader.bytes(8)?);
+        let sct = Self {
+            log_id,
+            timestamp: u64::from_be_bytes(timestamp),
+            extensions: reader.vec16()?.to_vec(),
+            hash_algorithm: reader.u8()?,
+            signature_algorithm: reader.u8()?,
+            signature: reader.vec16()?.to_vec(),
+            source,
+        };
+        reader.finish()?;
+        Ok(Some(sct))
+    }
+
+    /// Decodes the value of an SCT list extension, an OCTET STRING wrapping the list
+    fn decode_extension(value: &[u8], source: SctSource) -> Result<Vec<Self>, Error> {
+        Self::decode_list(OctetStringRef::from_der(value)?.as_bytes(), source)
+    }
+
+    /// Maps the TLS algorithms to the signature algorithm. Logs sign with ECDSA or RSA over
+    /// SHA-256 ([RFC 6962 Section 2.1.4]) or with Ed25519 ([RFC 9162 Section 2.1.4]).
+    ///
+    /// [RFC 6962 Section 2.1.4]: https://datatracker.ietf.org/doc/html/rfc6962#section-2.1.4
+    /// [RFC 9162 Section 2.1.4]: https://datatracker.ietf.org/doc/html/rfc9162#section-2.1.4
+    fn algorithm(&self) -> Result<AlgorithmIdentifierOwned, Error> {
+        let oid = match (self.hash_algorithm, self.signature_algorithm) {
+            (4, 1) => SHA_256_WITH_RSA_ENCRYPTION,
+            (4, 3) => ECDSA_WITH_SHA_256,
+            (8, 7) => ID_ED_25519,
+            _ => return Err(Error::Verification),
+        };
+        Ok(AlgorithmIdentifierOwned {
+            oid,
+            parameters: None,
+        })
+    }
+}
+
+/// Returns the DER-encoded precertificate `TBSCertificate` of `cert`, which is its own
+/// `TBSCertificate` without the SCT list extension.
+pub fn precertificate_tbs(cert: &Certificate) -> Result<Vec<u8>, Error> {
+    let mut tbs = cert.tbs_certificate.clone();
+    if let Some(extensions) = &mut tbs.extensions {
+        extensions.retain(|e| e.extn_id != ID_CT_PRECERT_SCTS);
+        if extensions.is_empty() {
+            tbs.extensions = None;
+        }
+    }
+    Ok(tbs.to_der()?)
+}
+
+/// Certificate Transparency log
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Log {
+    /// SHA-256 hash of the log's public key
+    pub id: [u8; 32],
+
+    /// Name of the log operator
+    pub operator: String,
+
+    /// Description of the log
+    pub description: String,
+
+    /// DER-encoded public key of the log
+    pub key: Vec<u8>,
+
+    /// Start of the temporal interval, as the duration since the UNIX epoch. The log only
+    /// accepts certificates which expire at or after the start.
+    pub start: Option<Duration>,
+
+    /// End of the temporal interval, as the duration since the UNIX epoch. The log only accepts
+    /// certificates which expire before the end.
+    pub end: Option<Duration>,
+}
+
+impl Log {
+    /// Creates a [`Log`] without temporal interval from its DER-encoded public `key`
+    pub fn new(operator: impl Into<String>, description: impl Into<String>, key: Vec<u8>) -> Self {
+        Self {
+            id: Sha256::digest(&key).into(),
+            operator: operator.into(),
+            description: description.into(),
+            key,
+            start: None,
+            end: None,
+        }
+    }
+}
+
+/// List of known Certificate Transparency logs
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogList {
+    logs: Vec<Log>,
+}
+
+impl LogList {
+    /// Creates an empty [`LogList`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `log` to the list, replacing any log with the same identifier
+    pub fn insert(&mut self, log: Log) {
+        self.logs.retain(|l| l.id != log.id);
+        self.logs.push(log);
+    }
+
+    /// Retrieves a log by its identifier
+    pub fn get(&self, id: &[u8; 32]) -> Option<&Log> {
+        self.logs.iter().find(|l| &l.id == id)
+    }
+
+    /// Returns an iterator over the logs
+    pub fn iter(&self) -> impl Iterator<Item = &Log> {
+        self.logs.iter()
+    }
+
+    /// Verifies `scts` for `cert`, which was issued by `issuer`, at `time`, given as the duration
+    /// since the UNIX epoch. Returns the distinct logs of the valid SCTs. SCTs from unknown logs
+    /// and invalid SCTs are ignored.
+    pub fn verify(
+        &self,
+        scts: &[SignedCertificateTimestamp],
+        cert: &Certificate,
+        issuer: &Certificate,
+        time: Duration,
+    ) -> Vec<&Log> {
+        let mut logs: Vec<&Log> = Vec::new();
+        for sct in scts {
+            if let Some(log) = self.get(&sct.log_id) {
+                if !logs.contains(&log) && sct.verify(log, cert, issuer, time).is_ok() {
+                    logs.push(log);
+                }
+            }
+        }
+        logs
+    }
+
+    /// Verifies `scts` like [`LogList::verify`], then checks the logs of the valid SCTs against
+    /// `policy`.
+    pub fn check<P>(
+        &self,
+        policy: &P,
+        scts: &[SignedCertificateTimestamp],
+        cert: &Certificate,
+        issuer: &Certificate,
+        time: Duration,
+    ) -> Result<(), Error>
+    where
+        P: CtPolicy + ?Sized,
+    {
+        policy.check(&self.verify(scts, cert, issuer, time))
+    }
+}
+
+/// Trait for deciding whether a certificate has sufficient valid SCTs
+pub trait CtPolicy {
+    /// Checks the distinct `logs` which issued a valid SCT. Returns [`Error::Transparency`] when
+    /// they are not sufficient.
+    fn check(&self, logs: &[&Log]) -> Result<(), Error>;
+}
+
+/// [`CtPolicy`] requiring valid SCTs from at least this many distinct log operators
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DistinctOperators(pub usize);
+
+impl CtPolicy for DistinctOperators {
+    fn check(&self, logs: &[&Log]) -> Result<(), Error> {
+        let mut operators: Vec<&str> = logs.iter().map(|l| l.operator.as_str()).collect();
+        operators.sort_unstable();
+        operators.dedup();
+        match operators.len() >= self.0 {
+            true => Ok(()),
+            false => Err(Error::Transparency),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for LogList {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use alloc::collections::BTreeMap;
+        use base64ct::{Base64, Encoding};
+        use serde::de::{Error, IgnoredAny};
+
+        #[derive(serde::Deserialize)]
+        struct LogListV3 {
+            operators: Vec<OperatorV3>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct OperatorV3 {
+            name: String,
+            logs: Vec<LogV3>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct LogV3 {
+            #[serde(default)]
+            description: String,
+            log_id: String,
+            key: String,
+            state: Option<BTreeMap<String, IgnoredAny>>,
+            temporal_interval: Option<IntervalV3>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct IntervalV3 {
+            start_inclusive: String,
+            end_exclusive: String,
+        }
+
+        let time = |s: &str| parse_time(s).ok_or_else(|| D::Error::custom("invalid timestamp"));
+        let mut list = LogList::new();
+        for operator in LogListV3::deserialize(deserializer)?.operators {
+            for log in operator.logs {
+                if log.state.as_ref().map_or(false, |state| {
+                    state.contains_key("pending") || state.contains_key("rejected")
+                }) {
+                    continue;
+                }
+                let id = Base64::decode_vec(&log.log_id).map_err(D::Error::custom)?;
+                let key = Base64::decode_vec(&log.key).map_err(D::Error::custom)?;
+                if id[..] != Sha256::digest(&key)[..] {
+                    return Err(D::Error::custom("log ID does not match the key"));
+                }
+                let (start, end) = match &log.temporal_interval {
+                    Some(i) => (
+                        Some(time(&i.start_inclusive)?),
+                        Some(time(&i.end_exclusive)?),
+                    ),
+                    None => (None, None),
+                };
+                list.insert(Log {
+                    id: id
+                        .try_into()
+                        .map_err(|_| D::Error::custom("invalid log ID"))?,
+                    operator: operator.name.clone(),
+                    description: log.description,
+                    key,
+                    start,
+                    end,
+                });
+            }
+        }
+        Ok(list)
+    }
+}
+
+/// Parses an RFC 3339 UTC timestamp, such as `2025-01-01T00:00:00Z`. Fractional seconds are
+/// truncated.
+#[cfg(feature = "serde")]
+fn parse_time(s: &str) -> Option<Duration> {
+    let (s, fraction) = match s.strip_suffix('Z')?.split_once('.') {
+        Some((s, fraction)) => (s, fraction),
+        None => (s.strip_suffix('Z')?, ""),
+    };
+    let bytes = s.as_bytes();
+    if bytes.len() != 19
+        || [(4, b'-'), (7, b'-'), (10, b'T'), (13, b':'), (16, b':')]
+            .iter()
+            .any(|(i, c)| bytes[*i] != *c)
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let number = |from: usize, to: usize| s.get(from..to)?.parse::<u16>().ok();
+    der::DateTime::new(
+        number(0, 4)?,
+        number(5, 7)? as u8,
+        number(8, 10)? as u8,
+        number(11, 13)? as u8,
+        number(14, 16)? as u8,
+        number(17, 19)? as u8,
+    )
+    .ok()
+    .map(|t| t.unix_duration())
+}
+
+/// Appends `value` as a TLS vector with a 24-bit length
+fn push_vec24(data: &mut Vec<u8>, value: &[u8]) -> Result<(), Error> {
+    if value.len() >= 1 << 24 {
+        return Err(Error::Transparency);
+    }
+    data.extend_from_slice(&(value.len() as u32).to_be_bytes()[1..]);
+    data.extend_from_slice(value);
+    Ok(())
+}
+
+/// Reader of TLS presentation language encodings
+struct TlsReader<'a>(&'a [u8]);
+
+impl<'a> TlsReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::Transparency);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Reads a vector with a 16-bit length
+    fn vec16(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.bytes(2)?;
+        self.bytes(usize::from(u16::from_be_bytes([len[0], len[1]])))
+    }
+
+    fn finish(&self) -> Result<(), Error> {
+        match self.0.is_empty() {
+            true => Ok(()),
+            false => Err(Error::Transparency),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{precertificate_tbs, SctSource, SignedCertificateTimestamp, ID_CT_PRECERT_SCTS};
+    use crate::error::Error;
+    use der::{DecodePem, Encode};
+    use x509_verify::x509_cert::Certificate;
+
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf-ct.pem");
+    const SCT_TLS: &[u8] = include_bytes!("../testdata/sct-tls.bin");
+    const OCSP: &[u8] = include_bytes!("../testdata/ocsp-sct.der");
+
+    /// 2025-05-01T00:00:00Z, in milliseconds
+    const TIMESTAMP: u64 = 1_746_057_600_000;
+
+    #[test]
+    fn decode_scts() {
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let scts = SignedCertificateTimestamp::from_certificate(&leaf).unwrap();
+        assert_eq!(scts.len(), 3);
+        assert!(scts
+            .iter()
+            .all(|s| s.timestamp == TIMESTAMP && s.source == SctSource::Embedded));
+        assert_eq!(
+            (scts[0].hash_algorithm, scts[0].signature_algorithm),
+            (8, 7)
+        );
+
+        let scts =
+            SignedCertificateTimestamp::decode_list(SCT_TLS, SctSource::TlsExtension).unwrap();
+        assert_eq!(scts.len(), 1);
+        assert!(matches!(
+            SignedCertificateTimestamp::decode_list(
+                &SCT_TLS[..SCT_TLS.len() - 1],
+                SctSource::TlsExtension
+            ),
+            Err(Error::Transparency)
+        ));
+        // SCTs of unknown versions are skipped
+        let mut list = SCT_TLS.to_vec();
+        list[4] = 1;
+        assert!(
+            SignedCertificateTimestamp::decode_list(&list, SctSource::TlsExtension)
+                .unwrap()
+                .is_empty()
+        );
+
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let scts =
+            SignedCertificateTimestamp::from_ocsp_response(OCSP, &leaf, &intermediate).unwrap();
+        assert_eq!(scts.len(), 1);
+        assert_eq!(scts[0].source, SctSource::Ocsp);
+    }
+
+    #[test]
+    fn precertificate() {
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let tbs = precertificate_tbs(&leaf).unwrap();
+        let full = leaf.tbs_certificate.to_der().unwrap();
+        assert!(tbs.len() < full.len());
+        assert!(!tbs
+            .windows(ID_CT_PRECERT_SCTS.as_bytes().len())
+            .any(|w| w == ID_CT_PRECERT_SCTS.as_bytes()));
+    }
+
+    #[cfg(feature = "serde")]
+    mod log_list {
+        use super::{INTERMEDIATE, LEAF, OCSP, SCT_TLS};
+        use crate::{
+            ct::{
+                parse_time, DistinctOperators, Log, LogList, SctSource, SignedCertificateTimestamp,
+            },
+            error::Error,
+        };
+        use alloc::vec::Vec;
+        use core::time::Duration;
+        use der::DecodePem;
+        use x509_verify::x509_cert::Certificate;
+
+        const LOGS: &str = include_str!("../testdata/ct-logs.json");
+
+        /// 2025-06-01T00:00:00Z
+        const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+        fn descriptions<'a>(logs: &[&'a Log]) -> Vec<&'a str> {
+            logs.iter().map(|l| l.description.as_str()).collect()
+        }
+
+        #[test]
+        fn load_json() {
+            let list: LogList = serde_json::from_str(LOGS).unwrap();
+            assert_eq!(list.iter().count(), 3);
+            for log in list.iter() {
+                assert_eq!(Log::new("", "", log.key.clone()).id, log.id);
+            }
+            let log = list.iter().next().unwrap();
+            assert_eq!(log.operator, "Operator A");
+            assert_eq!(log.start, Some(Duration::from_secs(2_019_686_400)));
+            assert_eq!(log.end, Some(Duration::from_secs(2_051_222_400)));
+
+            assert_eq!(
+                parse_time("2025-01-01T00:00:00.250Z"),
+                Some(Duration::from_secs(1_735_689_600))
+            );
+            assert_eq!(parse_time("2025-01-01T00:00:00+01:00"), None);
+            assert_eq!(parse_time("2025-01-01 00:00:00Z"), None);
+        }
+
+        #[test]
+        fn log_state() {
+            let leaf = Certificate::from_pem(LEAF).unwrap();
+            let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+            let scts = SignedCertificateTimestamp::from_certificate(&leaf).unwrap();
+            for state in ["pending", "rejected"] {
+                let mut json: serde_json::Value = serde_json::from_str(LOGS).unwrap();
+                json["operators"][1]["logs"][0]["state"] =
+                    serde_json::json!({ state: { "timestamp": "2025-01-01T00:00:00Z" } });
+                let list: LogList = serde_json::from_value(json).unwrap();
+                assert_eq!(list.iter().count(), 2);
+                // Operator B's only log no longer counts
+                assert!(matches!(
+                    list.check(&DistinctOperators(2), &scts, &leaf, &intermediate, NOW),
+                    Err(Error::Transparency)
+                ));
+            }
+
+            let mut json: serde_json::Value = serde_json::from_str(LOGS).unwrap();
+            json["operators"][1]["logs"][0]["log_id"] =
+                json["operators"][0]["logs"][0]["log_id"].clone();
+            assert!(serde_json::from_value::<LogList>(json).is_err());
+            // The log ID is not signed, so a log claiming another log's ID could pass its own
+            // SCTs off as the other operator's
+            let list: LogList = serde_json::from_str(LOGS).unwrap();
+            let mut log = list.get(&scts[0].log_id).unwrap().clone();
+            log.id = scts[2].log_id;
+            let mut sct = scts[0].clone();
+            sct.log_id = log.id;
+            assert!(matches!(
+                sct.verify(&log, &leaf, &intermediate, NOW),
+                Err(Error::Verification)
+            ));
+        }
+
+        #[test]
+        fn verify_embedded() {
+            let list: LogList = serde_json::from_str(LOGS).unwrap();
+            let leaf = Certificate::from_pem(LEAF).unwrap();
+            let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+            let scts = SignedCertificateTimestamp::from_certificate(&leaf).unwrap();
+
+            // The leaf expires at the start of 2034, which the second log does not accept. The
+            // interval limits the expiry of certificates, not the time of their SCTs.
+            let logs = list.verify(&scts, &leaf, &intermediate, NOW);
+            assert_eq!(descriptions(&logs), ["Operator A 2034", "Operator B Argon"]);
+            let log = list.get(&scts[1].log_id).unwrap();
+            assert!(matches!(
+                scts[1].verify(log, &leaf, &intermediate, NOW),
+                Err(Error::Verification)
+            ));
+
+            assert!(list
+                .check(&DistinctOperators(2), &scts, &leaf, &intermediate, NOW)
+                .is_ok());
+            assert!(matches!(
+                list.check(&DistinctOperators(3), &scts, &leaf, &intermediate, NOW),
+                Err(Error::Transparency)
+            ));
+            // SCTs from the future are rejected
+            let before = Duration::from_secs(1_746_000_000);
+            assert!(list.verify(&scts, &leaf, &intermediate, before).is_empty());
+            // The signature covers the issuer key
+            assert!(list.verify(&scts, &leaf, &leaf, NOW).is_empty());
+        }
+
+        #[test]
+        fn verify_delivered() {
+            let list: LogList = serde_json::from_str(LOGS).unwrap();
+            let leaf = Certificate::from_pem(LEAF).unwrap();
+            let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+            let mut scts =
+                SignedCertificateTimestamp::decode_list(SCT_TLS, SctSource::TlsExtension).unwrap();
+            scts.extend(
+                SignedCertificateTimestamp::from_ocsp_response(OCSP, &leaf, &intermediate).unwrap(),
+            );
+            let logs = list.verify(&scts, &leaf, &intermediate, NOW);
+            assert_eq!(descriptions(&logs), ["Operator A 2034", "Operator B Argon"]);
+
+            // The signed entry depends on where the SCT was delivered
+            scts[0].source = SctSource::Embedded;
+            let log = list.get(&scts[0].log_id).unwrap();
+            assert!(matches!(
+                scts[0].verify(log, &leaf, &intermediate, NOW),
+                Err(Error::Verification)
+            ));
+        }
+    }
+}
@@ -1,7 +1,10 @@
 //! RSCS Errors
 
+use core::fmt;
+use x509_path::CertificateError;
+
 /// Possible RSCS errors
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub enum Error {
     /// A certificate in the chain could not be verified
     Verification,
@@ -12,6 +15,35 @@ pub enum Error {
     /// A certificate in the chain is not yet valid
     NotYetValid,
 
+    /// A certificate in the chain is not valid for the requested purpose
+    InvalidPurpose,
+
+    /// A certificate in the chain is revoked
+    Revoked,
+
+    /// A certificate in the chain uses a signature algorithm or a key rejected by the
+    /// algorithm policy
+    WeakAlgorithm,
+
+    /// An issuer in the chain is not a CA allowed to sign certificates, or a certificate violates
+    /// a path length or name constraint of a CA above it
+    Constraint,
+
+    /// Certificate transparency data is malformed, or the SCTs do not satisfy the policy
+    Transparency,
+
+    /// A protected container failed its integrity check, usually due to a wrong password
+    Integrity,
+
+    /// A key store is malformed or holds unsupported entries
+    KeyStore,
+
+    /// A certificate backend could not be queried
+    Unavailable,
+
+    /// A certificate in the chain could not be parsed
+    Certificate(CertificateError),
+
     /// DER error
     Der(der::Error),
 
@@ -20,12 +52,46 @@ pub enum Error {
     Io(std::io::Error),
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Error::Verification => write!(f, "Certificate could not be verified"),
+            Error::Expired => write!(f, "Certificate is expired"),
+            Error::NotYetValid => write!(f, "Certificate is not yet valid"),
+            Error::InvalidPurpose => write!(f, "Certificate is not valid for the purpose"),
+            Error::Revoked => write!(f, "Certificate is revoked"),
+            Error::WeakAlgorithm => write!(f, "Certificate uses a rejected algorithm or key"),
+            Error::Constraint => write!(
+                f,
+                "Certificate violates a basic, path length or name constraint"
+            ),
+            Error::Transparency => write!(f, "Certificate transparency check failed"),
+            Error::Integrity => write!(f, "Integrity check failed"),
+            Error::KeyStore => write!(f, "Key store is malformed or unsupported"),
+            Error::Unavailable => write!(f, "Certificate backend is unavailable"),
+            Error::Certificate(e) => write!(f, "Certificate error: {}", e),
+            Error::Der(e) => write!(f, "DER error: {}", e),
+            #[cfg(feature = "std")]
+            Error::Io(e) => write!(f, "IO error: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
 impl From<der::Error> for Error {
     fn from(e: der::Error) -> Self {
         Self::Der(e)
     }
 }
 
+impl From<CertificateError> for Error {
+    fn from(e: CertificateError) -> Self {
+        Self::Certificate(e)
+    }
+}
+
 #[cfg(feature = "std")]
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
@@ -0,0 +1,268 @@
+//! Java KeyStore (JKS and JCEKS) trusted certificates
+//!
+//! Only `trustedCertEntry` records are read. Private key entries are skipped, while JCEKS secret
+//! key entries, which hold serialized Java objects, are not supported.
+
+use crate::{error::Error, loader::Loader, store::CertificateStore};
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
+use der::Decode;
+use sha1::{Digest, Sha1};
+use x509_verify::x509_cert::Certificate;
+
+/// Magic number of JKS key stores
+const JKS_MAGIC: u32 = 0xfeed_feed;
+
+/// Magic number of JCEKS key stores
+const JCEKS_MAGIC: u32 = 0xcece_cece;
+
+/// Entry tag of private keys
+const PRIVATE_KEY_ENTRY: u32 = 1;
+
+/// Entry tag of trusted certificates
+const TRUSTED_CERT_ENTRY: u32 = 2;
+
+/// Certificate type of X.509 certificates
+const X509: &[u8] = b"X.509";
+
+/// Salt of the key store integrity digest
+const INTEGRITY_SALT: &[u8] = b"Mighty Aphrodite";
+
+/// Trusted certificate entry of a Java KeyStore
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JksCertificate {
+    /// Alias of the entry
+    pub alias: String,
+
+    /// Creation time of the entry, as the duration since the UNIX epoch
+    pub created: Duration,
+
+    /// Trusted certificate
+    pub certificate: Certificate,
+}
+
+/// Trusted certificates of a JKS or JCEKS key store
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JavaKeyStore {
+    /// Trusted certificate entries, in the order of the key store
+    pub certificates: Vec<JksCertificate>,
+}
+
+impl JavaKeyStore {
+    /// Decodes a JKS or JCEKS key store. When `password` is given, the integrity digest of the
+    /// key store is verified with it. Without a password, the key store is read unchecked, as
+    /// Java does.
+    ///
+    /// `Error::Integrity` is returned when the digest does not match, usually due to a wrong
+    /// password. `Error::KeyStore` is returned when the key store is malformed or holds a secret
+    /// key entry.
+    pub fn from_bytes(data: &[u8], password: Option<&str>) -> Result<Self, Error> {
+        if data.len() < Sha1::output_size() {
+            return Err(Error::KeyStore);
+        }
+        let (body, digest) = data.split_at(data.len() - Sha1::output_size());
+        if let Some(password) = password {
+            let mut hasher = Sha1::new();
+            for unit in password.encode_utf16() {
+                hasher.update(unit.to_be_bytes());
+            }
+            hasher.update(INTEGRITY_SALT);
+            hasher.update(body);
+            if hasher.finalize().as_slice() != digest {
+                return Err(Error::Integrity);
+            }
+        }
+
+        let mut reader = Reader(body);
+        let magic = reader.u32()?;
+        let version = reader.u32()?;
+        if (magic != JKS_MAGIC && magic != JCEKS_MAGIC) || !(1..=2).contains(&version) {
+            return Err(Error::KeyStore);
+        }
+        let mut certificates = Vec::new();
+        for _ in 0..reader.u32()? {
+            let tag = reader.u32()?;
+            let alias = reader.utf()?;
+            let created = Duration::from_millis(reader.u64()?);
+            match tag {
+                PRIVATE_KEY_ENTRY => {
+                    let key_len = reader.u32()?;
+                    reader.bytes(key_len)?;
+                    for _ in 0..reader.u32()? {
+                        reader.certificate(version)?;
+                    }
+                }
+                TRUSTED_CERT_ENTRY => {
+                    let (cert_type, encoded) = reader.certificate(version)?;
+                    if cert_type == X509 {
+                        certificates.push(JksCertificate {
+                            alias,
+                            created,
+                            certificate: Certificate::from_der(encoded)?,
+                        });
+                    }
+                }
+                _ => return Err(Error::KeyStore),
+            }
+        }
+        if !reader.0.is_empty() {
+            return Err(Error::KeyStore);
+        }
+        Ok(Self { certificates })
+    }
+}
+
+impl<Id, L> CertificateStore<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Inserts every trusted certificate of a JKS or JCEKS key store into the certificate store.
+    /// Aliases are kept as the certificates' labels. See [`JavaKeyStore::from_bytes`].
+    pub fn import_jks(&mut self, data: &[u8], password: Option<&str>) -> Result<(), Error>
+    where
+        Error: for<'a> From<<Id as TryFrom<&'a Certificate>>::Error>,
+    {
+        let key_store = JavaKeyStore::from_bytes(data, password)?;
+        for entry in key_store.certificates {
+            let id = Id::try_from(&entry.certificate)?;
+            self.insert::<Error>(entry.certificate)?;
+            self.set_label(id, entry.alias);
+        }
+        Ok(())
+    }
+}
+
+/// Big-endian reader over the fields of a key store
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: u32) -> Result<&'a [u8], Error> {
+        let len = usize::try_from(len).map_err(|_| Error::KeyStore)?;
+        if self.0.len() < len {
+            return Err(Error::KeyStore);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from(self.u32()?) << 32 | u64::from(self.u32()?))
+    }
+
+    /// Reads a string as written by Java's `DataOutput.writeUTF`, which uses modified UTF-8:
+    /// NUL is encoded in two bytes and supplementary characters as two encoded surrogates.
+    fn utf(&mut self) -> Result<String, Error> {
+        let len = self.u16()?;
+        let bytes = self.bytes(len.into())?;
+        let mut units = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let continuation = |j: usize| match bytes.get(j) {
+                Some(b) if b & 0xc0 == 0x80 => Ok(u16::from(b & 0x3f)),
+                _ => Err(Error::KeyStore),
+            };
+            let b = bytes[i];
+            let (unit, width) = match b {
+                0x01..=0x7f => (u16::from(b), 1),
+                0xc0..=0xdf => (u16::from(b & 0x1f) << 6 | continuation(i + 1)?, 2),
+                0xe0..=0xef => (
+                    u16::from(b & 0x0f) << 12 | continuation(i + 1)? << 6 | continuation(i + 2)?,
+                    3,
+                ),
+                _ => return Err(Error::KeyStore),
+            };
+            units.push(unit);
+            i += width;
+        }
+        char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(|_| Error::KeyStore)
+    }
+
+    /// Reads a certificate as its type and encoding. Version 1 key stores only hold X.509
+    /// certificates and omit the type.
+    fn certificate(&mut self, version: u32) -> Result<(&'a [u8], &'a [u8]), Error> {
+        let cert_type = match version {
+            1 => X509,
+            _ => {
+                let len = self.u16()?;
+                self.bytes(len.into())?
+            }
+        };
+        let len = self.u32()?;
+        Ok((cert_type, self.bytes(len)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JavaKeyStore, Reader};
+    use crate::{error::Error, name::NameBytes, MemCertificateStore};
+    use core::time::Duration;
+    use der::DecodePem;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf.pem");
+    const JKS: &[u8] = include_bytes!("../testdata/truststore.jks");
+    const JCEKS: &[u8] = include_bytes!("../testdata/truststore.jceks");
+
+    /// 2025-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+    #[test]
+    fn decode_key_stores() {
+        let root = Certificate::from_pem(ROOT).unwrap();
+        for data in [JKS, JCEKS] {
+            // Both key stores also hold a private key entry, which is skipped
+            let key_store = JavaKeyStore::from_bytes(data, Some("changeit")).unwrap();
+            assert_eq!(key_store.certificates.len(), 2);
+            let entry = key_store
+                .certificates
+                .iter()
+                .find(|e| e.alias == "rscs test root")
+                .unwrap();
+            assert_eq!(entry.certificate, root);
+            assert_eq!(JavaKeyStore::from_bytes(data, None).unwrap(), key_store);
+            assert!(matches!(
+                JavaKeyStore::from_bytes(data, Some("wrong")),
+                Err(Error::Integrity)
+            ));
+            assert!(matches!(
+                JavaKeyStore::from_bytes(&data[..data.len() - 30], None),
+                Err(Error::KeyStore)
+            ));
+        }
+    }
+
+    #[test]
+    fn modified_utf8() {
+        // "a", NUL, "é" and U+1F600 as a surrogate pair
+        let data = b"\x00\x0ba\xc0\x80\xc3\xa9\xed\xa0\xbd\xed\xb8\x80";
+        assert_eq!(Reader(data).utf().unwrap(), "a\0é\u{1f600}");
+        assert!(Reader(b"\x00\x02\xed\xa0").utf().is_err());
+    }
+
+    #[test]
+    fn store_import() {
+        let mut store = MemCertificateStore::new();
+        store.import_jks(JKS, Some("changeit")).unwrap();
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let id = NameBytes::try_from(&root).unwrap();
+        assert_eq!(store.label(&id), Some("rscs test root"));
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        assert!(store.verify_at(&leaf, &[], NOW).is_ok());
+    }
+}
@@ -10,16 +10,50 @@
     unused_lifetimes,
     unused_qualifications
 )]
+#![cfg_attr(test, allow(clippy::unwrap_used))]
 
 extern crate alloc;
 
 #[cfg(feature = "std")]
 extern crate std;
 
+#[cfg(feature = "aia")]
+pub mod aia;
+pub mod algorithm;
+#[cfg(feature = "async")]
+pub mod async_loader;
+pub mod cache;
+pub mod crl;
+#[cfg(feature = "ct")]
+pub mod ct;
 pub mod error;
+#[cfg(feature = "jks")]
+pub mod jks;
 pub mod loader;
 pub mod name;
+pub mod ocsp;
+#[cfg(feature = "pkcs12")]
+pub mod pkcs12;
+#[cfg(feature = "pkcs7")]
+pub mod pkcs7;
+#[cfg(feature = "pkcs7")]
+pub mod signed_data;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+#[cfg(feature = "pkcs7")]
+pub mod timestamp;
+#[cfg(feature = "rustls")]
+pub mod tls;
+pub mod view;
+#[cfg(feature = "std")]
+pub mod watch;
 
+mod path;
+#[cfg(feature = "std")]
+mod shared;
 mod store;
 
+pub use path::VerifiedPath;
+#[cfg(feature = "std")]
+pub use shared::SharedCertificateStore;
 pub use store::{CertificateStore, MemCertificateStore};
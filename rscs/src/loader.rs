@@ -1,10 +1,17 @@
 //! Certificate Loader
 
+use crate::{error::Error, view::CertificateView};
 use alloc::boxed::Box;
 use x509_verify::x509_cert::Certificate;
 
+mod encoded;
+#[cfg(feature = "store_file")]
+mod file;
 mod mem;
 
+pub use encoded::DerLoader;
+#[cfg(feature = "store_file")]
+pub use file::FileLoader;
 pub use mem::MemLoader;
 
 /// Trait for defining a certificate loader to be used within the certificate store
@@ -16,6 +23,12 @@ where
     /// any. `None`, otherwise.
     fn insert(&mut self, id: Id, cert: Certificate) -> Option<Certificate>;
 
+    /// Inserts a certificate like [`Loader::insert`], but fails instead of leaving out a
+    /// certificate the loader can not store. The default implementation never fails.
+    fn try_insert(&mut self, id: Id, cert: Certificate) -> Result<Option<Certificate>, Error> {
+        Ok(self.insert(id, cert))
+    }
+
     /// Removes a certificate from the internal storage and returns it. Returns `None` if nothing
     /// was removed.
     fn remove(&mut self, id: &Id) -> Option<Certificate>;
@@ -26,4 +39,10 @@ where
 
     /// Returns an iterator over the internal storage in the form of a Tuple `(&Id, &Certificate)`
     fn iter(&self) -> Box<dyn Iterator<Item = (&'_ Id, &'_ Certificate)> + '_>;
+
+    /// Retrieves a borrowed view of a certificate's DER encoding without decoding it. Returns
+    /// `None` if no certificate was found or the loader does not keep certificates encoded.
+    fn view(&self, _id: &Id) -> Option<CertificateView<'_>> {
+        None
+    }
 }
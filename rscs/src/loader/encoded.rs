@@ -0,0 +1,179 @@
+//! DER-encoded in-memory loader
+
+use crate::{error::Error, loader::Loader, name::NameBytes, view::CertificateView};
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt;
+use der::{Decode, Encode};
+use hashbrown::HashMap;
+use once_cell::race::OnceBox;
+use x509_verify::x509_cert::Certificate;
+
+/// Memory-only certificate loader which keeps certificates DER-encoded. A certificate is only
+/// decoded the first time it is retrieved with [`Loader::get`], while [`Loader::view`] never
+/// decodes it. Path building matches issuers on views, and only decodes the trust anchors of the
+/// paths it considers.
+#[derive(Clone, Debug, Default)]
+pub struct DerLoader(HashMap<NameBytes, Encoded>);
+
+impl DerLoader {
+    /// Creates an empty [`DerLoader`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a DER-encoded certificate without decoding it. Only the structure of the
+    /// certificate is checked. Returns the encoding of the certificate it replaces, if any.
+    pub fn insert_der(&mut self, der: Vec<u8>) -> Result<Option<Vec<u8>>, Error> {
+        let id = NameBytes::from(&CertificateView::new(&der)?);
+        let encoded = Encoded {
+            der,
+            decoded: OnceBox::new(),
+        };
+        Ok(self.0.insert(id, encoded).map(|e| e.der))
+    }
+}
+
+impl Loader<NameBytes> for DerLoader {
+    /// Inserts `cert` along with its encoding. A certificate which can not be encoded, and
+    /// therefore not be viewed, is not inserted, and the certificate stored under `id` is kept.
+    /// Use [`Loader::try_insert`] to learn about such certificates.
+    fn insert(&mut self, id: NameBytes, cert: Certificate) -> Option<Certificate> {
+        self.try_insert(id, cert).ok().flatten()
+    }
+
+    /// Inserts `cert` along with its encoding. Fails if `cert` can not be encoded, in which case
+    /// the certificate stored under `id` is kept.
+    fn try_insert(
+        &mut self,
+        id: NameBytes,
+        cert: Certificate,
+    ) -> Result<Option<Certificate>, Error> {
+        let der = cert.to_der()?;
+        // The certificate is already decoded, so keep it along with its encoding
+        let encoded = Encoded {
+            der,
+            decoded: OnceBox::with_value(Box::new(cert)),
+        };
+        Ok(self
+            .0
+            .insert(id, encoded)
+            .and_then(Encoded::into_certificate))
+    }
+
+    fn remove(&mut self, id: &NameBytes) -> Option<Certificate> {
+        self.0.remove(id).and_then(Encoded::into_certificate)
+    }
+
+    fn get(&self, id: &NameBytes) -> Option<&Certificate> {
+        self.0.get(id).and_then(Encoded::certificate)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&'_ NameBytes, &'_ Certificate)> + '_> {
+        Box::from(
+            self.0
+                .iter()
+                .filter_map(|(id, e)| e.certificate().map(|c| (id, c))),
+        )
+    }
+
+    fn view(&self, id: &NameBytes) -> Option<CertificateView<'_>> {
+        self.0
+            .get(id)
+            .and_then(|e| CertificateView::new(&e.der).ok())
+    }
+}
+
+/// DER-encoded certificate, decoded on first access
+struct Encoded {
+    der: Vec<u8>,
+    decoded: OnceBox<Certificate>,
+}
+
+impl Encoded {
+    /// Returns the decoded certificate. Returns `None` if it can not be decoded.
+    fn certificate(&self) -> Option<&Certificate> {
+        self.decoded
+            .get_or_try_init(|| Certificate::from_der(&self.der).map(Box::new))
+            .ok()
+    }
+
+    fn into_certificate(self) -> Option<Certificate> {
+        match self.decoded.get() {
+            Some(cert) => Some(cert.clone()),
+            None => Certificate::from_der(&self.der).ok(),
+        }
+    }
+}
+
+impl Clone for Encoded {
+    fn clone(&self) -> Self {
+        let decoded = match self.decoded.get() {
+            Some(cert) => OnceBox::with_value(Box::new(cert.clone())),
+            None => OnceBox::new(),
+        };
+        Self {
+            der: self.der.clone(),
+            decoded,
+        }
+    }
+}
+
+impl fmt::Debug for Encoded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Encoded")
+            .field("der", &self.der)
+            .field("decoded", &self.decoded.get().is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DerLoader;
+    use crate::{error::Error, loader::Loader, name::NameBytes, store::CertificateStore};
+    use core::time::Duration;
+    use der::{DecodePem, Encode};
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../../testdata/intermediate.pem");
+    const LEAF: &[u8] = include_bytes!("../../testdata/leaf.pem");
+
+    /// 2025-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+    #[test]
+    fn lazy_decoding() {
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let id = NameBytes::try_from(&root).unwrap();
+        let mut loader = DerLoader::new();
+        assert_eq!(loader.insert_der(root.to_der().unwrap()).unwrap(), None);
+        assert!(loader.insert_der(b"not a certificate".to_vec()).is_err());
+
+        let view = loader.view(&id).unwrap();
+        assert_eq!(view.subject().unwrap(), root.tbs_certificate.subject);
+        assert!(loader.0[&id].decoded.get().is_none());
+        assert_eq!(loader.get(&id), Some(&root));
+        assert!(loader.0[&id].decoded.get().is_some());
+        assert_eq!(loader.remove(&id), Some(root));
+        assert!(loader.view(&id).is_none());
+    }
+
+    #[test]
+    fn store_verification() {
+        let mut loader = DerLoader::new();
+        for pem in [ROOT, INTERMEDIATE] {
+            let der = Certificate::from_pem(pem).unwrap().to_der().unwrap();
+            loader.insert_der(der).unwrap();
+        }
+        let mut store = CertificateStore::with_loader(loader);
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        assert!(store.verify_at(&leaf, &[], NOW).is_ok());
+
+        let id = NameBytes::try_from(&leaf).unwrap();
+        assert!(store.view(&id).is_none());
+        store.insert::<Error>(leaf.clone()).unwrap();
+        assert_eq!(store.view(&id).unwrap().der(), leaf.to_der().unwrap());
+        assert_eq!(store.iter().count(), 3);
+    }
+}
@@ -0,0 +1,538 @@
+//! Binary store file loader
+
+use crate::{
+    error::Error, loader::Loader, name::NameBytes, store::CertificateStore, view::CertificateView,
+};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::fmt;
+use der::{Decode, Encode};
+use hashbrown::{HashMap, HashSet};
+use once_cell::race::OnceBox;
+use sha2::{Digest, Sha256};
+use x509_path::KeyIdentifier;
+use x509_verify::x509_cert::{ext::pkix::SubjectKeyIdentifier, Certificate};
+
+/// Magic number of store files
+const MAGIC: &[u8; 8] = b"RSCSTORE";
+
+/// Current store file version
+const VERSION: u32 = 1;
+
+/// Size of the magic number, version and count
+const HEADER_SIZE: usize = 16;
+
+/// Size of an index entry
+const INDEX_ENTRY_SIZE: usize = 12;
+
+/// Size of a record entry
+const RECORD_SIZE: usize = 8;
+
+/// Size of the trailing checksum
+const CHECKSUM_SIZE: usize = 32;
+
+/// Index tables in the order of the file
+#[derive(Copy, Clone)]
+enum Index {
+    Subject = 0,
+    KeyIdentifier = 1,
+    Fingerprint = 2,
+}
+
+/// Decoded certificate of a store file
+type Entry = (NameBytes, Certificate);
+
+/// Certificate loader backed by a binary store file.
+///
+/// A store file holds the DER encoding of every certificate along with sorted indexes, so that
+/// certificates can be looked up without decoding the whole file. Certificates are only decoded
+/// the first time they are returned. All integers are big-endian:
+///
+/// ```text
+/// magic        "RSCSTORE"
+/// version      u32, currently 1
+/// count        u32, number of certificates
+/// indexes      subject, key identifier and fingerprint tables, in this order. Each table holds
+///              `count` entries of (u64 key, u32 record), sorted by key. Keys are the first 8
+///              bytes of the SHA-256 hash of the subject's DER encoding, of the subject key
+///              identifier and of the certificate's SHA-256 fingerprint, respectively.
+/// records      `count` entries of (u32 offset, u32 length) into the certificate area
+/// certificates concatenated DER encodings
+/// checksum     SHA-256 hash of everything above
+/// ```
+///
+/// Certificates without a subject key identifier extension are indexed by the SHA-1 hash of
+/// their subject public key, as described in RFC 5280 Section 4.2.1.2.
+///
+/// The file is kept as is and any `AsRef<[u8]>` can back it, such as a `Vec<u8>` or a read-only
+/// memory map. Certificates inserted into, or removed from, the loader are tracked in memory and
+/// never written back to the file.
+pub struct FileLoader {
+    data: Arc<dyn AsRef<[u8]> + Send + Sync>,
+    count: usize,
+    decoded: Vec<OnceBox<Entry>>,
+    removed: HashSet<usize>,
+    inserted: HashMap<NameBytes, Certificate>,
+}
+
+impl FileLoader {
+    /// Opens a store file. The header, the bounds of every table and the checksum are verified,
+    /// but no certificate is decoded.
+    ///
+    /// `Error::Integrity` is returned when the checksum does not match. `Error::KeyStore` is
+    /// returned when the file is malformed or of an unsupported version.
+    pub fn open(data: impl AsRef<[u8]> + Send + Sync + 'static) -> Result<Self, Error> {
+        let bytes = data.as_ref();
+        if bytes.len() < HEADER_SIZE + CHECKSUM_SIZE || &bytes[..8] != MAGIC {
+            return Err(Error::KeyStore);
+        }
+        if read_u32(bytes, 8) != VERSION {
+            return Err(Error::KeyStore);
+        }
+        let body = bytes.len() - CHECKSUM_SIZE;
+        if Sha256::digest(&bytes[..body]).as_slice() != &bytes[body..] {
+            return Err(Error::Integrity);
+        }
+
+        let count = usize::try_from(read_u32(bytes, 12)).map_err(|_| Error::KeyStore)?;
+        let blobs = count
+            .checked_mul(3 * INDEX_ENTRY_SIZE + RECORD_SIZE)
+            .and_then(|n| n.checked_add(HEADER_SIZE))
+            .filter(|n| *n <= body)
+            .ok_or(Error::KeyStore)?;
+        let area = body - blobs;
+        let loader = Self {
+            data: Arc::new(data),
+            count,
+            decoded: (0..count).map(|_| OnceBox::new()).collect(),
+            removed: HashSet::new(),
+            inserted: HashMap::new(),
+        };
+        for record in 0..count {
+            let (offset, len) = loader.record(record);
+            if offset.checked_add(len).map_or(true, |end| end > area) {
+                return Err(Error::KeyStore);
+            }
+        }
+        for index in [Index::Subject, Index::KeyIdentifier, Index::Fingerprint] {
+            for entry in 0..count {
+                if loader.index_entry(index, entry).1 >= count {
+                    return Err(Error::KeyStore);
+                }
+            }
+        }
+        Ok(loader)
+    }
+
+    /// Reads and opens a store file. See [`FileLoader::open`].
+    #[cfg(feature = "std")]
+    pub fn read(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        Self::open(std::fs::read(path)?)
+    }
+
+    /// Returns the number of certificates in the store file, regardless of later changes
+    pub fn file_len(&self) -> usize {
+        self.count
+    }
+
+    /// Retrieves a certificate by its subject key identifier
+    pub fn get_by_key_identifier(&self, key_id: &[u8]) -> Option<&Certificate> {
+        self.inserted
+            .values()
+            .find(|c| key_identifier(c).ok().as_deref() == Some(key_id))
+            .or_else(|| {
+                self.lookup(Index::KeyIdentifier, key_id, |c| {
+                    key_identifier(c).ok().as_deref() == Some(key_id)
+                })
+            })
+    }
+
+    /// Retrieves a certificate by the SHA-256 hash of its DER encoding
+    pub fn get_by_fingerprint(&self, fingerprint: &[u8]) -> Option<&Certificate> {
+        let matches = |der: &[u8]| Sha256::digest(der).as_slice() == fingerprint;
+        self.inserted
+            .values()
+            .find(|c| c.to_der().map_or(false, |der| matches(&der)))
+            .or_else(|| {
+                self.find(Index::Fingerprint, fingerprint, |r| matches(self.der(r)))
+                    .and_then(|r| self.entry(r))
+                    .map(|(_, c)| c)
+            })
+    }
+
+    /// Finds the first record of the file whose key in `index` matches `value` and whose
+    /// certificate satisfies `matches`
+    fn lookup<F>(&self, index: Index, value: &[u8], matches: F) -> Option<&Certificate>
+    where
+        F: Fn(&Certificate) -> bool,
+    {
+        self.find(index, value, |r| {
+            self.entry(r).map_or(false, |(_, c)| matches(c))
+        })
+        .and_then(|r| self.entry(r))
+        .map(|(_, c)| c)
+    }
+
+    /// Returns the record of the file, not removed, whose subject is `id`. Only the structure of
+    /// the candidate certificates is decoded.
+    fn find_subject(&self, id: &NameBytes) -> Option<usize> {
+        self.find(Index::Subject, id.as_bytes(), |r| {
+            self.view_record(r)
+                .map_or(false, |v| v.subject_der() == id.as_bytes())
+        })
+    }
+
+    /// Returns the first record of the file, not removed, whose key in `index` matches `value`
+    /// and which satisfies `matches`
+    fn find<F>(&self, index: Index, value: &[u8], matches: F) -> Option<usize>
+    where
+        F: Fn(usize) -> bool,
+    {
+        let key = hash_key(value);
+        let mut entry = self.partition_point(index, key);
+        while entry < self.count {
+            let (k, record) = self.index_entry(index, entry);
+            if k != key {
+                break;
+            }
+            if !self.removed.contains(&record) && matches(record) {
+                return Some(record);
+            }
+            entry += 1;
+        }
+        None
+    }
+
+    /// Returns the first entry of `index` whose key is not less than `key`
+    fn partition_point(&self, index: Index, key: u64) -> usize {
+        let (mut low, mut high) = (0, self.count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.index_entry(index, mid).0 < key {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    /// Decodes a record on first access. Returns `None` if the record can not be decoded.
+    fn entry(&self, record: usize) -> Option<&Entry> {
+        self.decoded[record]
+            .get_or_try_init(|| {
+                let cert = Certificate::from_der(self.der(record))?;
+                let id = NameBytes::try_from(&cert)?;
+                Ok::<_, Error>(Box::new((id, cert)))
+            })
+            .ok()
+    }
+
+    /// Returns a view of a record. Returns `None` if the record is malformed.
+    fn view_record(&self, record: usize) -> Option<CertificateView<'_>> {
+        CertificateView::new(self.der(record)).ok()
+    }
+
+    /// Returns the DER encoding of a record
+    fn der(&self, record: usize) -> &[u8] {
+        let (offset, len) = self.record(record);
+        let start = self.blobs() + offset;
+        &self.bytes()[start..start + len]
+    }
+
+    fn bytes(&self) -> &[u8] {
+        (*self.data).as_ref()
+    }
+
+    /// Returns the offset of the certificate area
+    fn blobs(&self) -> usize {
+        HEADER_SIZE + self.count * (3 * INDEX_ENTRY_SIZE + RECORD_SIZE)
+    }
+
+    /// Returns the key and record of an index entry
+    fn index_entry(&self, index: Index, entry: usize) -> (u64, usize) {
+        let pos = HEADER_SIZE + (index as usize * self.count + entry) * INDEX_ENTRY_SIZE;
+        let bytes = self.bytes();
+        (read_u64(bytes, pos), read_u32(bytes, pos + 8) as usize)
+    }
+
+    /// Returns the offset and length of a record within the certificate area
+    fn record(&self, record: usize) -> (usize, usize) {
+        let pos = HEADER_SIZE + 3 * self.count * INDEX_ENTRY_SIZE + record * RECORD_SIZE;
+        let bytes = self.bytes();
+        (
+            read_u32(bytes, pos) as usize,
+            read_u32(bytes, pos + 4) as usize,
+        )
+    }
+}
+
+impl Default for FileLoader {
+    /// Returns a loader over an empty store file
+    fn default() -> Self {
+        Self {
+            data: Arc::new(Vec::new()),
+            count: 0,
+            decoded: Vec::new(),
+            removed: HashSet::new(),
+            inserted: HashMap::new(),
+        }
+    }
+}
+
+impl Clone for FileLoader {
+    fn clone(&self) -> Self {
+        let decoded = self
+            .decoded
+            .iter()
+            .map(|d| match d.get() {
+                Some(entry) => OnceBox::with_value(Box::new(entry.clone())),
+                None => OnceBox::new(),
+            })
+            .collect();
+        Self {
+            data: self.data.clone(),
+            count: self.count,
+            decoded,
+            removed: self.removed.clone(),
+            inserted: self.inserted.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for FileLoader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileLoader")
+            .field("count", &self.count)
+            .field("removed", &self.removed)
+            .field("inserted", &self.inserted)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Loader<NameBytes> for FileLoader {
+    fn insert(&mut self, id: NameBytes, cert: Certificate) -> Option<Certificate> {
+        let replaced = self.remove(&id);
+        self.inserted.insert(id, cert);
+        replaced
+    }
+
+    fn remove(&mut self, id: &NameBytes) -> Option<Certificate> {
+        if let Some(cert) = self.inserted.remove(id) {
+            return Some(cert);
+        }
+        let record = self.find_subject(id)?;
+        self.removed.insert(record);
+        self.entry(record).map(|(_, c)| c.clone())
+    }
+
+    fn get(&self, id: &NameBytes) -> Option<&Certificate> {
+        self.inserted.get(id).or_else(|| {
+            self.find_subject(id)
+                .and_then(|r| self.entry(r))
+                .map(|(_, c)| c)
+        })
+    }
+
+    /// Only certificates of the store file have a view, not those inserted later
+    fn view(&self, id: &NameBytes) -> Option<CertificateView<'_>> {
+        if self.inserted.contains_key(id) {
+            return None;
+        }
+        self.find_subject(id).and_then(|r| self.view_record(r))
+    }
+
+    /// Iterates over every certificate, decoding the whole store file on the way
+    fn iter(&self) -> Box<dyn Iterator<Item = (&'_ NameBytes, &'_ Certificate)> + '_> {
+        let file = (0..self.count)
+            .filter(|r| !self.removed.contains(r))
+            .filter_map(|r| self.entry(r))
+            .filter(|(id, _)| !self.inserted.contains_key(id))
+            .map(|(id, c)| (id, c));
+        Box::from(file.chain(self.inserted.iter()))
+    }
+}
+
+impl CertificateStore<NameBytes, FileLoader> {
+    /// Opens a store file as a certificate store. See [`FileLoader::open`].
+    pub fn open_store_file(data: impl AsRef<[u8]> + Send + Sync + 'static) -> Result<Self, Error> {
+        Ok(Self::with_loader(FileLoader::open(data)?))
+    }
+}
+
+impl<Id, L> CertificateStore<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Writes every certificate in the store as a binary store file, which can be opened with
+    /// [`FileLoader::open`]. The same certificates always produce the same file. Labels are not
+    /// written.
+    pub fn to_store_file(&self) -> Result<Vec<u8>, Error> {
+        let mut subjects = Vec::new();
+        let mut key_ids = Vec::new();
+        let mut fingerprints = Vec::new();
+        let mut records = Vec::new();
+        let mut blobs = Vec::new();
+        // Certificates are written in the order of their encoding for reproducible files
+        let mut certs = self
+            .iter()
+            .map(|(_, c)| Ok((c.to_der()?, c)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        certs.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        for (record, (der, cert)) in certs.into_iter().enumerate() {
+            let record = u32::try_from(record).map_err(|_| Error::KeyStore)?;
+            subjects.push((hash_key(&cert.tbs_certificate.subject.to_der()?), record));
+            key_ids.push((hash_key(&key_identifier(cert)?), record));
+            fingerprints.push((hash_key(&Sha256::digest(&der)), record));
+            let offset = u32::try_from(blobs.len()).map_err(|_| Error::KeyStore)?;
+            let len = u32::try_from(der.len()).map_err(|_| Error::KeyStore)?;
+            records.push((offset, len));
+            blobs.extend_from_slice(&der);
+        }
+
+        let mut file = Vec::new();
+        file.extend_from_slice(MAGIC);
+        file.extend_from_slice(&VERSION.to_be_bytes());
+        file.extend_from_slice(&(records.len() as u32).to_be_bytes());
+        for mut index in [subjects, key_ids, fingerprints] {
+            index.sort_unstable();
+            for (key, record) in index {
+                file.extend_from_slice(&key.to_be_bytes());
+                file.extend_from_slice(&record.to_be_bytes());
+            }
+        }
+        for (offset, len) in records {
+            file.extend_from_slice(&offset.to_be_bytes());
+            file.extend_from_slice(&len.to_be_bytes());
+        }
+        file.extend_from_slice(&blobs);
+        let checksum = Sha256::digest(&file);
+        file.extend_from_slice(&checksum);
+        Ok(file)
+    }
+}
+
+/// Returns the subject key identifier of `cert`, or its key identifier using the default method
+fn key_identifier(cert: &Certificate) -> Result<Vec<u8>, Error> {
+    Ok(match cert.tbs_certificate.get::<SubjectKeyIdentifier>()? {
+        Some((_, ski)) => ski.0.as_bytes().to_vec(),
+        None => KeyIdentifier::from(&cert.tbs_certificate.subject_public_key_info)
+            .as_bytes()
+            .to_vec(),
+    })
+}
+
+/// Returns the index key of `value`
+fn hash_key(value: &[u8]) -> u64 {
+    read_u64(&Sha256::digest(value), 0)
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+fn read_u64(bytes: &[u8], pos: usize) -> u64 {
+    u64::from(read_u32(bytes, pos)) << 32 | u64::from(read_u32(bytes, pos + 4))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileLoader;
+    use crate::{
+        error::Error, loader::Loader, name::NameBytes, store::CertificateStore, MemCertificateStore,
+    };
+    use alloc::vec::Vec;
+    use core::time::Duration;
+    use der::{DecodePem, Encode};
+    use sha2::{Digest, Sha256};
+    use x509_verify::x509_cert::{ext::pkix::SubjectKeyIdentifier, Certificate};
+
+    const ROOT: &[u8] = include_bytes!("../../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../../testdata/intermediate.pem");
+    const LEAF: &[u8] = include_bytes!("../../testdata/leaf.pem");
+
+    /// 2025-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+    fn store_file() -> Vec<u8> {
+        let mut store = MemCertificateStore::new();
+        for pem in [ROOT, INTERMEDIATE] {
+            store
+                .insert::<Error>(Certificate::from_pem(pem).unwrap())
+                .unwrap();
+        }
+        store.to_store_file().unwrap()
+    }
+
+    #[test]
+    fn lookups() {
+        let loader = FileLoader::open(store_file()).unwrap();
+        assert_eq!(loader.file_len(), 2);
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+
+        let id = NameBytes::try_from(&root).unwrap();
+        assert_eq!(loader.view(&id).unwrap().der(), root.to_der().unwrap());
+        assert!(loader.decoded.iter().all(|d| d.get().is_none()));
+        assert_eq!(loader.get(&id), Some(&root));
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        assert_eq!(loader.get(&NameBytes::try_from(&leaf).unwrap()), None);
+
+        let (_, ski) = intermediate
+            .tbs_certificate
+            .get::<SubjectKeyIdentifier>()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            loader.get_by_key_identifier(ski.0.as_bytes()),
+            Some(&intermediate)
+        );
+        let fingerprint = Sha256::digest(root.to_der().unwrap());
+        assert_eq!(loader.get_by_fingerprint(&fingerprint), Some(&root));
+        assert_eq!(loader.iter().count(), 2);
+    }
+
+    #[test]
+    fn store_changes() {
+        let mut store = CertificateStore::open_store_file(store_file()).unwrap();
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        assert!(store.verify_at(&leaf, &[], NOW).is_ok());
+
+        let id = NameBytes::try_from(&intermediate).unwrap();
+        assert_eq!(store.remove(&id), Some(intermediate.clone()));
+        assert_eq!(store.get(&id), None);
+        assert!(store.verify_at(&leaf, &[], NOW).is_err());
+        assert_eq!(store.insert::<Error>(intermediate).unwrap(), None);
+        assert!(store.verify_at(&leaf, &[], NOW).is_ok());
+        assert_eq!(store.iter().count(), 2);
+
+        // Replacing a certificate of the file
+        let root_id = NameBytes::try_from(&root).unwrap();
+        assert_eq!(store.insert::<Error>(root.clone()).unwrap(), Some(root));
+        assert_eq!(store.iter().count(), 2);
+        assert!(store.get(&root_id).is_some());
+    }
+
+    #[test]
+    fn corrupt_files() {
+        let mut data = store_file();
+        let last = data.len() - 40;
+        data[last] ^= 1;
+        assert!(matches!(FileLoader::open(data), Err(Error::Integrity)));
+
+        let data = store_file();
+        assert!(matches!(
+            FileLoader::open(data[..20].to_vec()),
+            Err(Error::KeyStore)
+        ));
+        assert_eq!(
+            FileLoader::open(MemCertificateStore::new().to_store_file().unwrap())
+                .unwrap()
+                .iter()
+                .count(),
+            0
+        );
+    }
+}
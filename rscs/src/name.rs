@@ -1,6 +1,6 @@
 //! NameBytes
 
-use crate::error::Error;
+use crate::{error::Error, view::CertificateView};
 use alloc::vec::Vec;
 use core::hash::Hash;
 use der::Encode;
@@ -10,6 +10,13 @@ use x509_verify::x509_cert::{name::Name, Certificate};
 #[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct NameBytes(Vec<u8>);
 
+impl NameBytes {
+    /// Returns the DER encoding of the name
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl TryFrom<&Name> for NameBytes {
     type Error = Error;
 
@@ -18,6 +25,13 @@ impl TryFrom<&Name> for NameBytes {
     }
 }
 
+impl From<&CertificateView<'_>> for NameBytes {
+    /// Creates [`NameBytes`] from the `subject` of the certificate view, without decoding it
+    fn from(view: &CertificateView<'_>) -> Self {
+        Self(view.subject_der().to_vec())
+    }
+}
+
 impl TryFrom<&Certificate> for NameBytes {
     type Error = Error;
 
@@ -26,3 +40,23 @@ impl TryFrom<&Certificate> for NameBytes {
         Self::try_from(&cert.tbs_certificate.subject)
     }
 }
+
+/// Serializes as the base64 DER encoding in human-readable formats and as bytes otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for NameBytes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::snapshot::serialize_der(&self.0, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for NameBytes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use der::Decode;
+        use serde::de::Error;
+
+        let der = crate::snapshot::deserialize_der(deserializer)?;
+        Name::from_der(&der).map_err(D::Error::custom)?;
+        Ok(Self(der))
+    }
+}
@@ -0,0 +1,414 @@
+//! OCSP response checking ([RFC 6960])
+//!
+//! Only what is needed to check a single, typically stapled, response is implemented. Requests
+//! are not supported.
+//!
+//! [RFC 6960]: https://datatracker.ietf.org/doc/html/rfc6960
+
+use crate::{error::Error, store::verify_signature};
+use alloc::vec::Vec;
+use const_oid::db::{
+    rfc5280::ID_KP_OCSP_SIGNING,
+    rfc5912::{ID_CE_EXT_KEY_USAGE, ID_SHA_1, ID_SHA_256},
+    rfc6960::ID_PKIX_OCSP_BASIC,
+};
+use core::time::Duration;
+use der::{
+    asn1::{AnyRef, BitString, GeneralizedTime, Null, OctetStringRef},
+    referenced::OwnedToRef,
+    Choice, Decode, Encode, Enumerated, Sequence,
+};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use x509_verify::{
+    spki::AlgorithmIdentifierOwned,
+    x509_cert::{
+        ext::{pkix::ExtendedKeyUsage, Extensions},
+        name::Name,
+        serial_number::SerialNumber,
+        Certificate, Version,
+    },
+    Message, Signature, VerifyInfo, VerifyingKey,
+};
+
+/// Revocation status of a certificate as reported by an OCSP response
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum OcspStatus {
+    /// The certificate is not revoked
+    Good,
+
+    /// The certificate is revoked
+    Revoked,
+
+    /// The responder does not know about the certificate
+    Unknown,
+}
+
+/// Checks the DER-encoded OCSP `response` for `cert`, which was issued by `issuer`, at `time`,
+/// given as the duration since the UNIX epoch.
+///
+/// The response must be signed either by `issuer` itself or by a responder certificate, included
+/// in the response, which `issuer` delegated OCSP signing to. Only `sha1` and `sha256`
+/// certificate IDs are recognized.
+///
+/// `Error::Verification` is returned when the response is unsuccessful, does not cover `cert` or
+/// its signature can not be verified. `Error::NotYetValid` and `Error::Expired` are returned when
+/// `time` is outside of the response's `thisUpdate` and `nextUpdate`.
+pub fn check_response(
+    response: &[u8],
+    cert: &Certificate,
+    issuer: &Certificate,
+    time: Duration,
+) -> Result<OcspStatus, Error> {
+    let response = OcspResponse::from_der(response)?;
+    let bytes = match (response.response_status, response.response_bytes) {
+        (OcspResponseStatus::Successful, Some(bytes)) => bytes,
+        _ => return Err(Error::Verification),
+    };
+    if bytes.response_type != ID_PKIX_OCSP_BASIC {
+        return Err(Error::Verification);
+    }
+    let basic = BasicOcspResponse::from_der(bytes.response.as_bytes())?;
+    let tbs = basic.tbs_response_data.to_der()?;
+    let data = ResponseData::from_der(&tbs)?;
+
+    let responder = responder(&data.responder_id, &basic.certs, issuer, time)?;
+    let key = VerifyingKey::try_from(
+        responder
+            .tbs_certificate
+            .subject_public_key_info
+            .owned_to_ref(),
+    )
+    .map_err(|_| Error::Verification)?;
+    key.verify(VerifyInfo::new(
+        Message::from(tbs.clone()),
+        Signature::new(&basic.signature_algorithm, basic.signature.raw_bytes()),
+    ))
+    .map_err(|_| Error::Verification)?;
+
+    let single = data
+        .responses
+        .iter()
+        .find(|r| matches_certificate(&r.cert_id, cert, issuer).unwrap_or(false))
+        .ok_or(Error::Verification)?;
+    if single.this_update.to_unix_duration() > time {
+        return Err(Error::NotYetValid);
+    }
+    if let Some(next_update) = single.next_update {
+        if next_update.to_unix_duration() < time {
+            return Err(Error::Expired);
+        }
+    }
+    Ok(match single.cert_status {
+        CertStatus::Good(_) => OcspStatus::Good,
+        CertStatus::Revoked(_) => OcspStatus::Revoked,
+        CertStatus::Unknown(_) => OcspStatus::Unknown,
+    })
+}
+
+/// Returns the value of the `singleExtensions` extension identified by `oid` in the response for
+/// `cert`, which was issued by `issuer`. The response itself is not verified.
+#[cfg(feature = "ct")]
+pub(crate) fn single_extension(
+    response: &[u8],
+    cert: &Certificate,
+    issuer: &Certificate,
+    oid: der::asn1::ObjectIdentifier,
+) -> Result<Option<Vec<u8>>, Error> {
+    let response = OcspResponse::from_der(response)?;
+    let bytes = match (response.response_status, response.response_bytes) {
+        (OcspResponseStatus::Successful, Some(bytes)) => bytes,
+        _ => return Err(Error::Verification),
+    };
+    if bytes.response_type != ID_PKIX_OCSP_BASIC {
+        return Err(Error::Verification);
+    }
+    let basic = BasicOcspResponse::from_der(bytes.response.as_bytes())?;
+    let tbs = basic.tbs_response_data.to_der()?;
+    let data = ResponseData::from_der(&tbs)?;
+    let single = data
+        .responses
+        .iter()
+        .find(|r| matches_certificate(&r.cert_id, cert, issuer).unwrap_or(false))
+        .ok_or(Error::Verification)?;
+    Ok(single
+        .single_extensions
+        .iter()
+        .flatten()
+        .find(|e| e.extn_id == oid)
+        .map(|e| e.extn_value.as_bytes().to_vec()))
+}
+
+/// Finds the certificate which signed the response. Delegated responders must be issued by
+/// `issuer`, be valid at `time` and carry the `id-kp-OCSPSigning` extended key usage.
+fn responder<'c>(
+    id: &ResponderId<'_>,
+    certs: &'c Option<Vec<Certificate>>,
+    issuer: &'c Certificate,
+    time: Duration,
+) -> Result<&'c Certificate, Error> {
+    let matches = |cert: &Certificate| match id {
+        ResponderId::ByName(name) => &cert.tbs_certificate.subject == name,
+        ResponderId::ByKey(hash) => {
+            let key = &cert
+                .tbs_certificate
+                .subject_public_key_info
+                .subject_public_key;
+            Sha1::digest(key.raw_bytes()).as_slice() == hash.as_bytes()
+        }
+    };
+    if matches(issuer) {
+        return Ok(issuer);
+    }
+    let delegate = certs
+        .iter()
+        .flatten()
+        .find(|c| matches(c))
+        .ok_or(Error::Verification)?;
+    verify_signature(issuer, delegate)?;
+    let validity = &delegate.tbs_certificate.validity;
+    if validity.not_before.to_unix_duration() > time {
+        return Err(Error::NotYetValid);
+    }
+    if validity.not_after.to_unix_duration() < time {
+        return Err(Error::Expired);
+    }
+    let usage = match &delegate.tbs_certificate.extensions {
+        Some(extns) => match extns.iter().find(|e| e.extn_id == ID_CE_EXT_KEY_USAGE) {
+            Some(e) => ExtendedKeyUsage::from_der(e.extn_value.as_bytes())?,
+            None => return Err(Error::InvalidPurpose),
+        },
+        None => return Err(Error::InvalidPurpose),
+    };
+    if !usage.0.contains(&ID_KP_OCSP_SIGNING) {
+        return Err(Error::InvalidPurpose);
+    }
+    Ok(delegate)
+}
+
+/// Returns whether `id` identifies `cert` as issued by `issuer`
+fn matches_certificate(
+    id: &CertId<'_>,
+    cert: &Certificate,
+    issuer: &Certificate,
+) -> Result<bool, Error> {
+    if id.serial_number != cert.tbs_certificate.serial_number {
+        return Ok(false);
+    }
+    let name = issuer.tbs_certificate.subject.to_der()?;
+    let key = issuer
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key
+        .raw_bytes();
+    let (name_hash, key_hash) = match id.hash_algorithm.oid {
+        ID_SHA_1 => (Sha1::digest(&name).to_vec(), Sha1::digest(key).to_vec()),
+        ID_SHA_256 => (Sha256::digest(&name).to_vec(), Sha256::digest(key).to_vec()),
+        _ => return Ok(false),
+    };
+    Ok(id.issuer_name_hash.as_bytes() == name_hash.as_slice()
+        && id.issuer_key_hash.as_bytes() == key_hash.as_slice())
+}
+
+/// ```text
+/// OCSPResponse ::= SEQUENCE {
+///    responseStatus         OCSPResponseStatus,
+///    responseBytes          [0] EXPLICIT ResponseBytes OPTIONAL }
+/// ```
+#[derive(Sequence)]
+struct OcspResponse<'a> {
+    response_status: OcspResponseStatus,
+    #[asn1(context_specific = "0", optional = "true")]
+    response_bytes: Option<ResponseBytes<'a>>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Enumerated)]
+#[repr(u32)]
+enum OcspResponseStatus {
+    Successful = 0,
+    MalformedRequest = 1,
+    InternalError = 2,
+    TryLater = 3,
+    SigRequired = 5,
+    Unauthorized = 6,
+}
+
+/// ```text
+/// ResponseBytes ::= SEQUENCE {
+///    responseType   OBJECT IDENTIFIER,
+///    response       OCTET STRING }
+/// ```
+#[derive(Sequence)]
+struct ResponseBytes<'a> {
+    response_type: der::asn1::ObjectIdentifier,
+    response: OctetStringRef<'a>,
+}
+
+/// The response data is kept encoded so that the signature is checked over the exact bytes.
+///
+/// ```text
+/// BasicOCSPResponse ::= SEQUENCE {
+///    tbsResponseData      ResponseData,
+///    signatureAlgorithm   AlgorithmIdentifier,
+///    signature            BIT STRING,
+///    certs            [0] EXPLICIT SEQUENCE OF Certificate OPTIONAL }
+/// ```
+#[derive(Sequence)]
+struct BasicOcspResponse<'a> {
+    tbs_response_data: AnyRef<'a>,
+    signature_algorithm: AlgorithmIdentifierOwned,
+    signature: BitString,
+    #[asn1(context_specific = "0", optional = "true")]
+    certs: Option<Vec<Certificate>>,
+}
+
+/// ```text
+/// ResponseData ::= SEQUENCE {
+///    version              [0] EXPLICIT Version DEFAULT v1,
+///    responderID              ResponderID,
+///    producedAt               GeneralizedTime,
+///    responses                SEQUENCE OF SingleResponse,
+///    responseExtensions   [1] EXPLICIT Extensions OPTIONAL }
+/// ```
+#[derive(Sequence)]
+struct ResponseData<'a> {
+    #[asn1(context_specific = "0", default = "Default::default")]
+    version: Version,
+    responder_id: ResponderId<'a>,
+    produced_at: GeneralizedTime,
+    responses: Vec<SingleResponse<'a>>,
+    #[asn1(context_specific = "1", optional = "true")]
+    response_extensions: Option<Extensions>,
+}
+
+/// ```text
+/// ResponderID ::= CHOICE {
+///    byName   [1] Name,
+///    byKey    [2] KeyHash }
+/// ```
+#[derive(Choice)]
+enum ResponderId<'a> {
+    #[asn1(context_specific = "1", tag_mode = "EXPLICIT", constructed = "true")]
+    ByName(Name),
+
+    #[asn1(context_specific = "2", tag_mode = "EXPLICIT", constructed = "true")]
+    ByKey(OctetStringRef<'a>),
+}
+
+/// ```text
+/// SingleResponse ::= SEQUENCE {
+///    certID                       CertID,
+///    certStatus                   CertStatus,
+///    thisUpdate                   GeneralizedTime,
+///    nextUpdate         [0]       EXPLICIT GeneralizedTime OPTIONAL,
+///    singleExtensions   [1]       EXPLICIT Extensions OPTIONAL }
+/// ```
+#[derive(Sequence)]
+struct SingleResponse<'a> {
+    cert_id: CertId<'a>,
+    cert_status: CertStatus,
+    this_update: GeneralizedTime,
+    #[asn1(context_specific = "0", optional = "true")]
+    next_update: Option<GeneralizedTime>,
+    #[asn1(context_specific = "1", optional = "true")]
+    single_extensions: Option<Extensions>,
+}
+
+/// ```text
+/// CertID ::= SEQUENCE {
+///    hashAlgorithm       AlgorithmIdentifier,
+///    issuerNameHash      OCTET STRING,
+///    issuerKeyHash       OCTET STRING,
+///    serialNumber        CertificateSerialNumber }
+/// ```
+#[derive(Sequence)]
+struct CertId<'a> {
+    hash_algorithm: AlgorithmIdentifierOwned,
+    issuer_name_hash: OctetStringRef<'a>,
+    issuer_key_hash: OctetStringRef<'a>,
+    serial_number: SerialNumber,
+}
+
+/// ```text
+/// CertStatus ::= CHOICE {
+///    good        [0]     IMPLICIT NULL,
+///    revoked     [1]     IMPLICIT RevokedInfo,
+///    unknown     [2]     IMPLICIT UnknownInfo }
+/// ```
+#[derive(Choice)]
+enum CertStatus {
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT")]
+    Good(Null),
+
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", constructed = "true")]
+    Revoked(RevokedInfo),
+
+    #[asn1(context_specific = "2", tag_mode = "IMPLICIT")]
+    Unknown(Null),
+}
+
+/// ```text
+/// RevokedInfo ::= SEQUENCE {
+///    revocationTime              GeneralizedTime,
+///    revocationReason    [0]     EXPLICIT CRLReason OPTIONAL }
+/// ```
+#[derive(Sequence)]
+struct RevokedInfo {
+    revocation_time: GeneralizedTime,
+    #[asn1(context_specific = "0", optional = "true")]
+    revocation_reason: Option<x509_verify::x509_cert::ext::pkix::CrlReason>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_response, OcspStatus};
+    use crate::error::Error;
+    use core::time::Duration;
+    use der::DecodePem;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf.pem");
+    const GOOD: &[u8] = include_bytes!("../testdata/ocsp-good.der");
+    const REVOKED: &[u8] = include_bytes!("../testdata/ocsp-revoked.der");
+
+    /// 2027-01-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_798_761_600);
+
+    #[test]
+    fn response_status() {
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        assert_eq!(
+            check_response(GOOD, &leaf, &intermediate, NOW).unwrap(),
+            OcspStatus::Good
+        );
+        assert_eq!(
+            check_response(REVOKED, &leaf, &intermediate, NOW).unwrap(),
+            OcspStatus::Revoked
+        );
+        assert!(matches!(
+            check_response(
+                GOOD,
+                &leaf,
+                &intermediate,
+                Duration::from_secs(1_748_736_000)
+            ),
+            Err(Error::NotYetValid)
+        ));
+    }
+
+    #[test]
+    fn response_wrong_issuer() {
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        // The intermediate signed the response but is not a delegated responder of the root
+        assert!(matches!(
+            check_response(GOOD, &leaf, &root, NOW),
+            Err(Error::InvalidPurpose)
+        ));
+    }
+}
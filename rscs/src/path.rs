@@ -0,0 +1,273 @@
+//! Verified certification path
+
+use crate::error::Error;
+use alloc::vec::Vec;
+use const_oid::db::{
+    rfc5280::{
+        ANY_EXTENDED_KEY_USAGE, ANY_POLICY, ID_CE_AUTHORITY_KEY_IDENTIFIER,
+        ID_CE_BASIC_CONSTRAINTS, ID_CE_CRL_DISTRIBUTION_POINTS, ID_CE_KEY_USAGE,
+        ID_CE_NAME_CONSTRAINTS, ID_CE_SUBJECT_ALT_NAME, ID_CE_SUBJECT_KEY_IDENTIFIER,
+        ID_PE_AUTHORITY_INFO_ACCESS,
+    },
+    rfc5912::{ID_CE_CERTIFICATE_POLICIES, ID_CE_EXT_KEY_USAGE},
+};
+use der::{asn1::ObjectIdentifier, Decode};
+use x509_path::{is_self_issued, policy_set, PolicySet, TrustAnchor};
+use x509_verify::x509_cert::{
+    ext::pkix::{BasicConstraints, CertificatePolicies, ExtendedKeyUsage, KeyUsage},
+    time::Validity,
+    Certificate,
+};
+
+/// Certificate extensions processed by path validation or by the revocation and AIA modules. A
+/// certificate with any other critical extension is rejected, as required by RFC 5280.
+const KNOWN_EXTENSIONS: [ObjectIdentifier; 10] = [
+    ID_CE_AUTHORITY_KEY_IDENTIFIER,
+    ID_CE_BASIC_CONSTRAINTS,
+    ID_CE_CERTIFICATE_POLICIES,
+    ID_CE_CRL_DISTRIBUTION_POINTS,
+    ID_CE_EXT_KEY_USAGE,
+    ID_CE_KEY_USAGE,
+    ID_CE_NAME_CONSTRAINTS,
+    ID_CE_SUBJECT_ALT_NAME,
+    ID_CE_SUBJECT_KEY_IDENTIFIER,
+    ID_PE_AUTHORITY_INFO_ACCESS,
+];
+
+/// Certification path returned from a successful verification. The path is ordered from the
+/// target certificate up to, and including, the trust anchor.
+///
+/// With the `serde` feature, the path serializes its certificates as DER, the anchor's
+/// identifier, the path policies and the validity window as UNIX timestamps.
+#[derive(Clone, Debug)]
+pub struct VerifiedPath<Id> {
+    certificates: Vec<Certificate>,
+    anchor: Id,
+    policies: PolicySet,
+    validity: Validity,
+}
+
+impl<Id> VerifiedPath<Id> {
+    /// Creates a [`VerifiedPath`] from certificates whose signatures have already been verified.
+    /// The `certificates` MUST be ordered from the target to the trust anchor and MUST NOT be
+    /// empty. Fails with [`Error::Constraint`] if an issuer is not a CA or the path violates a
+    /// path length or name constraint, and with [`Error::Verification`] if a certificate carries
+    /// an unrecognized critical extension.
+    pub(crate) fn new(certificates: Vec<Certificate>, anchor: Id) -> Result<Self, Error> {
+        check_constraints(&certificates)?;
+        let policies = path_policies(&certificates)?;
+        let validity = path_validity(&certificates);
+        Ok(Self {
+            certificates,
+            anchor,
+            policies,
+            validity,
+        })
+    }
+
+    /// Returns the certificates in the path, ordered from the target to the trust anchor.
+    pub fn certificates(&self) -> &[Certificate] {
+        &self.certificates
+    }
+
+    /// Returns the target certificate of the path.
+    pub fn leaf(&self) -> &Certificate {
+        &self.certificates[0]
+    }
+
+    /// Returns the trust anchor which terminates the path.
+    pub fn anchor(&self) -> &Certificate {
+        &self.certificates[self.certificates.len() - 1]
+    }
+
+    /// Returns the identifier of the trust anchor within the certificate store.
+    pub fn anchor_id(&self) -> &Id {
+        &self.anchor
+    }
+
+    /// Returns the set of certificate policies valid for the entire path. A path where every
+    /// certificate asserts `anyPolicy` returns a set containing only `anyPolicy`.
+    ///
+    /// Policy mappings are not processed.
+    pub fn policies(&self) -> &PolicySet {
+        &self.policies
+    }
+
+    /// Returns the effective validity window of the path. This is the intersection of the
+    /// validity periods of every certificate in the path.
+    pub fn validity(&self) -> &Validity {
+        &self.validity
+    }
+
+    /// Checks that the path is valid for `purpose`, an extended key usage OID. Every certificate
+    /// in the path which carries the extended key usage extension must assert either `purpose`
+    /// or `anyExtendedKeyUsage`. Certificates without the extension are valid for any purpose.
+    pub fn check_purpose(&self, purpose: ObjectIdentifier) -> Result<(), Error> {
+        for cert in &self.certificates {
+            if let Some(usage) = extended_key_usage(cert)? {
+                if !usage.0.contains(&purpose) && !usage.0.contains(&ANY_EXTENDED_KEY_USAGE) {
+                    return Err(Error::InvalidPurpose);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<Id: serde::Serialize> serde::Serialize for VerifiedPath<Id> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use crate::snapshot::Der;
+        use der::Encode;
+        use serde::ser::{Error, SerializeStruct};
+
+        #[derive(serde::Serialize)]
+        struct Window {
+            not_before: u64,
+            not_after: u64,
+        }
+
+        let encoded = self
+            .certificates
+            .iter()
+            .map(|c| c.to_der())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(S::Error::custom)?;
+        let certificates: Vec<Der<'_>> = encoded.iter().map(|c| Der(c)).collect();
+        let window = Window {
+            not_before: self.validity.not_before.to_unix_duration().as_secs(),
+            not_after: self.validity.not_after.to_unix_duration().as_secs(),
+        };
+        let mut state = serializer.serialize_struct("VerifiedPath", 4)?;
+        state.serialize_field("certificates", &certificates)?;
+        state.serialize_field("anchor", &self.anchor)?;
+        state.serialize_field("policies", &self.policies)?;
+        state.serialize_field("validity", &window)?;
+        state.end()
+    }
+}
+
+/// Returns the extended key usage extension of `cert`, if any
+fn extended_key_usage(cert: &Certificate) -> Result<Option<ExtendedKeyUsage>, Error> {
+    match &cert.tbs_certificate.extensions {
+        Some(extns) => match extns.iter().find(|e| e.extn_id == ID_CE_EXT_KEY_USAGE) {
+            Some(e) => Ok(Some(ExtendedKeyUsage::from_der(e.extn_value.as_bytes())?)),
+            None => Ok(None),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Intersects the certificate policies of every certificate in the path except the trust anchor,
+/// which does not participate in policy processing.
+fn path_policies(certificates: &[Certificate]) -> Result<PolicySet, Error> {
+    let mut valid: Option<PolicySet> = None;
+    for cert in certificates.iter().take(certificates.len() - 1) {
+        let policies = match &cert.tbs_certificate.extensions {
+            Some(extns) => match extns
+                .iter()
+                .find(|e| e.extn_id == ID_CE_CERTIFICATE_POLICIES)
+            {
+                Some(e) => {
+                    PolicySet::try_from(CertificatePolicies::from_der(e.extn_value.as_bytes())?)?
+                }
+                None => PolicySet::new(),
+            },
+            None => PolicySet::new(),
+        };
+        if policies.contains(&ANY_POLICY) {
+            continue;
+        }
+        valid = Some(match valid {
+            Some(v) => v.intersection(&policies),
+            None => policies,
+        });
+    }
+    Ok(valid.unwrap_or_else(|| policy_set![ANY_POLICY]))
+}
+
+/// Checks that every certificate above the target, including the trust anchor, is a CA allowed to
+/// sign certificates, and checks its path length and name constraints against the certificates
+/// below it. As in RFC 5280 Section 6.1, self-issued intermediates such as key rollover
+/// certificates do not count towards path length constraints and are not subject to name
+/// constraints. The target certificate always is.
+fn check_constraints(certificates: &[Certificate]) -> Result<(), Error> {
+    for cert in certificates {
+        check_critical_extensions(cert)?;
+    }
+    for (i, ca) in certificates.iter().enumerate().skip(1) {
+        // The trust anchor is trusted as is, such as a v1 root without basic constraints, but
+        // constraints it does assert still apply
+        let path_len = match i == certificates.len() - 1 {
+            true => anchor_path_len(ca)?,
+            false => check_issuer(ca)?,
+        };
+        if let Some(max) = path_len {
+            let intermediates = certificates[1..i]
+                .iter()
+                .filter(|c| !is_self_issued(c))
+                .count();
+            if intermediates > max as usize {
+                return Err(Error::Constraint);
+            }
+        }
+        if let Some(constraints) = TrustAnchor::try_from(ca)?.name_constraints() {
+            for (j, cert) in certificates[..i].iter().enumerate() {
+                if (j == 0 || !is_self_issued(cert)) && !constraints.permits_certificate(cert)? {
+                    return Err(Error::Constraint);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `cert` asserts `cA` in its basic constraints and, if it has a key usage extension,
+/// `keyCertSign`. Returns its path length constraint, which is only meaningful for a CA.
+fn check_issuer(cert: &Certificate) -> Result<Option<u8>, Error> {
+    let path_len = match cert.tbs_certificate.get::<BasicConstraints>()? {
+        Some((_, bc)) if bc.ca => bc.path_len_constraint,
+        _ => return Err(Error::Constraint),
+    };
+    match cert.tbs_certificate.get::<KeyUsage>()? {
+        Some((_, usage)) if !usage.key_cert_sign() => Err(Error::Constraint),
+        _ => Ok(path_len),
+    }
+}
+
+/// Returns the path length constraint of the trust anchor `cert`, if it asserts `cA`
+fn anchor_path_len(cert: &Certificate) -> Result<Option<u8>, Error> {
+    Ok(match cert.tbs_certificate.get::<BasicConstraints>()? {
+        Some((_, bc)) if bc.ca => bc.path_len_constraint,
+        _ => None,
+    })
+}
+
+/// Rejects `cert` if it carries a critical extension which is not processed
+fn check_critical_extensions(cert: &Certificate) -> Result<(), Error> {
+    match &cert.tbs_certificate.extensions {
+        Some(extns)
+            if extns
+                .iter()
+                .any(|e| e.critical && !KNOWN_EXTENSIONS.contains(&e.extn_id)) =>
+        {
+            Err(Error::Verification)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Intersects the validity periods of every certificate in the path
+fn path_validity(certificates: &[Certificate]) -> Validity {
+    let mut validity = certificates[0].tbs_certificate.validity;
+    for cert in &certificates[1..] {
+        let other = &cert.tbs_certificate.validity;
+        if other.not_before.to_unix_duration() > validity.not_before.to_unix_duration() {
+            validity.not_before = other.not_before;
+        }
+        if other.not_after.to_unix_duration() < validity.not_after.to_unix_duration() {
+            validity.not_after = other.not_after;
+        }
+    }
+    validity
+}
@@ -0,0 +1,623 @@
+//! PKCS#12 trust stores ([RFC 7292])
+//!
+//! Java and OpenSSL exchange trust stores as PKCS#12 files holding only certificate bags. Java
+//! marks trusted certificates with the Oracle `trustedKeyUsage` bag attribute.
+//!
+//! Safe contents may be unencrypted, encrypted with PBES2 (PBKDF2 and AES-CBC or 3DES-CBC) or
+//! with the legacy `pbeWithSHAAnd3-KeyTripleDES-CBC`. The integrity MAC may use SHA-1 or SHA-2.
+//! Private keys and other bag types are skipped. Like Java, iteration counts above 5,000,000 are
+//! rejected so that a crafted file can not stall the reader.
+//!
+//! [RFC 7292]: https://datatracker.ietf.org/doc/html/rfc7292
+
+use crate::{error::Error, loader::Loader, store::CertificateStore};
+use ::pkcs12::{
+    cert_type::CertBag,
+    digest_info::DigestInfo,
+    kdf::{derive_key_utf8, Pkcs12KeyType},
+    mac_data::MacData,
+    pbe_params::Pkcs12PbeParams,
+    pfx::{Pfx, Version},
+    PKCS_12_CERT_BAG_OID, PKCS_12_PBE_WITH_SHAAND3_KEY_TRIPLE_DES_CBC, PKCS_12_X509_CERT_OID,
+};
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use cbc::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use cms::{
+    content_info::{CmsVersion, ContentInfo},
+    encrypted_data::EncryptedData,
+    enveloped_data::EncryptedContentInfo,
+};
+use const_oid::{
+    db::{
+        rfc5280::ANY_EXTENDED_KEY_USAGE,
+        rfc5911::{ID_DATA, ID_ENCRYPTED_DATA},
+        rfc5912::{ID_SHA_1, ID_SHA_256, ID_SHA_384, ID_SHA_512},
+    },
+    ObjectIdentifier,
+};
+use der::{
+    asn1::{Any, BmpString, OctetString, SetOfVec},
+    Decode, Encode, Sequence,
+};
+use digest::{core_api::BlockSizeUser, Digest, FixedOutputReset};
+use hmac::{Mac, SimpleHmac};
+use pkcs5::{pbes2, EncryptionScheme};
+use rand_core::CryptoRngCore;
+use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
+use x509_verify::{
+    spki::AlgorithmIdentifierOwned,
+    x509_cert::{
+        attr::{Attribute, Attributes},
+        Certificate,
+    },
+};
+
+/// `friendlyName` as defined in [RFC 2985]
+///
+/// [RFC 2985]: https://datatracker.ietf.org/doc/html/rfc2985
+pub const PKCS_9_AT_FRIENDLY_NAME: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.20");
+
+/// Oracle `trustedKeyUsage` bag attribute, which Java uses to mark trusted certificates
+pub const ORACLE_TRUSTED_KEY_USAGE: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("2.16.840.1.113894.746875.1.1");
+
+/// Iterations of the key derivation and MAC functions used when encoding
+const ITERATIONS: u32 = 10_000;
+
+/// Highest iteration count accepted when decoding, the limit of Java's `PKCS12KeyStore`
+const MAX_ITERATIONS: i64 = 5_000_000;
+
+/// Certificate of a PKCS#12 trust store
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pkcs12Certificate {
+    /// Certificate of the bag
+    pub certificate: Certificate,
+
+    /// Value of the `friendlyName` bag attribute, the alias of the entry in Java
+    pub friendly_name: Option<String>,
+
+    /// Values of the Oracle `trustedKeyUsage` bag attribute. Java only treats certificates with
+    /// this attribute as trusted.
+    pub trusted_key_usage: Option<Vec<ObjectIdentifier>>,
+}
+
+/// Certificates of a PKCS#12 trust store
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pkcs12TrustStore {
+    /// Certificates of the trust store
+    pub certificates: Vec<Pkcs12Certificate>,
+}
+
+impl Pkcs12TrustStore {
+    /// Creates an empty [`Pkcs12TrustStore`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes a DER-encoded PKCS#12 file protected with `password`.
+    ///
+    /// `Error::Integrity` is returned when the file has no MAC, the MAC does not match or the
+    /// contents can not be decrypted, which is usually caused by a wrong password, and when an
+    /// iteration count is below one or above 5,000,000. Public-key integrity and privacy modes
+    /// are not supported.
+    pub fn from_der(data: &[u8], password: &str) -> Result<Self, Error> {
+        Self::decode(data, password, true)
+    }
+
+    /// Decodes a DER-encoded PKCS#12 file like [`Pkcs12TrustStore::from_der`], but also accepts
+    /// files without a MAC, whose contents are then not checked for integrity. A MAC which is
+    /// present is still verified.
+    pub fn from_der_unauthenticated(data: &[u8], password: &str) -> Result<Self, Error> {
+        Self::decode(data, password, false)
+    }
+
+    /// Decodes the file, failing if `require_mac` is set and the file has no MAC
+    fn decode(data: &[u8], password: &str, require_mac: bool) -> Result<Self, Error> {
+        let pfx = Pfx::from_der(data)?;
+        if pfx.auth_safe.content_type != ID_DATA {
+            return Err(Error::Integrity);
+        }
+        let auth_safe = pfx.auth_safe.content.decode_as::<OctetString>()?;
+        match &pfx.mac_data {
+            Some(mac_data) => verify_mac(mac_data, password, auth_safe.as_bytes())?,
+            None if require_mac => return Err(Error::Integrity),
+            None => {}
+        }
+
+        let mut certificates = Vec::new();
+        for info in Vec::<ContentInfo>::from_der(auth_safe.as_bytes())? {
+            let contents = match info.content_type {
+                ID_DATA => info.content.decode_as::<OctetString>()?.into_bytes(),
+                ID_ENCRYPTED_DATA => decrypt(&info.content.decode_as()?, password)?,
+                _ => continue,
+            };
+            for bag in Vec::<SafeBag>::from_der(&contents)? {
+                if bag.bag_id != PKCS_12_CERT_BAG_OID {
+                    continue;
+                }
+                let cert_bag = bag.bag_value.decode_as::<CertBag>()?;
+                if cert_bag.cert_id != PKCS_12_X509_CERT_OID {
+                    continue;
+                }
+                let mut entry = Pkcs12Certificate {
+                    certificate: Certificate::from_der(cert_bag.cert_value.as_bytes())?,
+                    friendly_name: None,
+                    trusted_key_usage: None,
+                };
+                for attr in bag.bag_attributes.iter().flat_map(|a| a.iter()) {
+                    match attr.oid {
+                        PKCS_9_AT_FRIENDLY_NAME => {
+                            if let Some(value) = attr.values.get(0) {
+                                entry.friendly_name =
+                                    Some(value.decode_as::<BmpString>()?.to_string());
+                            }
+                        }
+                        ORACLE_TRUSTED_KEY_USAGE => {
+                            entry.trusted_key_usage = Some(
+                                attr.values
+                                    .iter()
+                                    .map(|v| v.decode_as::<ObjectIdentifier>())
+                                    .collect::<Result<_, _>>()?,
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                certificates.push(entry);
+            }
+        }
+        Ok(Self { certificates })
+    }
+
+    /// Encodes the trust store as DER protected with `password`. Certificates are encrypted with
+    /// PBES2, using PBKDF2 with HMAC-SHA-256 and AES-256-CBC, and the MAC uses SHA-256. `rng`
+    /// provides the salts and the initialization vector.
+    pub fn to_der(&self, password: &str, rng: &mut impl CryptoRngCore) -> Result<Vec<u8>, Error> {
+        let mut bags = Vec::new();
+        for entry in &self.certificates {
+            let cert_bag = CertBag {
+                cert_id: PKCS_12_X509_CERT_OID,
+                cert_value: OctetString::new(entry.certificate.to_der()?)?,
+            };
+            let mut attributes = Vec::new();
+            if let Some(name) = &entry.friendly_name {
+                attributes.push(Attribute {
+                    oid: PKCS_9_AT_FRIENDLY_NAME,
+                    values: SetOfVec::try_from(vec![Any::encode_from(&BmpString::from_utf8(
+                        name,
+                    )?)?])?,
+                });
+            }
+            if let Some(usage) = &entry.trusted_key_usage {
+                attributes.push(Attribute {
+                    oid: ORACLE_TRUSTED_KEY_USAGE,
+                    values: SetOfVec::try_from(
+                        usage
+                            .iter()
+                            .map(Any::encode_from)
+                            .collect::<Result<Vec<_>, _>>()?,
+                    )?,
+                });
+            }
+            bags.push(SafeBag {
+                bag_id: PKCS_12_CERT_BAG_OID,
+                bag_value: Any::encode_from(&cert_bag)?,
+                bag_attributes: match attributes.is_empty() {
+                    true => None,
+                    false => Some(SetOfVec::try_from(attributes)?),
+                },
+            });
+        }
+
+        let mut salt = [0u8; 16];
+        let mut iv = [0u8; 16];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut iv);
+        let scheme = EncryptionScheme::from(
+            pbes2::Parameters::pbkdf2_sha256_aes256cbc(ITERATIONS, &salt, &iv)
+                .map_err(|_| Error::Integrity)?,
+        );
+        let encrypted = scheme
+            .encrypt(password, &bags.to_der()?)
+            .map_err(|_| Error::Integrity)?;
+        let encrypted_data = EncryptedData {
+            version: CmsVersion::V0,
+            enc_content_info: EncryptedContentInfo {
+                content_type: ID_DATA,
+                content_enc_alg: AlgorithmIdentifierOwned::from_der(&scheme.to_der()?)?,
+                encrypted_content: Some(OctetString::new(encrypted)?),
+            },
+            unprotected_attrs: None,
+        };
+        let auth_safe = vec![ContentInfo {
+            content_type: ID_ENCRYPTED_DATA,
+            content: Any::encode_from(&encrypted_data)?,
+        }]
+        .to_der()?;
+
+        let mut mac_salt = [0u8; 16];
+        rng.fill_bytes(&mut mac_salt);
+        let mac = hmac::<Sha256>(password, &mac_salt, ITERATIONS.into(), &auth_safe)?
+            .finalize()
+            .into_bytes()
+            .to_vec();
+        let pfx = Pfx {
+            version: Version::V3,
+            auth_safe: ContentInfo {
+                content_type: ID_DATA,
+                content: Any::encode_from(&OctetString::new(auth_safe)?)?,
+            },
+            mac_data: Some(MacData {
+                mac: DigestInfo {
+                    algorithm: AlgorithmIdentifierOwned {
+                        oid: ID_SHA_256,
+                        parameters: Some(Any::null()),
+                    },
+                    digest: OctetString::new(mac)?,
+                },
+                mac_salt: OctetString::new(mac_salt.to_vec())?,
+                iterations: ITERATIONS as i32,
+            }),
+        };
+        Ok(pfx.to_der()?)
+    }
+}
+
+impl<Id, L> CertificateStore<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Inserts the trusted certificates of a DER-encoded PKCS#12 trust store into the certificate
+    /// store. Friendly names are kept as the certificates' labels. See
+    /// [`Pkcs12TrustStore::from_der`].
+    ///
+    /// As in Java, only certificates with the Oracle `trustedKeyUsage` attribute are trusted.
+    /// Other certificate bags, such as the chain of a private key entry, are skipped. The store
+    /// can not restrict an anchor to some purposes, so certificates which are only trusted for
+    /// specific extended key usages rather than `anyExtendedKeyUsage` are skipped as well.
+    pub fn import_pkcs12(&mut self, data: &[u8], password: &str) -> Result<(), Error>
+    where
+        Error: for<'a> From<<Id as TryFrom<&'a Certificate>>::Error>,
+    {
+        let trust_store = Pkcs12TrustStore::from_der(data, password)?;
+        for entry in trust_store.certificates {
+            match &entry.trusted_key_usage {
+                Some(usage) if usage.contains(&ANY_EXTENDED_KEY_USAGE) => {}
+                _ => continue,
+            }
+            let id = Id::try_from(&entry.certificate)?;
+            self.insert::<Error>(entry.certificate)?;
+            if let Some(name) = entry.friendly_name {
+                self.set_label(id, name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a PKCS#12 trust store of every certificate in the store. Labels become friendly
+    /// names and every certificate is trusted for any extended key usage.
+    pub fn to_pkcs12(&self) -> Pkcs12TrustStore {
+        Pkcs12TrustStore {
+            certificates: self
+                .iter()
+                .map(|(id, cert)| Pkcs12Certificate {
+                    certificate: cert.clone(),
+                    friendly_name: self.label(id).map(String::from),
+                    trusted_key_usage: Some(vec![ANY_EXTENDED_KEY_USAGE]),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Checks the MAC over the authenticated safe
+fn verify_mac(mac_data: &MacData, password: &str, auth_safe: &[u8]) -> Result<(), Error> {
+    let salt = mac_data.mac_salt.as_bytes();
+    let iterations = mac_data.iterations.into();
+    let expected = mac_data.mac.digest.as_bytes();
+    // verify_slice compares in constant time
+    let verified = match mac_data.mac.algorithm.oid {
+        ID_SHA_1 => hmac::<Sha1>(password, salt, iterations, auth_safe)?.verify_slice(expected),
+        ID_SHA_256 => hmac::<Sha256>(password, salt, iterations, auth_safe)?.verify_slice(expected),
+        ID_SHA_384 => hmac::<Sha384>(password, salt, iterations, auth_safe)?.verify_slice(expected),
+        ID_SHA_512 => hmac::<Sha512>(password, salt, iterations, auth_safe)?.verify_slice(expected),
+        _ => return Err(Error::Integrity),
+    };
+    verified.map_err(|_| Error::Integrity)
+}
+
+/// Returns the HMAC of `data` keyed by the PKCS#12 key derivation function
+fn hmac<D>(
+    password: &str,
+    salt: &[u8],
+    iterations: i64,
+    data: &[u8],
+) -> Result<SimpleHmac<D>, Error>
+where
+    D: Digest + FixedOutputReset + BlockSizeUser,
+{
+    let key = derive_key_utf8::<D>(
+        password,
+        salt,
+        Pkcs12KeyType::Mac,
+        check_iterations(iterations)?,
+        <D as Digest>::output_size(),
+    )?;
+    let mut mac = SimpleHmac::<D>::new_from_slice(&key).map_err(|_| Error::Integrity)?;
+    mac.update(data);
+    Ok(mac)
+}
+
+/// Rejects iteration counts below one or above [`MAX_ITERATIONS`] before they reach the key
+/// derivation function
+fn check_iterations(iterations: i64) -> Result<i32, Error> {
+    match (1..=MAX_ITERATIONS).contains(&iterations) {
+        true => i32::try_from(iterations).map_err(|_| Error::Integrity),
+        false => Err(Error::Integrity),
+    }
+}
+
+/// Decrypts safe contents encrypted with either PBES2 or `pbeWithSHAAnd3-KeyTripleDES-CBC`
+fn decrypt(data: &EncryptedData, password: &str) -> Result<Vec<u8>, Error> {
+    let info = &data.enc_content_info;
+    let ciphertext = info
+        .encrypted_content
+        .as_ref()
+        .ok_or(Error::Integrity)?
+        .as_bytes();
+    let algorithm = &info.content_enc_alg;
+    if algorithm.oid == PKCS_12_PBE_WITH_SHAAND3_KEY_TRIPLE_DES_CBC {
+        let params = algorithm
+            .parameters
+            .as_ref()
+            .ok_or(Error::Integrity)?
+            .decode_as::<Pkcs12PbeParams>()?;
+        let iterations = check_iterations(params.iterations.into())?;
+        let salt = params.salt.as_bytes();
+        let key =
+            derive_key_utf8::<Sha1>(password, salt, Pkcs12KeyType::EncryptionKey, iterations, 24)?;
+        let iv = derive_key_utf8::<Sha1>(password, salt, Pkcs12KeyType::Iv, iterations, 8)?;
+        cbc::Decryptor::<des::TdesEde3>::new_from_slices(&key, &iv)
+            .map_err(|_| Error::Integrity)?
+            .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+            .map_err(|_| Error::Integrity)
+    } else {
+        let der = algorithm.to_der()?;
+        let scheme = EncryptionScheme::from_der(&der)?;
+        match scheme.pbes2().map(|params| &params.kdf) {
+            Some(pbes2::Kdf::Pbkdf2(kdf)) => check_iterations(kdf.iteration_count.into())?,
+            _ => return Err(Error::Integrity),
+        };
+        scheme
+            .decrypt(password, ciphertext)
+            .map_err(|_| Error::Integrity)
+    }
+}
+
+/// The bag value is kept encoded as its type depends on the bag ID.
+///
+/// ```text
+/// SafeBag ::= SEQUENCE {
+///    bagId          BAG-TYPE.&id ({PKCS12BagSet}),
+///    bagValue       [0] EXPLICIT BAG-TYPE.&Type({PKCS12BagSet}{@bagId}),
+///    bagAttributes  SET OF PKCS12Attribute OPTIONAL }
+/// ```
+#[derive(Sequence)]
+struct SafeBag {
+    bag_id: ObjectIdentifier,
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT")]
+    bag_value: Any,
+    bag_attributes: Option<Attributes>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pkcs12Certificate, Pkcs12TrustStore};
+    use crate::{error::Error, name::NameBytes, MemCertificateStore};
+    use ::pkcs12::{pbe_params::Pkcs12PbeParams, pfx::Pfx};
+    use alloc::{vec, vec::Vec};
+    use cms::{content_info::ContentInfo, encrypted_data::EncryptedData};
+    use const_oid::db::{
+        rfc5280::{ANY_EXTENDED_KEY_USAGE, ID_KP_EMAIL_PROTECTION},
+        rfc5911::ID_ENCRYPTED_DATA,
+    };
+    use core::time::Duration;
+    use der::{
+        asn1::{Any, OctetString},
+        Decode, DecodePem, Encode,
+    };
+    use pkcs5::{pbes2, EncryptionScheme};
+    use rand_core::{CryptoRng, RngCore};
+    use x509_verify::{spki::AlgorithmIdentifierOwned, x509_cert::Certificate};
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf.pem");
+    const TRUSTSTORE: &[u8] = include_bytes!("../testdata/truststore.p12");
+    const TRUSTSTORE_LEGACY: &[u8] = include_bytes!("../testdata/truststore-legacy.p12");
+
+    /// 2025-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+    /// Deterministic, and therefore insecure, generator for tests only
+    struct CountingRng(u8);
+
+    impl RngCore for CountingRng {
+        fn next_u32(&mut self) -> u32 {
+            rand_core::impls::next_u32_via_fill(self)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            rand_core::impls::next_u64_via_fill(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for b in dest {
+                self.0 = self.0.wrapping_add(1);
+                *b = self.0;
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for CountingRng {}
+
+    /// Removes the MAC of `data` and passes the algorithm of each encrypted content to `update`
+    fn rewrite(data: &[u8], update: impl Fn(&mut AlgorithmIdentifierOwned)) -> Vec<u8> {
+        let mut pfx = Pfx::from_der(data).unwrap();
+        pfx.mac_data = None;
+        let auth_safe = pfx.auth_safe.content.decode_as::<OctetString>().unwrap();
+        let mut infos = Vec::<ContentInfo>::from_der(auth_safe.as_bytes()).unwrap();
+        for info in &mut infos {
+            if info.content_type == ID_ENCRYPTED_DATA {
+                let mut encrypted = info.content.decode_as::<EncryptedData>().unwrap();
+                update(&mut encrypted.enc_content_info.content_enc_alg);
+                info.content = Any::encode_from(&encrypted).unwrap();
+            }
+        }
+        let auth_safe = OctetString::new(infos.to_der().unwrap()).unwrap();
+        pfx.auth_safe.content = Any::encode_from(&auth_safe).unwrap();
+        pfx.to_der().unwrap()
+    }
+
+    #[test]
+    fn decode_trust_stores() {
+        let root = Certificate::from_pem(ROOT).unwrap();
+        for data in [TRUSTSTORE, TRUSTSTORE_LEGACY] {
+            let trust_store = Pkcs12TrustStore::from_der(data, "changeit").unwrap();
+            assert_eq!(trust_store.certificates.len(), 2);
+            let entry = &trust_store.certificates[0];
+            assert_eq!(entry.certificate, root);
+            assert_eq!(entry.friendly_name.as_deref(), Some("rscs test root"));
+            assert_eq!(entry.trusted_key_usage, Some(vec![ANY_EXTENDED_KEY_USAGE]));
+            assert!(matches!(
+                Pkcs12TrustStore::from_der(data, "wrong"),
+                Err(Error::Integrity)
+            ));
+        }
+    }
+
+    #[test]
+    fn store_import_export() {
+        let mut store = MemCertificateStore::new();
+        store.import_pkcs12(TRUSTSTORE, "changeit").unwrap();
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let id = NameBytes::try_from(&root).unwrap();
+        assert_eq!(store.label(&id), Some("rscs test root"));
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        assert!(store.verify_at(&leaf, &[], NOW).is_ok());
+
+        let der = store
+            .to_pkcs12()
+            .to_der("secret", &mut CountingRng(0))
+            .unwrap();
+        let mut imported = MemCertificateStore::new();
+        imported.import_pkcs12(&der, "secret").unwrap();
+        assert_eq!(imported.iter().count(), 2);
+        assert_eq!(imported.label(&id), Some("rscs test root"));
+        assert!(imported.remove(&id).is_some());
+        assert_eq!(imported.label(&id), None);
+    }
+
+    #[test]
+    fn untrusted_bags() {
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let mut trust_store = Pkcs12TrustStore::from_der(TRUSTSTORE, "changeit").unwrap();
+        trust_store.certificates.push(Pkcs12Certificate {
+            certificate: leaf.clone(),
+            friendly_name: None,
+            trusted_key_usage: None,
+        });
+        let der = trust_store.to_der("secret", &mut CountingRng(0)).unwrap();
+        let decoded = Pkcs12TrustStore::from_der(&der, "secret").unwrap();
+        assert_eq!(decoded.certificates.len(), 3);
+
+        // The leaf has no trustedKeyUsage attribute and is not imported
+        let mut store = MemCertificateStore::new();
+        store.import_pkcs12(&der, "secret").unwrap();
+        assert_eq!(store.iter().count(), 2);
+        assert!(store.iter().all(|(_, c)| *c != leaf));
+        assert!(store.iter().any(|(_, c)| *c == root));
+
+        // Trusted for email protection only, which an anchor of the store can not be limited to
+        trust_store.certificates[2].trusted_key_usage = Some(vec![ID_KP_EMAIL_PROTECTION]);
+        let der = trust_store.to_der("secret", &mut CountingRng(0)).unwrap();
+        let mut store = MemCertificateStore::new();
+        store.import_pkcs12(&der, "secret").unwrap();
+        assert_eq!(store.iter().count(), 2);
+        assert!(store.iter().all(|(_, c)| *c != leaf));
+    }
+
+    #[test]
+    fn missing_mac() {
+        for data in [TRUSTSTORE, TRUSTSTORE_LEGACY] {
+            let der = rewrite(data, |_| {});
+            assert!(matches!(
+                Pkcs12TrustStore::from_der(&der, "changeit"),
+                Err(Error::Integrity)
+            ));
+            let trust_store = Pkcs12TrustStore::from_der_unauthenticated(&der, "changeit").unwrap();
+            assert_eq!(trust_store.certificates.len(), 2);
+            // A MAC which is present is still checked
+            assert!(matches!(
+                Pkcs12TrustStore::from_der_unauthenticated(data, "wrong"),
+                Err(Error::Integrity)
+            ));
+        }
+        let mut store = MemCertificateStore::new();
+        assert!(matches!(
+            store.import_pkcs12(&rewrite(TRUSTSTORE, |_| {}), "changeit"),
+            Err(Error::Integrity)
+        ));
+    }
+
+    #[test]
+    fn reject_iterations() {
+        for iterations in [0, -1, 5_000_001, i32::MAX] {
+            let mut pfx = Pfx::from_der(TRUSTSTORE).unwrap();
+            pfx.mac_data.as_mut().unwrap().iterations = iterations;
+            assert!(matches!(
+                Pkcs12TrustStore::from_der(&pfx.to_der().unwrap(), "changeit"),
+                Err(Error::Integrity)
+            ));
+        }
+
+        // PBES2 with PBKDF2
+        let der = rewrite(TRUSTSTORE, |algorithm| {
+            let mut params =
+                pbes2::Parameters::pbkdf2_sha256_aes256cbc(1, &[1; 16], &[2; 16]).unwrap();
+            if let pbes2::Kdf::Pbkdf2(kdf) = &mut params.kdf {
+                kdf.iteration_count = u32::MAX;
+            }
+            let scheme = EncryptionScheme::from(params);
+            *algorithm = AlgorithmIdentifierOwned::from_der(&scheme.to_der().unwrap()).unwrap();
+        });
+        assert!(matches!(
+            Pkcs12TrustStore::from_der_unauthenticated(&der, "changeit"),
+            Err(Error::Integrity)
+        ));
+
+        // pbeWithSHAAnd3-KeyTripleDES-CBC
+        let der = rewrite(TRUSTSTORE_LEGACY, |algorithm| {
+            let parameters = algorithm.parameters.as_ref().unwrap();
+            let mut params = parameters.decode_as::<Pkcs12PbeParams>().unwrap();
+            params.iterations = i32::MAX;
+            algorithm.parameters = Some(Any::encode_from(&params).unwrap());
+        });
+        assert!(matches!(
+            Pkcs12TrustStore::from_der_unauthenticated(&der, "changeit"),
+            Err(Error::Integrity)
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+//! PKCS#7 certificate bundles
+//!
+//! Certificates and CRLs are exchanged as degenerate CMS `SignedData`, which carries no content
+//! and no signers. These are commonly found as `.p7b` or `.p7c` files and in AIA `caIssuers`
+//! responses.
+
+use crate::{error::Error, loader::Loader, path::VerifiedPath, store::CertificateStore};
+use alloc::{string::String, vec::Vec};
+use cms::{
+    cert::CertificateChoices,
+    content_info::{CmsVersion, ContentInfo},
+    revocation::{RevocationInfoChoice, RevocationInfoChoices},
+    signed_data::{CertificateSet, EncapsulatedContentInfo, SignedData, SignerInfos},
+};
+use const_oid::db::rfc5911::{ID_DATA, ID_SIGNED_DATA};
+use der::{
+    asn1::{Any, SetOfVec},
+    pem::{self, LineEnding},
+    Decode, Encode,
+};
+use x509_verify::x509_cert::{crl::CertificateList, Certificate};
+
+/// PEM label of PKCS#7 bundles
+const PEM_LABEL: &str = "PKCS7";
+
+/// PEM label of CMS structures, which some tools use for PKCS#7 bundles
+const PEM_LABEL_CMS: &str = "CMS";
+
+/// Certificates and CRLs carried by a PKCS#7 bundle
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pkcs7Bundle {
+    /// Certificates of the bundle
+    pub certificates: Vec<Certificate>,
+
+    /// Certificate revocation lists of the bundle
+    pub crls: Vec<CertificateList>,
+}
+
+impl Pkcs7Bundle {
+    /// Creates an empty [`Pkcs7Bundle`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes a PKCS#7 bundle from either PEM or DER
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let start = data
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(data.len());
+        if data[start..].starts_with(b"-----BEGIN") {
+            Self::from_pem(data)
+        } else {
+            Self::from_der(data)
+        }
+    }
+
+    /// Decodes a DER-encoded PKCS#7 bundle. Certificate and revocation formats other than X.509
+    /// certificates and CRLs are skipped.
+    pub fn from_der(data: &[u8]) -> Result<Self, Error> {
+        let info = ContentInfo::from_der(data)?;
+        if info.content_type != ID_SIGNED_DATA {
+            return Err(Error::Der(
+                der::ErrorKind::OidUnknown {
+                    oid: info.content_type,
+                }
+                .into(),
+            ));
+        }
+        let signed = info.content.decode_as::<SignedData>()?;
+        let certificates = signed
+            .certificates
+            .map(|set| {
+                set.0
+                    .into_vec()
+                    .into_iter()
+                    .filter_map(|c| match c {
+                        CertificateChoices::Certificate(c) => Some(c),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let crls = signed
+            .crls
+            .map(|set| {
+                set.0
+                    .into_vec()
+                    .into_iter()
+                    .filter_map(|c| match c {
+                        RevocationInfoChoice::Crl(c) => Some(c),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self { certificates, crls })
+    }
+
+    /// Decodes a PEM-encoded PKCS#7 bundle labeled either `PKCS7` or `CMS`
+    pub fn from_pem(data: &[u8]) -> Result<Self, Error> {
+        let (label, der) = pem::decode_vec(data).map_err(der::Error::from)?;
+        if label != PEM_LABEL && label != PEM_LABEL_CMS {
+            return Err(Error::Der(der::Error::from(pem::Error::Label)));
+        }
+        Self::from_der(&der)
+    }
+
+    /// Encodes the bundle as DER.
+    ///
+    /// Certificates and CRLs are encoded as a DER `SET OF` and are therefore sorted by their
+    /// encoding rather than kept in their original order.
+    pub fn to_der(&self) -> Result<Vec<u8>, Error> {
+        let certificates = SetOfVec::try_from(
+            self.certificates
+                .iter()
+                .cloned()
+                .map(CertificateChoices::Certificate)
+                .collect::<Vec<_>>(),
+        )?;
+        let crls = SetOfVec::try_from(
+            self.crls
+                .iter()
+                .cloned()
+                .map(RevocationInfoChoice::Crl)
+                .collect::<Vec<_>>(),
+        )?;
+        let signed = SignedData {
+            version: CmsVersion::V1,
+            digest_algorithms: SetOfVec::new(),
+            encap_content_info: EncapsulatedContentInfo {
+                econtent_type: ID_DATA,
+                econtent: None,
+            },
+            certificates: Some(CertificateSet(certificates)),
+            crls: match self.crls.is_empty() {
+                true => None,
+                false => Some(RevocationInfoChoices(crls)),
+            },
+            signer_infos: SignerInfos(SetOfVec::new()),
+        };
+        let info = ContentInfo {
+            content_type: ID_SIGNED_DATA,
+            content: Any::encode_from(&signed)?,
+        };
+        Ok(info.to_der()?)
+    }
+
+    /// Encodes the bundle as PEM with the `PKCS7` label. See [`Pkcs7Bundle::to_der`].
+    pub fn to_pem(&self) -> Result<String, Error> {
+        let der = self.to_der()?;
+        Ok(pem::encode_string(PEM_LABEL, LineEnding::LF, &der).map_err(der::Error::from)?)
+    }
+}
+
+impl<Id, L> CertificateStore<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Inserts every certificate of a PEM or DER PKCS#7 bundle into the certificate store.
+    /// The certificate revocation lists of the bundle are returned.
+    pub fn import_pkcs7(&mut self, data: &[u8]) -> Result<Vec<CertificateList>, Error>
+    where
+        Error: for<'a> From<<Id as TryFrom<&'a Certificate>>::Error>,
+    {
+        let bundle = Pkcs7Bundle::decode(data)?;
+        for cert in bundle.certificates {
+            self.insert::<Error>(cert)?;
+        }
+        Ok(bundle.crls)
+    }
+
+    /// Returns a PKCS#7 bundle of every certificate in the store
+    pub fn to_pkcs7(&self) -> Pkcs7Bundle {
+        Pkcs7Bundle {
+            certificates: self.iter().map(|(_, c)| c.clone()).collect(),
+            crls: Vec::new(),
+        }
+    }
+}
+
+impl<Id> VerifiedPath<Id> {
+    /// Returns a PKCS#7 bundle of every certificate in the path, including the trust anchor
+    pub fn to_pkcs7(&self) -> Pkcs7Bundle {
+        Pkcs7Bundle {
+            certificates: self.certificates().to_vec(),
+            crls: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pkcs7Bundle;
+    use crate::MemCertificateStore;
+    use core::time::Duration;
+    use der::DecodePem;
+    use x509_verify::x509_cert::Certificate;
+
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf.pem");
+    const CHAIN: &[u8] = include_bytes!("../testdata/chain.p7b");
+    const BUNDLE: &[u8] = include_bytes!("../testdata/bundle.p7b");
+
+    /// 2025-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+    #[test]
+    fn decode_bundles() {
+        let chain = Pkcs7Bundle::decode(CHAIN).unwrap();
+        assert_eq!(chain.certificates.len(), 2);
+        assert!(chain.crls.is_empty());
+        let bundle = Pkcs7Bundle::decode(BUNDLE).unwrap();
+        assert_eq!(bundle.certificates, chain.certificates);
+        assert_eq!(bundle.crls.len(), 1);
+        assert!(Pkcs7Bundle::decode(LEAF).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let bundle = Pkcs7Bundle::decode(BUNDLE).unwrap();
+        let pem = bundle.to_pem().unwrap();
+        assert_eq!(Pkcs7Bundle::decode(pem.as_bytes()).unwrap(), bundle);
+        let der = bundle.to_der().unwrap();
+        assert_eq!(Pkcs7Bundle::decode(&der).unwrap(), bundle);
+    }
+
+    #[test]
+    fn store_import_export() {
+        let mut store = MemCertificateStore::new();
+        let crls = store.import_pkcs7(BUNDLE).unwrap();
+        assert_eq!(crls.len(), 1);
+        assert_eq!(store.to_pkcs7().certificates.len(), 2);
+
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let path = store.verify_at(&leaf, &[], NOW).unwrap();
+        let exported = path.to_pkcs7();
+        assert_eq!(exported.certificates.len(), 2);
+        assert!(exported.certificates.contains(&leaf));
+    }
+}
@@ -0,0 +1,138 @@
+//! Thread-safe certificate store
+
+use crate::{error::Error, loader::Loader, path::VerifiedPath, store::CertificateStore};
+use arc_swap::ArcSwap;
+use core::time::Duration;
+use std::sync::{Arc, Mutex};
+use x509_verify::x509_cert::{name::Name, Certificate};
+
+/// Certificate store which can be shared between threads and updated while in use.
+///
+/// Readers take a snapshot of the current [`CertificateStore`], which is never blocked by
+/// writers. Writers modify a private copy of the store and atomically swap it in once the whole
+/// batch of changes has been applied. Verifications which are in-flight during an update finish
+/// against the snapshot they started with.
+#[derive(Debug)]
+pub struct SharedCertificateStore<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    current: ArcSwap<CertificateStore<Id, L>>,
+    writer: Mutex<()>,
+}
+
+impl<Id, L> SharedCertificateStore<Id, L>
+where
+    L: Loader<Id> + Clone,
+    Id: Clone + Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Creates a [`SharedCertificateStore`] initialized with `store`
+    pub fn new(store: CertificateStore<Id, L>) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(store),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Returns the current snapshot of the certificate store. The snapshot is unaffected by any
+    /// later updates.
+    pub fn snapshot(&self) -> Arc<CertificateStore<Id, L>> {
+        self.current.load_full()
+    }
+
+    /// Replaces the entire certificate store. Returns the previous snapshot.
+    pub fn replace(&self, store: CertificateStore<Id, L>) -> Arc<CertificateStore<Id, L>> {
+        let _guard = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        self.current.swap(Arc::new(store))
+    }
+
+    /// Applies a batch of changes to the certificate store. `f` operates on a copy of the current
+    /// store, which is published atomically once `f` returns. Readers never observe a partially
+    /// applied batch.
+    pub fn update<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut CertificateStore<Id, L>) -> T,
+    {
+        let _guard = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let mut store = CertificateStore::clone(&self.current.load());
+        let result = f(&mut store);
+        self.current.store(Arc::new(store));
+        result
+    }
+
+    /// Applies a batch of changes to the certificate store like [`SharedCertificateStore::update`].
+    /// The changes are only published if `f` succeeds. Otherwise, the store is left untouched.
+    pub fn try_update<F, T, E>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut CertificateStore<Id, L>) -> Result<T, E>,
+    {
+        let _guard = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let mut store = CertificateStore::clone(&self.current.load());
+        let result = f(&mut store)?;
+        self.current.store(Arc::new(store));
+        Ok(result)
+    }
+
+    /// Verifies `cert` against the current snapshot at `time`. See
+    /// [`CertificateStore::verify_at`] for details.
+    pub fn verify_at(
+        &self,
+        cert: &Certificate,
+        chain: &[&Certificate],
+        time: Duration,
+    ) -> Result<VerifiedPath<Id>, Error>
+    where
+        Id: for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        self.current.load().verify_at(cert, chain, time)
+    }
+
+    /// Verifies `cert` against the current snapshot at the current system time. See
+    /// [`CertificateStore::verify_at`] for details.
+    pub fn verify(
+        &self,
+        cert: &Certificate,
+        chain: &[&Certificate],
+    ) -> Result<VerifiedPath<Id>, Error>
+    where
+        Id: for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        self.current.load().verify(cert, chain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SharedCertificateStore;
+    use crate::{error::Error, MemCertificateStore};
+    use der::DecodePem;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+
+    #[test]
+    fn snapshots_are_isolated() {
+        let shared = SharedCertificateStore::new(MemCertificateStore::new());
+        let before = shared.snapshot();
+        shared
+            .try_update(|store| {
+                store.insert::<Error>(Certificate::from_pem(ROOT)?)?;
+                store.insert::<Error>(Certificate::from_pem(INTERMEDIATE)?)
+            })
+            .unwrap();
+        assert_eq!(before.iter().count(), 0);
+        assert_eq!(shared.snapshot().iter().count(), 2);
+
+        // A failed batch is not published
+        let result: Result<(), Error> = shared.try_update(|store| {
+            store.insert::<Error>(Certificate::from_pem(ROOT)?)?;
+            Err(Error::Verification)
+        });
+        assert!(result.is_err());
+        assert_eq!(shared.snapshot().generation(), 2);
+    }
+}
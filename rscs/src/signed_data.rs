@@ -0,0 +1,487 @@
+//! CMS `SignedData` signature verification ([RFC 5652])
+//!
+//! Signers are located among the certificates embedded in the `SignedData` and validated to a
+//! trust anchor of a [`CertificateStore`]. This is the format produced by `openssl cms -sign` and
+//! used for detached code and firmware signatures.
+//!
+//! [RFC 5652]: https://datatracker.ietf.org/doc/html/rfc5652
+
+use crate::{
+    error::Error,
+    loader::Loader,
+    path::VerifiedPath,
+    store::CertificateStore,
+    timestamp::{Timestamp, ID_AA_TIME_STAMP_TOKEN},
+};
+use alloc::vec::Vec;
+use cms::{
+    cert::CertificateChoices,
+    content_info::ContentInfo,
+    signed_data::{SignedData, SignerIdentifier, SignerInfo},
+};
+use const_oid::{
+    db::{
+        rfc5280::ID_KP_CODE_SIGNING,
+        rfc5911::{ID_CONTENT_TYPE, ID_DATA, ID_MESSAGE_DIGEST, ID_SIGNED_DATA, ID_SIGNING_TIME},
+        rfc5912::{
+            ID_SHA_1, ID_SHA_256, ID_SHA_384, ID_SHA_512, RSA_ENCRYPTION,
+            SHA_1_WITH_RSA_ENCRYPTION, SHA_256_WITH_RSA_ENCRYPTION, SHA_384_WITH_RSA_ENCRYPTION,
+            SHA_512_WITH_RSA_ENCRYPTION,
+        },
+    },
+    ObjectIdentifier,
+};
+use core::time::Duration;
+use der::{
+    asn1::{Any, OctetString},
+    pem,
+    referenced::OwnedToRef,
+    Decode, Encode,
+};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use x509_verify::{
+    spki::AlgorithmIdentifierOwned,
+    x509_cert::{ext::pkix::SubjectKeyIdentifier, name::Name, time::Time, Certificate},
+    Message, Signature, VerifyInfo, VerifyingKey,
+};
+
+/// Content and signers of a verified `SignedData`
+///
+/// With the `serde` feature, the content itself is not serialized.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VerifiedSignedData<Id> {
+    /// Content type of the signed content
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::snapshot::serialize_oid")
+    )]
+    pub content_type: ObjectIdentifier,
+
+    /// Signed content, either encapsulated or detached
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub content: Vec<u8>,
+
+    /// Every signer of the content, in the order of the `SignerInfo`s
+    pub signers: Vec<VerifiedSigner<Id>>,
+}
+
+/// Signer of a verified `SignedData`
+///
+/// With the `serde` feature, times serialize as UNIX timestamps.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VerifiedSigner<Id> {
+    /// Path from the signer's certificate to the trust anchor
+    pub path: VerifiedPath<Id>,
+
+    /// Value of the `signing-time` signed attribute, as the duration since the UNIX epoch
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::snapshot::serialize_opt_unix_time")
+    )]
+    pub signing_time: Option<Duration>,
+
+    /// Verified RFC 3161 timestamp of the signature, when timestamps are used
+    pub timestamp: Option<Timestamp<Id>>,
+
+    /// Time the signer's certificate path was validated at, as the duration since the UNIX epoch
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::snapshot::serialize_unix_time")
+    )]
+    pub validation_time: Duration,
+}
+
+/// Verifies CMS `SignedData` against a [`CertificateStore`].
+///
+/// Every `SignerInfo` must carry a valid signature made by an embedded certificate which chains
+/// to the store and is valid for the configured purpose, `id-kp-codeSigning` by default.
+#[derive(Clone, Debug)]
+pub struct SignedDataVerifier<'s, Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    store: &'s CertificateStore<Id, L>,
+    time: Duration,
+    purpose: ObjectIdentifier,
+    use_signing_time: bool,
+    use_timestamps: bool,
+}
+
+impl<'s, Id, L> SignedDataVerifier<'s, Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Creates a [`SignedDataVerifier`] which validates signers at `time`, given as the duration
+    /// since the UNIX epoch.
+    pub fn new(store: &'s CertificateStore<Id, L>, time: Duration) -> Self {
+        Self {
+            store,
+            time,
+            purpose: ID_KP_CODE_SIGNING,
+            use_signing_time: false,
+            use_timestamps: false,
+        }
+    }
+
+    /// Sets the extended key usage the signers' certificate paths must permit
+    pub fn purpose(mut self, purpose: ObjectIdentifier) -> Self {
+        self.purpose = purpose;
+        self
+    }
+
+    /// Validates each signer at its `signing-time` attribute, when present, instead of the
+    /// verifier's time. The attribute is asserted by the signer itself and should only be
+    /// trusted where the signer's key is known not to have been compromised.
+    pub fn use_signing_time(mut self, enabled: bool) -> Self {
+        self.use_signing_time = enabled;
+        self
+    }
+
+    /// Validates each signer at the `genTime` of its RFC 3161 timestamp, carried in the
+    /// `id-aa-timeStampToken` unsigned attribute, when present. Timestamps take precedence over
+    /// the `signing-time` attribute. The timestamp authority is validated at the verifier's
+    /// time. See [`CertificateStore::verify_timestamp`].
+    pub fn use_timestamps(mut self, enabled: bool) -> Self {
+        self.use_timestamps = enabled;
+        self
+    }
+
+    /// Verifies a PEM or DER-encoded `ContentInfo` holding `SignedData`. `detached` must be given
+    /// when the content is not encapsulated.
+    ///
+    /// `Error::Verification` is returned when there are no signers, a signer's certificate can
+    /// not be found, the signed attributes do not match the content, or are missing while the
+    /// content is not `id-data`, or a signature is invalid. Errors validating a signer's
+    /// certificate path are returned as is.
+    pub fn verify(
+        &self,
+        data: &[u8],
+        detached: Option<&[u8]>,
+    ) -> Result<VerifiedSignedData<Id>, Error>
+    where
+        Id: Clone + for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        let signed = decode(data)?;
+        let content = match (encapsulated_content(&signed)?, detached) {
+            (Some(content), None) => content,
+            (None, Some(detached)) => detached.to_vec(),
+            _ => return Err(Error::Verification),
+        };
+        let content_type = signed.encap_content_info.econtent_type;
+        let certificates = embedded_certificates(&signed);
+
+        if signed.signer_infos.0.is_empty() {
+            return Err(Error::Verification);
+        }
+        let mut signers = Vec::new();
+        for signer in signed.signer_infos.0.iter() {
+            let cert = find_signer(&signer.sid, &certificates)?.ok_or(Error::Verification)?;
+            let signing_time = verify_signer(signer, cert, content_type, &content)?;
+            let timestamp = match self.use_timestamps {
+                true => signer_timestamp(self.store, signer, self.time)?,
+                false => None,
+            };
+            let validation_time = match (&timestamp, self.use_signing_time, signing_time) {
+                (Some(timestamp), _, _) => timestamp.gen_time,
+                (None, true, Some(signing_time)) => signing_time,
+                _ => self.time,
+            };
+            let path = self.store.verify_at(cert, &certificates, validation_time)?;
+            path.check_purpose(self.purpose)?;
+            signers.push(VerifiedSigner {
+                path,
+                signing_time,
+                timestamp,
+                validation_time,
+            });
+        }
+        Ok(VerifiedSignedData {
+            content_type,
+            content,
+            signers,
+        })
+    }
+}
+
+/// Decodes a PEM `CMS` or `PKCS7` structure, or a DER one, holding `SignedData`
+pub(crate) fn decode(data: &[u8]) -> Result<SignedData, Error> {
+    let start = data
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(data.len());
+    let info = if data[start..].starts_with(b"-----BEGIN") {
+        let (label, der) = pem::decode_vec(data).map_err(der::Error::from)?;
+        if label != "CMS" && label != "PKCS7" {
+            return Err(Error::Der(der::Error::from(pem::Error::Label)));
+        }
+        ContentInfo::from_der(&der)?
+    } else {
+        ContentInfo::from_der(data)?
+    };
+    if info.content_type != ID_SIGNED_DATA {
+        return Err(Error::Verification);
+    }
+    Ok(info.content.decode_as::<SignedData>()?)
+}
+
+/// Returns the encapsulated content of `signed`, if any
+pub(crate) fn encapsulated_content(signed: &SignedData) -> Result<Option<Vec<u8>>, Error> {
+    match &signed.encap_content_info.econtent {
+        Some(econtent) => Ok(Some(
+            OctetString::from_der(&econtent.to_der()?)?.into_bytes(),
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Returns the X.509 certificates embedded in `signed`
+pub(crate) fn embedded_certificates(signed: &SignedData) -> Vec<&Certificate> {
+    signed
+        .certificates
+        .iter()
+        .flat_map(|set| set.0.iter())
+        .filter_map(|c| match c {
+            CertificateChoices::Certificate(c) => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Verifies the timestamp of `signer`'s signature, if any
+fn signer_timestamp<Id, L>(
+    store: &CertificateStore<Id, L>,
+    signer: &SignerInfo,
+    time: Duration,
+) -> Result<Option<Timestamp<Id>>, Error>
+where
+    L: Loader<Id>,
+    Id: Clone + Eq + for<'a> TryFrom<&'a Certificate> + for<'a> TryFrom<&'a Name>,
+    Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+{
+    let token = signer
+        .unsigned_attrs
+        .iter()
+        .flat_map(|attrs| attrs.iter())
+        .find(|a| a.oid == ID_AA_TIME_STAMP_TOKEN)
+        .and_then(|a| a.values.get(0));
+    match token {
+        Some(token) => Ok(Some(store.verify_timestamp(
+            &token.to_der()?,
+            signer.signature.as_bytes(),
+            time,
+        )?)),
+        None => Ok(None),
+    }
+}
+
+/// Finds the certificate identified by `sid`, either by issuer and serial number or by subject
+/// key identifier
+pub(crate) fn find_signer<'c>(
+    sid: &SignerIdentifier,
+    certificates: &[&'c Certificate],
+) -> Result<Option<&'c Certificate>, Error> {
+    for cert in certificates {
+        let found = match sid {
+            SignerIdentifier::IssuerAndSerialNumber(id) => {
+                id.issuer == cert.tbs_certificate.issuer
+                    && id.serial_number == cert.tbs_certificate.serial_number
+            }
+            SignerIdentifier::SubjectKeyIdentifier(id) => {
+                match cert.tbs_certificate.get::<SubjectKeyIdentifier>()? {
+                    Some((_, ski)) => ski == *id,
+                    None => false,
+                }
+            }
+        };
+        if found {
+            return Ok(Some(cert));
+        }
+    }
+    Ok(None)
+}
+
+/// Checks the signed attributes and the signature of `signer` over `content`. Returns the
+/// `signing-time` attribute, if any.
+pub(crate) fn verify_signer(
+    signer: &SignerInfo,
+    cert: &Certificate,
+    content_type: ObjectIdentifier,
+    content: &[u8],
+) -> Result<Option<Duration>, Error> {
+    let mut signing_time = None;
+    let message = match &signer.signed_attrs {
+        Some(attrs) => {
+            let digest = digest(signer.digest_alg.oid, content)?;
+            let value = |oid| -> Result<&Any, Error> {
+                let mut found = attrs.iter().filter(|a| a.oid == oid);
+                match (found.next(), found.next()) {
+                    (Some(attr), None) if attr.values.len() == 1 => {
+                        attr.values.get(0).ok_or(Error::Verification)
+                    }
+                    _ => Err(Error::Verification),
+                }
+            };
+            if value(ID_CONTENT_TYPE)?.decode_as::<ObjectIdentifier>()? != content_type {
+                return Err(Error::Verification);
+            }
+            if value(ID_MESSAGE_DIGEST)?
+                .decode_as::<OctetString>()?
+                .as_bytes()
+                != digest
+            {
+                return Err(Error::Verification);
+            }
+            if attrs.iter().any(|a| a.oid == ID_SIGNING_TIME) {
+                let time = Time::from_der(&value(ID_SIGNING_TIME)?.to_der()?)?;
+                signing_time = Some(time.to_unix_duration());
+            }
+            attrs.to_der()?
+        }
+        // Signed attributes may only be omitted for id-data content (RFC 5652 Section 5.3)
+        None if content_type == ID_DATA => content.to_vec(),
+        None => return Err(Error::Verification),
+    };
+
+    let algorithm = signature_algorithm(signer);
+    let key = VerifyingKey::try_from(cert.tbs_certificate.subject_public_key_info.owned_to_ref())
+        .map_err(|_| Error::Verification)?;
+    key.verify(VerifyInfo::new(
+        Message::from(message),
+        Signature::new(&algorithm, signer.signature.as_bytes()),
+    ))
+    .map_err(|_| Error::Verification)?;
+    Ok(signing_time)
+}
+
+/// Hashes `content` with the digest algorithm `oid`
+pub(crate) fn digest(oid: ObjectIdentifier, content: &[u8]) -> Result<Vec<u8>, Error> {
+    Ok(match oid {
+        ID_SHA_1 => Sha1::digest(content).to_vec(),
+        ID_SHA_256 => Sha256::digest(content).to_vec(),
+        ID_SHA_384 => Sha384::digest(content).to_vec(),
+        ID_SHA_512 => Sha512::digest(content).to_vec(),
+        _ => return Err(Error::Verification),
+    })
+}
+
+/// Returns the signature algorithm of `signer`. CMS allows RSA PKCS#1 v1.5 signatures to be
+/// identified by `rsaEncryption` alone, with the hash given by the digest algorithm.
+fn signature_algorithm(signer: &SignerInfo) -> AlgorithmIdentifierOwned {
+    if signer.signature_algorithm.oid != RSA_ENCRYPTION {
+        return signer.signature_algorithm.clone();
+    }
+    let oid = match signer.digest_alg.oid {
+        ID_SHA_1 => SHA_1_WITH_RSA_ENCRYPTION,
+        ID_SHA_256 => SHA_256_WITH_RSA_ENCRYPTION,
+        ID_SHA_384 => SHA_384_WITH_RSA_ENCRYPTION,
+        ID_SHA_512 => SHA_512_WITH_RSA_ENCRYPTION,
+        _ => return signer.signature_algorithm.clone(),
+    };
+    AlgorithmIdentifierOwned {
+        oid,
+        parameters: Some(Any::null()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignedDataVerifier;
+    use crate::{error::Error, MemCertificateStore};
+    use const_oid::db::rfc5280::ID_KP_EMAIL_PROTECTION;
+    use core::time::Duration;
+    use der::DecodePem;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const MANIFEST: &[u8] = include_bytes!("../testdata/manifest.txt");
+    const SIGNED: &[u8] = include_bytes!("../testdata/manifest.p7s");
+    const SIGNED_SKI: &[u8] = include_bytes!("../testdata/manifest-ski.p7s");
+    const SIGNED_LEAF: &[u8] = include_bytes!("../testdata/manifest-leaf.p7s");
+    const SIGNED_NO_ATTRS: &[u8] = include_bytes!("../testdata/manifest-noattr-data.p7s");
+    const SIGNED_NO_ATTRS_TST: &[u8] = include_bytes!("../testdata/manifest-noattr-tst.p7s");
+
+    /// 2027-01-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_798_761_600);
+
+    fn store() -> MemCertificateStore {
+        let mut store = MemCertificateStore::new();
+        store
+            .insert::<Error>(Certificate::from_pem(ROOT).unwrap())
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn encapsulated() {
+        let store = store();
+        let verifier = SignedDataVerifier::new(&store, NOW);
+        for data in [SIGNED, SIGNED_SKI] {
+            let verified = verifier.verify(data, None).unwrap();
+            assert_eq!(verified.content, MANIFEST);
+            assert_eq!(verified.signers.len(), 1);
+            assert!(verified.signers[0].signing_time.is_some());
+            assert_eq!(verified.signers[0].validation_time, NOW);
+        }
+        assert!(matches!(
+            verifier.verify(SIGNED, Some(MANIFEST)),
+            Err(Error::Verification)
+        ));
+        assert!(matches!(
+            verifier
+                .purpose(ID_KP_EMAIL_PROTECTION)
+                .verify(SIGNED, None),
+            Err(Error::InvalidPurpose)
+        ));
+
+        // The signer's certificate expires in 2034
+        let later = Duration::from_secs(2_051_222_400);
+        let verifier = SignedDataVerifier::new(&store, later);
+        assert!(matches!(verifier.verify(SIGNED, None), Err(Error::Expired)));
+        let verified = verifier
+            .use_signing_time(true)
+            .verify(SIGNED, None)
+            .unwrap();
+        let signer = &verified.signers[0];
+        assert_eq!(Some(signer.validation_time), signer.signing_time);
+    }
+
+    #[test]
+    fn detached() {
+        let store = store();
+        let verifier = SignedDataVerifier::new(&store, NOW);
+        assert!(matches!(
+            verifier.verify(SIGNED_LEAF, None),
+            Err(Error::Verification)
+        ));
+        assert!(matches!(
+            verifier.verify(SIGNED_LEAF, Some(b"tampered manifest\n")),
+            Err(Error::Verification)
+        ));
+        // The leaf certificate is only valid for TLS server authentication
+        assert!(matches!(
+            verifier.verify(SIGNED_LEAF, Some(MANIFEST)),
+            Err(Error::InvalidPurpose)
+        ));
+    }
+
+    #[test]
+    fn without_signed_attributes() {
+        let store = store();
+        let verifier = SignedDataVerifier::new(&store, NOW);
+        let verified = verifier.verify(SIGNED_NO_ATTRS, None).unwrap();
+        assert_eq!(verified.content, MANIFEST);
+        assert!(verified.signers[0].signing_time.is_none());
+
+        // The same signature over content which is not id-data
+        assert!(matches!(
+            verifier.verify(SIGNED_NO_ATTRS_TST, None),
+            Err(Error::Verification)
+        ));
+    }
+}
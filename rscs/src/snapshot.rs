@@ -0,0 +1,225 @@
+//! Serializable certificate store snapshots
+//!
+//! A [`StoreSnapshot`] holds the DER encoding of every certificate in a [`CertificateStore`]
+//! along with its metadata. Certificates are written as base64 in human-readable formats, such as
+//! JSON, so that a store can be logged and later reloaded without going through PEM.
+
+use crate::{error::Error, loader::Loader, store::CertificateStore};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use base64ct::{Base64, Encoding};
+#[cfg(feature = "pkcs7")]
+use const_oid::ObjectIdentifier;
+use core::fmt;
+#[cfg(feature = "pkcs7")]
+use core::time::Duration;
+use der::{Decode, Encode};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use x509_verify::x509_cert::Certificate;
+
+/// Serializable snapshot of the certificates in a [`CertificateStore`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreSnapshot {
+    /// Certificates of the store
+    pub certificates: Vec<SnapshotEntry>,
+}
+
+/// Certificate of a [`StoreSnapshot`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    /// DER-encoded certificate
+    #[serde(serialize_with = "serialize_der", deserialize_with = "deserialize_der")]
+    pub certificate: Vec<u8>,
+
+    /// Subject of the certificate as an RFC 4514 string. Informational only, it is ignored when
+    /// the snapshot is loaded.
+    #[serde(default)]
+    pub subject: String,
+
+    /// Label of the certificate, such as a keystore alias
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl<Id, L> CertificateStore<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Takes a [`StoreSnapshot`] of every certificate and label in the store
+    pub fn to_snapshot(&self) -> Result<StoreSnapshot, Error> {
+        let mut certificates = Vec::new();
+        for (id, cert) in self.iter() {
+            certificates.push(SnapshotEntry {
+                certificate: cert.to_der()?,
+                subject: cert.tbs_certificate.subject.to_string(),
+                label: self.label(id).map(String::from),
+            });
+        }
+        Ok(StoreSnapshot { certificates })
+    }
+
+    /// Creates a certificate store from a [`StoreSnapshot`]. Returns an error if any certificate
+    /// of the snapshot can not be decoded.
+    pub fn from_snapshot(snapshot: &StoreSnapshot) -> Result<Self, Error>
+    where
+        Error: for<'a> From<<Id as TryFrom<&'a Certificate>>::Error>,
+    {
+        let mut store = Self::new();
+        for entry in &snapshot.certificates {
+            let cert = Certificate::from_der(&entry.certificate)?;
+            let id = Id::try_from(&cert)?;
+            store.insert::<Error>(cert)?;
+            if let Some(label) = &entry.label {
+                store.set_label(id, label.as_str());
+            }
+        }
+        Ok(store)
+    }
+}
+
+/// DER encoding which serializes as base64 in human-readable formats and as bytes otherwise
+pub(crate) struct Der<'a>(pub(crate) &'a [u8]);
+
+impl Serialize for Der<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_der(self.0, serializer)
+    }
+}
+
+/// Serializes DER as base64 in human-readable formats and as bytes otherwise
+pub(crate) fn serialize_der<S: Serializer>(der: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&Base64::encode_string(der))
+    } else {
+        serializer.serialize_bytes(der)
+    }
+}
+
+/// Deserializes DER written by [`serialize_der`]
+pub(crate) fn deserialize_der<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<u8>, D::Error> {
+    struct DerVisitor;
+
+    impl<'de> de::Visitor<'de> for DerVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("base64 or bytes")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            Base64::decode_vec(v).map_err(E::custom)
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            Ok(v.to_vec())
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+            Ok(v)
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut bytes = Vec::new();
+            while let Some(b) = seq.next_element()? {
+                bytes.push(b);
+            }
+            Ok(bytes)
+        }
+    }
+
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(DerVisitor)
+    } else {
+        deserializer.deserialize_byte_buf(DerVisitor)
+    }
+}
+
+/// Serializes an object identifier in dotted notation
+#[cfg(feature = "pkcs7")]
+pub(crate) fn serialize_oid<S: Serializer>(
+    oid: &ObjectIdentifier,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(oid)
+}
+
+/// Serializes a duration since the UNIX epoch as whole seconds
+#[cfg(feature = "pkcs7")]
+pub(crate) fn serialize_unix_time<S: Serializer>(
+    time: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(time.as_secs())
+}
+
+/// Serializes an optional duration since the UNIX epoch as whole seconds
+#[cfg(feature = "pkcs7")]
+pub(crate) fn serialize_opt_unix_time<S: Serializer>(
+    time: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    time.map(|t| t.as_secs()).serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StoreSnapshot;
+    use crate::{error::Error, name::NameBytes, MemCertificateStore};
+    use core::time::Duration;
+    use der::DecodePem;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf.pem");
+
+    /// 2025-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+    #[test]
+    fn snapshot_round_trip() {
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let id = NameBytes::try_from(&root).unwrap();
+        let mut store = MemCertificateStore::new();
+        store.insert::<Error>(root).unwrap();
+        store.set_label(id.clone(), "rscs test root");
+
+        let json = serde_json::to_string(&store.to_snapshot().unwrap()).unwrap();
+        let snapshot: StoreSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(snapshot.certificates.len(), 1);
+        assert!(snapshot.certificates[0].subject.contains("CN="));
+
+        let restored = MemCertificateStore::from_snapshot(&snapshot).unwrap();
+        assert_eq!(restored.label(&id), Some("rscs test root"));
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        assert!(restored.verify_at(&leaf, &[&intermediate], NOW).is_ok());
+
+        assert!(serde_json::from_str::<StoreSnapshot>(
+            r#"{"certificates":[{"certificate":"not base64!"}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn serialize_path() {
+        let mut store = MemCertificateStore::new();
+        store
+            .insert::<Error>(Certificate::from_pem(ROOT).unwrap())
+            .unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let path = store.verify_at(&leaf, &[&intermediate], NOW).unwrap();
+
+        let value = serde_json::to_value(&path).unwrap();
+        assert_eq!(value["certificates"].as_array().unwrap().len(), 3);
+        assert!(value["anchor"].is_string());
+        assert!(value["policies"].is_array());
+        assert!(value["validity"]["not_after"].as_u64().unwrap() > NOW.as_secs());
+    }
+}
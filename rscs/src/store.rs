@@ -4,22 +4,80 @@ use crate::{
     error::Error,
     loader::{Loader, MemLoader},
     name::NameBytes,
+    path::VerifiedPath,
+    view::CertificateView,
 };
-use alloc::boxed::Box;
-use core::marker::PhantomData;
-use x509_verify::x509_cert::Certificate;
+use alloc::{borrow::Cow, boxed::Box, string::String, vec, vec::Vec};
+use core::{
+    borrow::Borrow,
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use der::{referenced::OwnedToRef, Encode};
+use x509_path::{is_self_issued, AuthorityKeyIdentifierRef};
+use x509_verify::{
+    x509_cert::{name::Name, Certificate},
+    VerifyingKey,
+};
+
+/// Longest chain of certificates a path search extends, not counting the trust anchor
+const MAX_PATH_LEN: usize = 16;
+
+/// Candidate issuers a single path search may try
+const MAX_CANDIDATES: usize = 256;
+
+/// Signatures a single path search may verify
+const MAX_SIGNATURES: usize = 100;
 
 /// Certificate Store implementation. Can store and retrieve certificates by a defined, unique ID.
-#[derive(Clone, Debug)]
+///
+/// With [`NameBytes`] identifiers, the store holds at most one certificate per subject name, so
+/// the old and new roots of a CA key rollover can not both be trust anchors: inserting one
+/// replaces the other. Keep a single root in the store and pass the self-issued rollover
+/// certificate linking the other key to it, such as the new key certified by the old one, in the
+/// untrusted chain. Paths then cross between the two keys of the CA.
+#[derive(Debug)]
 pub struct CertificateStore<Id, L>
 where
     L: Loader<Id>,
     Id: Eq + for<'a> TryFrom<&'a Certificate>,
 {
     inner: L,
+    instance: usize,
+    generation: u64,
+    labels: Vec<(Id, String)>,
     phantom: PhantomData<Id>,
 }
 
+impl<Id, L> Clone for CertificateStore<Id, L>
+where
+    L: Loader<Id> + Clone,
+    Id: Clone + Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Copies the store. The copy is a new instance, which diverges from the original once either
+    /// is modified.
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            instance: next_instance(),
+            generation: self.generation,
+            labels: self.labels.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Id, L> Default for CertificateStore<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<Id, L> CertificateStore<Id, L>
 where
     L: Loader<Id>,
@@ -29,24 +87,79 @@ where
     pub fn new() -> Self {
         Self {
             inner: L::default(),
-            phantom: PhantomData::default(),
+            instance: next_instance(),
+            generation: 0,
+            labels: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a [`CertificateStore`] backed by an existing `loader`
+    pub fn with_loader(loader: L) -> Self {
+        Self {
+            inner: loader,
+            instance: next_instance(),
+            generation: 0,
+            labels: Vec::new(),
+            phantom: PhantomData,
         }
     }
 
-    /// Inserts a certificate into the certificate store. Returns `E` if the conversion from
-    /// `Certificate` to `Id` fails.
+    /// Inserts a certificate into the certificate store, replacing and returning any certificate
+    /// with the same `Id`. Returns `E` if the conversion from `Certificate` to `Id` fails or the
+    /// loader can not store the certificate. See [`Loader::try_insert`].
     pub fn insert<E>(&mut self, cert: Certificate) -> Result<Option<Certificate>, E>
     where
-        E: for<'a> From<<Id as TryFrom<&'a Certificate>>::Error>,
+        E: for<'a> From<<Id as TryFrom<&'a Certificate>>::Error> + From<Error>,
     {
         let id = Id::try_from(&cert)?;
-        Ok(self.inner.insert(id, cert))
+        let replaced = self.inner.try_insert(id, cert)?;
+        self.generation = self.generation.wrapping_add(1);
+        Ok(replaced)
     }
 
     /// Removes a certificate from the certificate store. The certificate is returned if any was
     /// found.
     pub fn remove(&mut self, id: &Id) -> Option<Certificate> {
-        self.inner.remove(id)
+        let removed = self.inner.remove(id);
+        if removed.is_some() {
+            self.generation = self.generation.wrapping_add(1);
+        }
+        self.labels.retain(|(i, _)| i != id);
+        removed
+    }
+
+    /// Attaches a human-readable label, such as a keystore alias or friendly name, to the
+    /// certificate identified by `id`. Any previous label is replaced. Labels are kept until the
+    /// certificate is removed and do not affect verification.
+    pub fn set_label(&mut self, id: Id, label: impl Into<String>) {
+        let label = label.into();
+        match self.labels.iter_mut().find(|(i, _)| *i == id) {
+            Some((_, l)) => *l = label,
+            None => self.labels.push((id, label)),
+        }
+    }
+
+    /// Returns the label of the certificate identified by `id`, if any
+    pub fn label(&self, id: &Id) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(i, _)| i == id)
+            .map(|(_, l)| l.as_str())
+    }
+
+    /// Returns the generation of the certificate store. The generation changes every time the
+    /// contents of the store are modified, which allows callers to invalidate anything derived
+    /// from a previous state of the store. Generations of different stores are unrelated; see
+    /// [`CertificateStore::instance`].
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns an identifier unique to this store instance within the process. Clones are new
+    /// instances, so the instance and the generation together identify a state of one store.
+    pub fn instance(&self) -> usize {
+        self.instance
     }
 
     /// Retrieves a certificate from the certificate store. Returns `None` if nothing was found.
@@ -54,30 +167,956 @@ where
         self.inner.get(id)
     }
 
+    /// Retrieves a borrowed view of a certificate's DER encoding, when the loader keeps
+    /// certificates encoded. See [`Loader::view`].
+    pub fn view(&self, id: &Id) -> Option<CertificateView<'_>> {
+        self.inner.view(id)
+    }
+
+    /// Returns whether `cert` is the certificate stored under `id`. Certificates the loader keeps
+    /// encoded are compared by their encoding, without decoding them.
+    pub(crate) fn holds(&self, id: &Id, cert: &Certificate) -> bool {
+        match self.inner.view(id) {
+            Some(view) => cert.to_der().map_or(false, |der| view.der() == der),
+            None => self.inner.get(id) == Some(cert),
+        }
+    }
+
+    /// Returns the certificate stored under `id` if it may have issued `cert`, see [`is_issuer`].
+    /// Certificates the loader keeps encoded are only decoded once their subject and key
+    /// identifier match.
+    pub(crate) fn trusted_issuer(&self, id: &Id, cert: &Certificate) -> Option<&Certificate> {
+        if let Some(view) = self.inner.view(id) {
+            if !may_be_issuer(&view, cert) {
+                return None;
+            }
+        }
+        self.inner.get(id).filter(|issuer| is_issuer(issuer, cert))
+    }
+
     /// Returns an iterator over the internal storage in the form of a Tuple `(&Id, &Certificate)`
     pub fn iter(&self) -> Box<dyn Iterator<Item = (&'_ Id, &'_ Certificate)> + '_> {
         self.inner.iter()
     }
 
-    /// Verifies `cert` up its own issuance chain. Verification ends once a certificate in `cert`'s
-    /// chain is found in the certificate store and all signatures are verified. Verification fails
-    /// when any of the following hold true:
+    /// Verifies `cert` up its own issuance chain at the current system time. See
+    /// [`CertificateStore::verify_at`] for details.
+    ///
+    /// Reading the system time requires the `std` feature, so this method is intentionally not
+    /// available in `no_std` builds, unlike in earlier versions. Those call
+    /// [`CertificateStore::verify_at`] with a time from the platform's own clock instead.
+    #[cfg(feature = "std")]
+    pub fn verify(
+        &self,
+        cert: &Certificate,
+        chain: &[&Certificate],
+    ) -> Result<VerifiedPath<Id>, Error>
+    where
+        Id: Clone + for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO);
+        self.verify_at(cert, chain, now)
+    }
+
+    /// Verifies `cert` up its own issuance chain at `time`, given as the duration since the UNIX
+    /// epoch. Verification ends once a certificate in `cert`'s chain is found in the certificate
+    /// store and all signatures are verified. Verification fails when any of the following hold
+    /// true:
     ///
     /// - None of the certificates in the chain are trusted
     /// - The trust chain is broken and can not be inferred
     /// - Any of the certificates in the trust chain are expired
     /// - Any of the certificates in the trust chain are not yet valid
     /// - Signature verification fails
+    /// - An issuer is not a CA allowed to sign certificates
+    /// - A certificate carries an unrecognized critical extension
     ///
     /// This method makes no guarantees about the certificates in `chain`. The sole focus is to
     /// verify `cert` all the way up its own trust chain. The `chain` is only used when an issuer
-    /// in `cert`'s trust chain is not already present in the certificate store.
+    /// in `cert`'s trust chain is not already present in the certificate store. When several
+    /// certificates in `chain` may have issued the same certificate, such as cross-certificates,
+    /// each is tried in order until a path reaches the store. Issuers in the store are preferred.
+    /// Self-issued key rollover certificates let a path cross from a CA's new key to its old one,
+    /// or back, while self-signed certificates in `chain` are never used as intermediates.
+    ///
+    /// Path length and name constraints are enforced as in RFC 5280, which exempts self-issued
+    /// intermediates, and a violation fails with [`Error::Constraint`].
+    ///
+    /// Paths are at most 16 certificates long, not counting the trust anchor. Since a chain of
+    /// mutual cross-certificates can hold factorially many paths, the search gives up with
+    /// [`Error::Verification`] once it tried 256 candidate issuers or verified 100 signatures.
+    ///
+    /// On success, the [`VerifiedPath`] which was used to reach the trust anchor is returned.
     ///
     /// This function does not provide any revocation checking.
-    pub fn verify(&self, cert: &Certificate, chain: &[&Certificate]) -> Result<(), Error> {
-        unimplemented!()
+    pub fn verify_at(
+        &self,
+        cert: &Certificate,
+        chain: &[&Certificate],
+        time: Duration,
+    ) -> Result<VerifiedPath<Id>, Error>
+    where
+        Id: Clone + for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        self.verify_with(cert, chain, time, &mut verify_signature)
+    }
+
+    /// Same as [`CertificateStore::verify_at`], but every signature between two certificates of
+    /// the path is checked through `verify_sig`, which is given the issuer and the signed
+    /// certificate. Self-signatures, which only leave self-signed certificates of `chain` out of
+    /// the path, are checked without it.
+    pub(crate) fn verify_with<'c>(
+        &'c self,
+        cert: &'c Certificate,
+        chain: &'c [&'c Certificate],
+        time: Duration,
+        verify_sig: &mut dyn FnMut(&Certificate, &Certificate) -> Result<(), Error>,
+    ) -> Result<VerifiedPath<Id>, Error>
+    where
+        Id: Clone + for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        let mut source = Presented { store: self, chain };
+        search(
+            &mut source,
+            &mut vec![Cow::Borrowed(cert)],
+            time,
+            verify_sig,
+        )
+    }
+
+    /// Returns every valid certification path from `cert` to a certificate in the store at
+    /// `time`, shortest first. Paths of the same length are in the order of `chain`.
+    ///
+    /// Unlike [`CertificateStore::verify_at`], which stops at the first path found, this explores
+    /// every route through cross-certificates, such as through a bridge CA, so that the caller
+    /// can see and choose between them. Fails with [`Error::Verification`] when there is no
+    /// valid path, or when finding and verifying the candidates takes more than the work limits
+    /// of [`CertificateStore::candidate_paths`] and [`CertificateStore::verify_at`].
+    pub fn verify_all_at(
+        &self,
+        cert: &Certificate,
+        chain: &[&Certificate],
+        time: Duration,
+    ) -> Result<Vec<VerifiedPath<Id>>, Error>
+    where
+        Id: Clone + for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        let mut budget = Budget::new();
+        let mut candidates = Vec::new();
+        self.extend_candidates(vec![cert], chain, &mut candidates, &mut budget)?;
+        let mut paths = Vec::new();
+        for path in &candidates {
+            budget.signatures(path.len() - 1)?;
+            if let Ok(verified) = self.verify_path_at(path, time) {
+                paths.push(verified);
+            }
+        }
+        if paths.is_empty() {
+            return Err(Error::Verification);
+        }
+        paths.sort_by_key(|p| p.certificates().len());
+        Ok(paths)
+    }
+}
+
+impl<Id, L> CertificateStore<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Returns every candidate certification path from `cert` to a certificate in the store.
+    /// Each path is ordered from `cert` to the trust anchor. Candidates are found by name
+    /// chaining and authority key identifiers alone: neither signatures nor validity periods are
+    /// checked. A path never holds two certificates with the same subject and public key, which
+    /// ends loops through mutual cross-certificates. Certificates in `chain` whose authority key
+    /// identifier, if any, matches their own key are taken as self-signed and never used as
+    /// intermediates, as they can only end a path as a trust anchor. Self-issued key rollover
+    /// certificates, which are signed by another key of the same CA, are.
+    ///
+    /// Paths are cut off like in [`CertificateStore::verify_at`], and the search fails with
+    /// [`Error::Verification`] once it tried 256 candidate issuers.
+    pub fn candidate_paths<'c>(
+        &'c self,
+        cert: &'c Certificate,
+        chain: &[&'c Certificate],
+    ) -> Result<Vec<Vec<&'c Certificate>>, Error>
+    where
+        Id: for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        let mut paths = Vec::new();
+        self.extend_candidates(vec![cert], chain, &mut paths, &mut Budget::new())?;
+        Ok(paths)
+    }
+
+    /// Verifies a single candidate `path`, as returned by [`CertificateStore::candidate_paths`],
+    /// at `time`. The path must be ordered from the target certificate to the trust anchor, and
+    /// the trust anchor must be present in the certificate store.
+    pub fn verify_path_at(
+        &self,
+        path: &[&Certificate],
+        time: Duration,
+    ) -> Result<VerifiedPath<Id>, Error>
+    where
+        Id: Clone + for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        let anchor = path.last().ok_or(Error::Verification)?;
+        let id = <Id as TryFrom<&Name>>::try_from(&anchor.tbs_certificate.subject)?;
+        if !self.holds(&id, anchor) {
+            return Err(Error::Verification);
+        }
+        for cert in path {
+            check_validity(cert, time)?;
+        }
+        for pair in path.windows(2) {
+            if !is_issuer(pair[1], pair[0]) {
+                return Err(Error::Verification);
+            }
+            verify_signature(pair[1], pair[0])?;
+        }
+        VerifiedPath::new(path.iter().map(|c| (*c).clone()).collect(), id)
+    }
+
+    /// Depth-first search for the issuers of the last certificate in `path`
+    fn extend_candidates<'c>(
+        &'c self,
+        path: Vec<&'c Certificate>,
+        chain: &[&'c Certificate],
+        paths: &mut Vec<Vec<&'c Certificate>>,
+        budget: &mut Budget,
+    ) -> Result<(), Error>
+    where
+        Id: for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        let current = path[path.len() - 1];
+        let id = <Id as TryFrom<&Name>>::try_from(&current.tbs_certificate.subject)?;
+        if self.holds(&id, current) {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+            return Ok(());
+        }
+        let id = <Id as TryFrom<&Name>>::try_from(&current.tbs_certificate.issuer)?;
+        if let Some(anchor) = self
+            .trusted_issuer(&id, current)
+            .filter(|a| !in_path(&path, a))
+        {
+            let mut complete = path.clone();
+            complete.push(anchor);
+            if !paths.contains(&complete) {
+                paths.push(complete);
+            }
+        }
+        if path.len() >= MAX_PATH_LEN {
+            return Ok(());
+        }
+        for issuer in chain
+            .iter()
+            .filter(|c| is_issuer(c, current))
+            .filter(|c| !in_path(&path, c) && !may_be_self_signed(c))
+        {
+            budget.candidate()?;
+            let mut next = path.clone();
+            next.push(issuer);
+            self.extend_candidates(next, chain, paths, budget)?;
+        }
+        Ok(())
+    }
+}
+
+/// Source of the certificates which [`search`] chains through
+pub(crate) trait IssuerSource<'c, Id> {
+    /// Returns the trusted certificate stored under `id`
+    fn anchor(&self, id: &Id) -> Option<&Certificate>;
+
+    /// Returns whether `cert` is the trusted certificate stored under `id`
+    fn is_anchor(&self, id: &Id, cert: &Certificate) -> bool {
+        self.anchor(id) == Some(cert)
+    }
+
+    /// Returns the trusted certificate stored under `id` if it may have issued `cert`
+    fn trusted_issuer(&self, id: &Id, cert: &Certificate) -> Option<&Certificate> {
+        self.anchor(id).filter(|anchor| is_issuer(anchor, cert))
+    }
+
+    /// Returns the untrusted certificates presented along with the target certificate
+    fn chain(&self) -> &'c [&'c Certificate];
+
+    /// Returns the `index`-th batch of further candidate issuers of `cert`, or `None` once there
+    /// are no more. Batches are only asked for, in order, once neither the trust anchor nor the
+    /// presented chain nor the previous batches led to a verified path.
+    fn fetch_issuers(
+        &mut self,
+        _cert: &Certificate,
+        _index: usize,
+    ) -> Result<Option<Vec<Cow<'c, Certificate>>>, Error> {
+        Ok(None)
+    }
+}
+
+/// [`IssuerSource`] of a certificate store and a presented chain
+struct Presented<'c, Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    store: &'c CertificateStore<Id, L>,
+    chain: &'c [&'c Certificate],
+}
+
+impl<'c, Id, L> IssuerSource<'c, Id> for Presented<'c, Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    fn anchor(&self, id: &Id) -> Option<&Certificate> {
+        self.store.inner.get(id)
+    }
+
+    fn is_anchor(&self, id: &Id, cert: &Certificate) -> bool {
+        self.store.holds(id, cert)
+    }
+
+    fn trusted_issuer(&self, id: &Id, cert: &Certificate) -> Option<&Certificate> {
+        self.store.trusted_issuer(id, cert)
+    }
+
+    fn chain(&self) -> &'c [&'c Certificate] {
+        self.chain
     }
 }
 
+/// Work left to a single path search. Each step fails with [`Error::Verification`] once the
+/// budget is spent, which ends the whole search.
+struct Budget {
+    candidates: usize,
+    signatures: usize,
+}
+
+impl Budget {
+    fn new() -> Self {
+        Self {
+            candidates: MAX_CANDIDATES,
+            signatures: MAX_SIGNATURES,
+        }
+    }
+
+    /// Spends one candidate issuer
+    fn candidate(&mut self) -> Result<(), Error> {
+        self.candidates = self.candidates.checked_sub(1).ok_or(Error::Verification)?;
+        Ok(())
+    }
+
+    /// Spends `count` signature verifications
+    fn signatures(&mut self, count: usize) -> Result<(), Error> {
+        self.signatures = self
+            .signatures
+            .checked_sub(count)
+            .ok_or(Error::Verification)?;
+        Ok(())
+    }
+
+    fn is_spent(&self) -> bool {
+        self.candidates == 0 || self.signatures == 0
+    }
+}
+
+/// Depth-first search for a verified path from the last certificate in `path`, see
+/// [`CertificateStore::verify_at`]. Issuers are tried in order, backtracking whenever a branch can
+/// not reach a trust anchor of `source`. The first error other than [`Error::Verification`] is
+/// returned when no path is found, and [`Error::Verification`] when the search ran out of budget.
+pub(crate) fn search<'c, Id, S>(
+    source: &mut S,
+    path: &mut Vec<Cow<'c, Certificate>>,
+    time: Duration,
+    verify_sig: &mut dyn FnMut(&Certificate, &Certificate) -> Result<(), Error>,
+) -> Result<VerifiedPath<Id>, Error>
+where
+    S: IssuerSource<'c, Id>,
+    Id: Clone + for<'a> TryFrom<&'a Name>,
+    Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+{
+    let mut budget = Budget::new();
+    match extend_path(source, path, time, verify_sig, &mut budget) {
+        Err(_) if budget.is_spent() => Err(Error::Verification),
+        result => result,
+    }
+}
+
+/// Recursive step of [`search`]
+fn extend_path<'c, Id, S>(
+    source: &mut S,
+    path: &mut Vec<Cow<'c, Certificate>>,
+    time: Duration,
+    verify_sig: &mut dyn FnMut(&Certificate, &Certificate) -> Result<(), Error>,
+    budget: &mut Budget,
+) -> Result<VerifiedPath<Id>, Error>
+where
+    S: IssuerSource<'c, Id>,
+    Id: Clone + for<'a> TryFrom<&'a Name>,
+    Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+{
+    let current = &path[path.len() - 1];
+    check_validity(current, time)?;
+
+    // The certificate itself is trusted
+    let id = <Id as TryFrom<&Name>>::try_from(&current.tbs_certificate.subject)?;
+    if source.is_anchor(&id, current) {
+        return VerifiedPath::new(path.iter().map(|c| (**c).clone()).collect(), id);
+    }
+
+    // The issuer is trusted
+    let mut error = None;
+    let id = <Id as TryFrom<&Name>>::try_from(&current.tbs_certificate.issuer)?;
+    if let Some(anchor) = source
+        .trusted_issuer(&id, current)
+        .filter(|a| !in_path(path, a))
+    {
+        budget.signatures(1)?;
+        match check_validity(anchor, time).and_then(|_| verify_sig(anchor, current)) {
+            Ok(()) => {
+                let mut certificates: Vec<Certificate> =
+                    path.iter().map(|c| (**c).clone()).collect();
+                certificates.push(anchor.clone());
+                return VerifiedPath::new(certificates, id);
+            }
+            Err(e) => error = Some(e),
+        }
+    }
+
+    // Otherwise, try every issuer in the untrusted chain, then the fetched ones
+    if path.len() >= MAX_PATH_LEN {
+        return Err(error.unwrap_or(Error::Verification));
+    }
+    let mut candidates: Vec<Cow<'c, Certificate>> =
+        source.chain().iter().map(|c| Cow::Borrowed(*c)).collect();
+    let mut index = 0;
+    loop {
+        for issuer in candidates {
+            let current = &path[path.len() - 1];
+            if !is_issuer(&issuer, current) || in_path(path, &issuer) {
+                continue;
+            }
+            budget.candidate()?;
+            if may_be_self_signed(&issuer) {
+                budget.signatures(1)?;
+                if verify_signature(&issuer, &issuer).is_ok() {
+                    continue;
+                }
+            }
+            budget.signatures(1)?;
+            if verify_sig(&issuer, current).is_err() {
+                continue;
+            }
+            path.push(issuer);
+            match extend_path(source, path, time, verify_sig, budget) {
+                Ok(verified) => return Ok(verified),
+                Err(_) if budget.is_spent() => return Err(Error::Verification),
+                Err(Error::Verification) => {}
+                Err(e) => error = error.or(Some(e)),
+            }
+            path.pop();
+        }
+        candidates = match source.fetch_issuers(&path[path.len() - 1], index)? {
+            Some(candidates) => candidates,
+            None => break,
+        };
+        index += 1;
+    }
+    Err(error.unwrap_or(Error::Verification))
+}
+
+/// Returns a new store instance identifier
+fn next_instance() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Checks that `cert` is valid at `time`
+pub(crate) fn check_validity(cert: &Certificate, time: Duration) -> Result<(), Error> {
+    let validity = &cert.tbs_certificate.validity;
+    if time < validity.not_before.to_unix_duration() {
+        Err(Error::NotYetValid)
+    } else if time > validity.not_after.to_unix_duration() {
+        Err(Error::Expired)
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns whether `issuer` may have issued `cert`: its subject must be the issuer of `cert`, and
+/// it must match the authority key identifier of `cert`, if any.
+pub(crate) fn is_issuer(issuer: &Certificate, cert: &Certificate) -> bool {
+    issuer.tbs_certificate.subject == cert.tbs_certificate.issuer
+        && match AuthorityKeyIdentifierRef::from_certificate(cert) {
+            Ok(Some(aki)) => aki.matches(issuer).unwrap_or(false),
+            Ok(None) => true,
+            Err(_) => false,
+        }
+}
+
+/// Returns whether the certificate viewed by `issuer` may have issued `cert`, comparing the
+/// subject and the key identifier like [`is_issuer`] without decoding the certificate. A match
+/// still needs to be confirmed with [`is_issuer`].
+fn may_be_issuer(issuer: &CertificateView<'_>, cert: &Certificate) -> bool {
+    if cert
+        .tbs_certificate
+        .issuer
+        .to_der()
+        .map_or(true, |name| issuer.subject_der() != name)
+    {
+        return false;
+    }
+    let key_id = match AuthorityKeyIdentifierRef::from_certificate(cert) {
+        Ok(aki) => match aki.and_then(|aki| aki.key_id()) {
+            Some(key_id) => key_id,
+            None => return true,
+        },
+        Err(_) => return false,
+    };
+    if let Ok(Some(subject_key_id)) = issuer.subject_key_identifier() {
+        if subject_key_id.as_bytes() == key_id.as_bytes() {
+            return true;
+        }
+    }
+    issuer
+        .subject_public_key_info()
+        .map_or(false, |spki| matches!(key_id.method(spki), Ok(Some(_))))
+}
+
+/// Returns whether `cert` is self-issued and its authority key identifier, if any, matches its own
+/// key. This rules out key rollover certificates without checking a signature, so that only
+/// certificates which may be self-signed need to go through signature verification.
+fn may_be_self_signed(cert: &Certificate) -> bool {
+    is_self_issued(cert) && is_issuer(cert, cert)
+}
+
+/// Returns whether `path` already holds a certificate with the subject and the public key of
+/// `cert`. Mutual cross-certificates differ from each other, so comparing certificates alone does
+/// not prevent loops such as `A -> B -> A` through a bridge CA.
+fn in_path<C: Borrow<Certificate>>(path: &[C], cert: &Certificate) -> bool {
+    path.iter().map(Borrow::borrow).any(|c: &Certificate| {
+        c.tbs_certificate.subject == cert.tbs_certificate.subject
+            && c.tbs_certificate.subject_public_key_info
+                == cert.tbs_certificate.subject_public_key_info
+    })
+}
+
+/// Verifies the signature on `cert` using the public key of `issuer`
+pub(crate) fn verify_signature(issuer: &Certificate, cert: &Certificate) -> Result<(), Error> {
+    let key = VerifyingKey::try_from(
+        issuer
+            .tbs_certificate
+            .subject_public_key_info
+            .owned_to_ref(),
+    )
+    .map_err(|_| Error::Verification)?;
+    key.verify(cert).map_err(|_| Error::Verification)
+}
+
 /// Memory-only certificate store
 pub type MemCertificateStore = CertificateStore<NameBytes, MemLoader>;
+
+#[cfg(test)]
+mod tests {
+    use super::CertificateStore;
+    use crate::{
+        error::Error,
+        loader::{DerLoader, Loader},
+        name::NameBytes,
+        view::CertificateView,
+        MemCertificateStore,
+    };
+    use alloc::{boxed::Box, vec::Vec};
+    use const_oid::db::rfc5280::{ID_KP_CLIENT_AUTH, ID_KP_SERVER_AUTH};
+    use core::{cell::Cell, time::Duration};
+    use der::{DecodePem, Encode};
+    use x509_path::{is_self_issued, is_self_signed};
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf.pem");
+    const LEAF_AKI: &[u8] = include_bytes!("../testdata/leaf-aki.pem");
+    const LEAF_7093: &[u8] = include_bytes!("../testdata/leaf-7093.pem");
+    const BRIDGE_CHAIN: &[u8] = include_bytes!("../testdata/bridge-chain.pem");
+    const PARTNER_ROOT: &[u8] = include_bytes!("../testdata/partner-root.pem");
+    const PARTNER_LEAF: &[u8] = include_bytes!("../testdata/partner-leaf.pem");
+    const ROLLOVER_CHAIN: &[u8] = include_bytes!("../testdata/rollover-chain.pem");
+    const LEAF_ROLLOVER: &[u8] = include_bytes!("../testdata/leaf-rollover.pem");
+    const MESH_CHAIN: &[u8] = include_bytes!("../testdata/mesh-chain.pem");
+    const MESH_LEAF: &[u8] = include_bytes!("../testdata/mesh-leaf.pem");
+    const SUB_LEAF: &[u8] = include_bytes!("../testdata/sub-leaf.pem");
+    const LEAF_ISSUED: &[u8] = include_bytes!("../testdata/leaf-issued.pem");
+    const LEAF_CRITICAL: &[u8] = include_bytes!("../testdata/leaf-critical.pem");
+    const V1_ROOT: &[u8] = include_bytes!("../testdata/v1-root.pem");
+    const V1_LEAF: &[u8] = include_bytes!("../testdata/v1-leaf.pem");
+    const CONSTRAINED_CHAIN: &[u8] = include_bytes!("../testdata/constrained-chain.pem");
+    const CONSTRAINED_LEAF: &[u8] = include_bytes!("../testdata/constrained-leaf.pem");
+    const CONSTRAINED_LEAF_BAD: &[u8] = include_bytes!("../testdata/constrained-leaf-bad.pem");
+
+    /// Same subject as `INTERMEDIATE`, with another key
+    const INTERMEDIATE_2: &[u8] = include_bytes!("../testdata/intermediate2.pem");
+
+    /// 2025-06-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_748_736_000);
+
+    /// [`DerLoader`] counting the certificates retrieved, and therefore decoded, with `get`
+    #[derive(Default)]
+    struct CountingLoader {
+        inner: DerLoader,
+        decoded: Cell<usize>,
+    }
+
+    impl Loader<NameBytes> for CountingLoader {
+        fn insert(&mut self, id: NameBytes, cert: Certificate) -> Option<Certificate> {
+            self.inner.insert(id, cert)
+        }
+
+        fn remove(&mut self, id: &NameBytes) -> Option<Certificate> {
+            self.inner.remove(id)
+        }
+
+        fn get(&self, id: &NameBytes) -> Option<&Certificate> {
+            let cert = self.inner.get(id);
+            self.decoded
+                .set(self.decoded.get() + usize::from(cert.is_some()));
+            cert
+        }
+
+        fn iter(&self) -> Box<dyn Iterator<Item = (&'_ NameBytes, &'_ Certificate)> + '_> {
+            self.inner.iter()
+        }
+
+        fn view(&self, id: &NameBytes) -> Option<CertificateView<'_>> {
+            self.inner.view(id)
+        }
+    }
+
+    fn store() -> MemCertificateStore {
+        let mut store = MemCertificateStore::new();
+        store
+            .insert::<Error>(Certificate::from_pem(ROOT).unwrap())
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn verify_returns_path() {
+        let store = store();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let path = store.verify_at(&leaf, &[&intermediate], NOW).unwrap();
+        assert_eq!(path.certificates().len(), 3);
+        assert_eq!(path.leaf(), &leaf);
+        assert_eq!(path.certificates()[1], intermediate);
+        assert_eq!(
+            path.anchor().tbs_certificate.subject,
+            intermediate.tbs_certificate.issuer
+        );
+        assert_eq!(
+            path.validity().not_before,
+            leaf.tbs_certificate.validity.not_before
+        );
+        assert_eq!(
+            path.validity().not_after,
+            leaf.tbs_certificate.validity.not_after
+        );
+        assert_eq!(path.policies().len(), 1);
+    }
+
+    #[test]
+    fn verify_purpose() {
+        let store = store();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let path = store.verify_at(&leaf, &[&intermediate], NOW).unwrap();
+        assert!(path.check_purpose(ID_KP_SERVER_AUTH).is_ok());
+        assert!(matches!(
+            path.check_purpose(ID_KP_CLIENT_AUTH),
+            Err(Error::InvalidPurpose)
+        ));
+    }
+
+    #[test]
+    fn candidate_paths() {
+        let store = store();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let paths = store.candidate_paths(&leaf, &[&intermediate]).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].len(), 3);
+        assert!(store.verify_path_at(&paths[0], NOW).is_ok());
+        assert!(store.verify_path_at(&paths[0][..2], NOW).is_err());
+        assert!(store.candidate_paths(&leaf, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn encoded_anchors() {
+        let encoded = |pem: &[u8]| {
+            let mut loader = CountingLoader::default();
+            let der = Certificate::from_pem(pem).unwrap().to_der().unwrap();
+            loader.inner.insert_der(der).unwrap();
+            CertificateStore::with_loader(loader)
+        };
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+
+        // Neither the subject key identifier nor the encoding of the stored certificate match, so
+        // it is not decoded
+        let store = encoded(INTERMEDIATE_2);
+        assert!(store.verify_at(&leaf, &[], NOW).is_err());
+        assert!(store.verify_at(&intermediate, &[], NOW).is_err());
+        assert!(store.candidate_paths(&leaf, &[]).unwrap().is_empty());
+        assert_eq!(store.inner.decoded.get(), 0);
+
+        // Only the trust anchor of the path is
+        let store = encoded(ROOT);
+        assert!(store.verify_at(&leaf, &[&intermediate], NOW).is_ok());
+        assert_eq!(store.inner.decoded.get(), 1);
+    }
+
+    #[test]
+    fn authority_key_identifier() {
+        let store = store();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let intermediate2 = Certificate::from_pem(INTERMEDIATE_2).unwrap();
+        // The keyIdentifier of the leaves, either SHA-1 or truncated SHA-256, and the issuer and
+        // serial number of the other leaf only match the first intermediate
+        for pem in [LEAF, LEAF_7093, LEAF_AKI] {
+            let leaf = Certificate::from_pem(pem).unwrap();
+            let paths = store
+                .candidate_paths(&leaf, &[&intermediate2, &intermediate])
+                .unwrap();
+            assert_eq!(paths.len(), 1);
+            assert_eq!(paths[0][1], &intermediate);
+            let root = paths[0][2];
+            assert!(store
+                .verify_path_at(&[&leaf, &intermediate2, root], NOW)
+                .is_err());
+            assert!(store
+                .verify_at(&leaf, &[&intermediate2, &intermediate], NOW)
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn bridge_paths() {
+        // Cross-certificates in both directions between the root and the bridge, and between the
+        // bridge and the partner root, ordered so that the first bridge certificate is a dead end
+        let chain = Certificate::load_pem_chain(BRIDGE_CHAIN).unwrap();
+        let chain: Vec<&Certificate> = chain.iter().collect();
+        let (root_by_bridge, bridge_by_partner, partner_by_bridge, bridge_by_root) =
+            (chain[0], chain[1], chain[2], chain[3]);
+        let leaf = Certificate::from_pem(PARTNER_LEAF).unwrap();
+        let mut store = store();
+
+        let path = store.verify_at(&leaf, &chain, NOW).unwrap();
+        let expected = [
+            &leaf,
+            partner_by_bridge,
+            bridge_by_root,
+            store.iter().next().unwrap().1,
+        ];
+        assert!(path.certificates().iter().eq(expected));
+        let candidates = store.candidate_paths(&leaf, &chain).unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert!(!candidates[0].contains(&bridge_by_partner));
+        assert!(!candidates[0].contains(&root_by_bridge));
+
+        // With the partner root trusted as well, the direct path comes first
+        let partner_root = Certificate::from_pem(PARTNER_ROOT).unwrap();
+        store.insert::<Error>(partner_root.clone()).unwrap();
+        let paths = store.verify_all_at(&leaf, &chain, NOW).unwrap();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(
+            paths[0].certificates(),
+            [leaf.clone(), partner_root.clone()]
+        );
+        assert_eq!(paths[1].certificates().len(), 4);
+        assert_eq!(
+            store.verify_at(&leaf, &chain, NOW).unwrap().anchor(),
+            &partner_root
+        );
+        // Expired paths are left out
+        let later = Duration::from_secs(2_100_000_000);
+        assert!(matches!(
+            store.verify_all_at(&leaf, &chain, later),
+            Err(Error::Verification)
+        ));
+    }
+
+    #[test]
+    fn cross_certified_mesh() {
+        // Eight keys of the same CA each certify all the others, which makes for over 13,000
+        // paths from the leaf, none of which reaches the store
+        let chain = Certificate::load_pem_chain(MESH_CHAIN).unwrap();
+        let chain: Vec<&Certificate> = chain.iter().collect();
+        assert_eq!(chain.len(), 56);
+        let leaf = Certificate::from_pem(MESH_LEAF).unwrap();
+        let store = store();
+
+        let mut signatures = 0;
+        let verified = store.verify_with(&leaf, &chain, NOW, &mut |issuer, cert| {
+            signatures += 1;
+            super::verify_signature(issuer, cert)
+        });
+        assert!(matches!(verified, Err(Error::Verification)));
+        assert!(signatures <= super::MAX_SIGNATURES);
+        assert!(matches!(
+            store.candidate_paths(&leaf, &chain),
+            Err(Error::Verification)
+        ));
+        assert!(matches!(
+            store.verify_all_at(&leaf, &chain, NOW),
+            Err(Error::Verification)
+        ));
+    }
+
+    #[test]
+    fn issuer_constraints() {
+        let mut store = store();
+        store
+            .insert::<Error>(Certificate::from_pem(PARTNER_ROOT).unwrap())
+            .unwrap();
+
+        // An end-entity certificate, below a root without a path length constraint, signed
+        // another certificate
+        let leaf = Certificate::from_pem(PARTNER_LEAF).unwrap();
+        let issued = Certificate::from_pem(LEAF_ISSUED).unwrap();
+        assert!(store.verify_at(&leaf, &[], NOW).is_ok());
+        assert!(matches!(
+            store.verify_at(&issued, &[&leaf], NOW),
+            Err(Error::Constraint)
+        ));
+        assert!(matches!(
+            store.verify_all_at(&issued, &[&leaf], NOW),
+            Err(Error::Verification)
+        ));
+
+        // An unrecognized critical extension
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let critical = Certificate::from_pem(LEAF_CRITICAL).unwrap();
+        assert!(matches!(
+            store.verify_at(&critical, &[&intermediate], NOW),
+            Err(Error::Verification)
+        ));
+
+        // A trust anchor is not required to be a CA, such as a v1 root without extensions. It is
+        // only accepted once trusted, as a self-signed certificate in the chain never is.
+        let v1_root = Certificate::from_pem(V1_ROOT).unwrap();
+        let v1_leaf = Certificate::from_pem(V1_LEAF).unwrap();
+        assert!(matches!(
+            store.verify_at(&v1_leaf, &[&v1_root], NOW),
+            Err(Error::Verification)
+        ));
+        store.insert::<Error>(v1_root).unwrap();
+        assert!(store.verify_at(&v1_leaf, &[], NOW).is_ok());
+    }
+
+    #[test]
+    fn key_rollover() {
+        // The intermediate, whose path length constraint is 0, certified its new key with its old
+        // one. A self-signed certificate for the new key comes first in the chain.
+        let chain = Certificate::load_pem_chain(ROLLOVER_CHAIN).unwrap();
+        let chain: Vec<&Certificate> = chain.iter().collect();
+        let (self_signed, rollover, intermediate) = (chain[0], chain[1], chain[2]);
+        assert!(is_self_signed(self_signed));
+        assert!(is_self_issued(rollover) && !is_self_signed(rollover));
+        assert!(!is_self_issued(intermediate));
+        let store = store();
+
+        // The self-issued rollover certificate does not count towards the path length, and the
+        // self-signed certificate is never used as an intermediate
+        let leaf = Certificate::from_pem(LEAF_ROLLOVER).unwrap();
+        let path = store.verify_at(&leaf, &chain, NOW).unwrap();
+        let expected = [
+            &leaf,
+            rollover,
+            intermediate,
+            store.iter().next().unwrap().1,
+        ];
+        assert!(path.certificates().iter().eq(expected));
+        assert_eq!(store.verify_all_at(&leaf, &chain, NOW).unwrap().len(), 1);
+
+        // Self-signatures are not edges of the path and are checked without the signature hook,
+        // so that a hook rejecting the self-signed certificate does not make it an intermediate
+        let mut checked = Vec::new();
+        store
+            .verify_with(&leaf, &chain, NOW, &mut |issuer, cert| {
+                checked.push((issuer.clone(), cert.clone()));
+                match issuer == self_signed {
+                    true => Err(Error::WeakAlgorithm),
+                    false => super::verify_signature(issuer, cert),
+                }
+            })
+            .unwrap();
+        assert!(!checked.iter().any(|(i, c)| i == c));
+        assert!(!checked.iter().any(|(i, _)| i == self_signed));
+        assert!(store
+            .candidate_paths(&leaf, &chain)
+            .unwrap()
+            .iter()
+            .all(|p| !p.contains(&self_signed)));
+
+        // A CA below the intermediate does count
+        let leaf = Certificate::from_pem(SUB_LEAF).unwrap();
+        assert!(matches!(
+            store.verify_at(&leaf, &chain, NOW),
+            Err(Error::Constraint)
+        ));
+    }
+
+    #[test]
+    fn name_constraints() {
+        // The constrained CA only permits names under example.com and its directory subtree,
+        // which its own self-issued rollover certificate is outside of
+        let chain = Certificate::load_pem_chain(CONSTRAINED_CHAIN).unwrap();
+        let chain: Vec<&Certificate> = chain.iter().collect();
+        let store = store();
+
+        let leaf = Certificate::from_pem(CONSTRAINED_LEAF).unwrap();
+        let path = store.verify_at(&leaf, &chain, NOW).unwrap();
+        assert_eq!(path.certificates().len(), 4);
+
+        let leaf = Certificate::from_pem(CONSTRAINED_LEAF_BAD).unwrap();
+        assert!(matches!(
+            store.verify_at(&leaf, &chain, NOW),
+            Err(Error::Constraint)
+        ));
+        assert!(matches!(
+            store.verify_all_at(&leaf, &chain, NOW),
+            Err(Error::Verification)
+        ));
+    }
+
+    #[test]
+    fn verify_broken_chain() {
+        let store = store();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        assert!(matches!(
+            store.verify_at(&leaf, &[], NOW),
+            Err(Error::Verification)
+        ));
+    }
+
+    #[test]
+    fn verify_expired() {
+        let store = store();
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+        let later = Duration::from_secs(2_100_000_000);
+        assert!(matches!(
+            store.verify_at(&leaf, &[&intermediate], later),
+            Err(Error::Expired)
+        ));
+    }
+}
@@ -0,0 +1,456 @@
+//! RFC 3161 timestamp token validation ([RFC 3161])
+//!
+//! A `TimeStampToken` is CMS `SignedData` over a `TSTInfo`, signed by a timestamp authority.
+//! Its `genTime` proves that the timestamped data existed at that time, and is typically used as
+//! the historical validation time of a signature after the signer's certificate has expired.
+//!
+//! [RFC 3161]: https://datatracker.ietf.org/doc/html/rfc3161
+
+use crate::{
+    error::Error,
+    loader::Loader,
+    path::VerifiedPath,
+    signed_data::{self, digest},
+    store::CertificateStore,
+};
+use alloc::vec::Vec;
+use cms::signed_data::SignerInfo;
+use const_oid::{
+    db::{
+        rfc5280::ID_KP_TIME_STAMPING,
+        rfc5911::{ID_AA_SIGNING_CERTIFICATE, ID_AA_SIGNING_CERTIFICATE_V_2},
+        rfc5912::{ID_SHA_1, ID_SHA_256},
+    },
+    ObjectIdentifier,
+};
+use core::time::Duration;
+use der::{
+    asn1::{Any, AnyRef, IntRef, OctetString, OctetStringRef},
+    DateTime, Decode, Encode, Sequence, Tag, Tagged,
+};
+use x509_verify::{
+    spki::AlgorithmIdentifierOwned,
+    x509_cert::{
+        ext::{
+            pkix::{
+                name::{GeneralName, GeneralNames},
+                ExtendedKeyUsage,
+            },
+            Extensions,
+        },
+        name::Name,
+        serial_number::SerialNumber,
+        Certificate,
+    },
+};
+
+/// `id-ct-TSTInfo` as defined in [RFC 3161]
+///
+/// [RFC 3161]: https://datatracker.ietf.org/doc/html/rfc3161
+pub const ID_CT_TST_INFO: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+
+/// `id-aa-timeStampToken` as defined in [RFC 3161]
+///
+/// [RFC 3161]: https://datatracker.ietf.org/doc/html/rfc3161
+pub const ID_AA_TIME_STAMP_TOKEN: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.2.14");
+
+/// Verified RFC 3161 timestamp
+///
+/// With the `serde` feature, times serialize as UNIX timestamps, the accuracy in milliseconds and
+/// the serial number as hex.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Timestamp<Id> {
+    /// Time the timestamp was generated at, as the duration since the UNIX epoch
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::snapshot::serialize_unix_time")
+    )]
+    pub gen_time: Duration,
+
+    /// Accuracy of `gen_time` as asserted by the timestamp authority. The actual time lies
+    /// within `gen_time` plus or minus the accuracy.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_accuracy"))]
+    pub accuracy: Option<Duration>,
+
+    /// Policy the timestamp was issued under
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::snapshot::serialize_oid")
+    )]
+    pub policy: ObjectIdentifier,
+
+    /// Serial number of the timestamp, as a big-endian integer
+    #[cfg_attr(feature = "serde", serde(serialize_with = "hex::serialize"))]
+    pub serial_number: Vec<u8>,
+
+    /// Path from the timestamp authority's certificate to the trust anchor
+    pub path: VerifiedPath<Id>,
+}
+
+impl<Id, L> CertificateStore<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Verifies the PEM or DER-encoded `TimeStampToken` over `data` at `time`, given as the
+    /// duration since the UNIX epoch.
+    ///
+    /// The token must carry exactly one signer whose certificate is embedded in the token, chains
+    /// to the certificate store and has a critical extended key usage extension with
+    /// `id-kp-timeStamping` as its sole purpose. The signer's `signingCertificate` or
+    /// `signingCertificateV2` attribute must identify that certificate. The message imprint must be the `sha1`, `sha256`,
+    /// `sha384` or `sha512` hash of `data`. Fractional seconds of `genTime` are truncated.
+    ///
+    /// `Error::Verification` is returned when the token is malformed, its signature is invalid,
+    /// the signing certificate attribute is missing or does not match, or the message imprint
+    /// does not match `data`. `Error::InvalidPurpose` is returned when the
+    /// signer is not a timestamp authority. Errors validating the signer's certificate path are
+    /// returned as is.
+    pub fn verify_timestamp(
+        &self,
+        token: &[u8],
+        data: &[u8],
+        time: Duration,
+    ) -> Result<Timestamp<Id>, Error>
+    where
+        Id: Clone + for<'a> TryFrom<&'a Name>,
+        Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+    {
+        let signed = signed_data::decode(token)?;
+        if signed.encap_content_info.econtent_type != ID_CT_TST_INFO {
+            return Err(Error::Verification);
+        }
+        let content = signed_data::encapsulated_content(&signed)?.ok_or(Error::Verification)?;
+        let certificates = signed_data::embedded_certificates(&signed);
+        let signer = match signed.signer_infos.0.as_slice() {
+            [signer] => signer,
+            _ => return Err(Error::Verification),
+        };
+        let cert =
+            signed_data::find_signer(&signer.sid, &certificates)?.ok_or(Error::Verification)?;
+        signed_data::verify_signer(signer, cert, ID_CT_TST_INFO, &content)?;
+        check_signing_certificate(signer, cert)?;
+
+        match cert.tbs_certificate.get::<ExtendedKeyUsage>()? {
+            Some((true, usage)) if usage.0 == [ID_KP_TIME_STAMPING] => {}
+            _ => return Err(Error::InvalidPurpose),
+        }
+        let path = self.verify_at(cert, &certificates, time)?;
+        path.check_purpose(ID_KP_TIME_STAMPING)?;
+
+        let info = TstInfo::from_der(&content)?;
+        if info.version != 1 {
+            return Err(Error::Verification);
+        }
+        let imprint = info.message_imprint;
+        if imprint.hashed_message.as_bytes() != digest(imprint.hash_algorithm.oid, data)? {
+            return Err(Error::Verification);
+        }
+        let accuracy = info.accuracy.map(|a| {
+            Duration::from_secs(a.seconds.unwrap_or(0).into())
+                + Duration::from_millis(a.millis.unwrap_or(0).into())
+                + Duration::from_micros(a.micros.unwrap_or(0).into())
+        });
+        Ok(Timestamp {
+            gen_time: gen_time(info.gen_time)?,
+            accuracy,
+            policy: info.policy,
+            serial_number: info.serial_number.as_bytes().to_vec(),
+            path,
+        })
+    }
+}
+
+/// Decodes `genTime`, which unlike other DER GeneralizedTime values may carry fractional seconds
+/// ([RFC 3161 Section 2.4.2]). The fraction is truncated.
+///
+/// [RFC 3161 Section 2.4.2]: https://datatracker.ietf.org/doc/html/rfc3161#section-2.4.2
+fn gen_time(time: AnyRef<'_>) -> Result<Duration, Error> {
+    if time.tag() != Tag::GeneralizedTime {
+        return Err(Error::Verification);
+    }
+    let (digits, rest) = match time.value() {
+        [digits @ .., b'Z'] if digits.len() >= 14 => digits.split_at(14),
+        _ => return Err(Error::Verification),
+    };
+    let fraction = match rest {
+        [] => true,
+        [b'.', fraction @ ..] => !fraction.is_empty() && fraction.iter().all(u8::is_ascii_digit),
+        _ => false,
+    };
+    if !fraction || !digits.iter().all(u8::is_ascii_digit) {
+        return Err(Error::Verification);
+    }
+    let number = |range: core::ops::Range<usize>| {
+        digits[range]
+            .iter()
+            .fold(0u16, |n, d| n * 10 + u16::from(d - b'0'))
+    };
+    let date_time = DateTime::new(
+        number(0..4),
+        number(4..6) as u8,
+        number(6..8) as u8,
+        number(8..10) as u8,
+        number(10..12) as u8,
+        number(12..14) as u8,
+    )?;
+    Ok(date_time.unix_duration())
+}
+
+/// Checks that the `signingCertificate` or `signingCertificateV2` attribute of `signer` identifies
+/// `cert` ([RFC 3161 Section 2.4.1], [RFC 5816]). Only the first `ESSCertID` refers to the signer's
+/// certificate, the others may identify its issuers.
+///
+/// [RFC 3161 Section 2.4.1]: https://datatracker.ietf.org/doc/html/rfc3161#section-2.4.1
+/// [RFC 5816]: https://datatracker.ietf.org/doc/html/rfc5816
+fn check_signing_certificate(signer: &SignerInfo, cert: &Certificate) -> Result<(), Error> {
+    let mut found = signer
+        .signed_attrs
+        .iter()
+        .flat_map(|a| a.iter())
+        .filter(|a| a.oid == ID_AA_SIGNING_CERTIFICATE || a.oid == ID_AA_SIGNING_CERTIFICATE_V_2);
+    let attr = match (found.next(), found.next()) {
+        (Some(attr), None) if attr.values.len() == 1 => attr,
+        _ => return Err(Error::Verification),
+    };
+    let value = attr.values.get(0).ok_or(Error::Verification)?;
+    let (algorithm, hash, issuer_serial) = if attr.oid == ID_AA_SIGNING_CERTIFICATE_V_2 {
+        let certs = value.decode_as::<SigningCertificateV2>()?.certs;
+        let id = certs.into_iter().next().ok_or(Error::Verification)?;
+        let algorithm = id.hash_algorithm.map_or(ID_SHA_256, |a| a.oid);
+        (algorithm, id.cert_hash, id.issuer_serial)
+    } else {
+        let certs = value.decode_as::<SigningCertificate>()?.certs;
+        let id = certs.into_iter().next().ok_or(Error::Verification)?;
+        (ID_SHA_1, id.cert_hash, id.issuer_serial)
+    };
+    if hash.as_bytes() != digest(algorithm, &cert.to_der()?)? {
+        return Err(Error::Verification);
+    }
+    if let Some(issuer_serial) = issuer_serial {
+        let issuer = GeneralName::DirectoryName(cert.tbs_certificate.issuer.clone());
+        if issuer_serial.serial_number != cert.tbs_certificate.serial_number
+            || issuer_serial.issuer != [issuer]
+        {
+            return Err(Error::Verification);
+        }
+    }
+    Ok(())
+}
+
+/// Serializes the accuracy of a timestamp in whole milliseconds
+#[cfg(feature = "serde")]
+fn serialize_accuracy<S: serde::Serializer>(
+    accuracy: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use serde::Serialize;
+    accuracy
+        .map(|a| u64::try_from(a.as_millis()).unwrap_or(u64::MAX))
+        .serialize(serializer)
+}
+
+/// ```text
+/// TSTInfo ::= SEQUENCE  {
+///    version                      INTEGER  { v1(1) },
+///    policy                       TSAPolicyId,
+///    messageImprint               MessageImprint,
+///    serialNumber                 INTEGER,
+///    genTime                      GeneralizedTime,
+///    accuracy                     Accuracy                 OPTIONAL,
+///    ordering                     BOOLEAN             DEFAULT FALSE,
+///    nonce                        INTEGER                  OPTIONAL,
+///    tsa                          [0] GeneralName          OPTIONAL,
+///    extensions                   [1] IMPLICIT Extensions   OPTIONAL  }
+/// ```
+#[derive(Sequence)]
+struct TstInfo<'a> {
+    version: u8,
+    policy: ObjectIdentifier,
+    message_imprint: MessageImprint<'a>,
+    serial_number: IntRef<'a>,
+    gen_time: AnyRef<'a>,
+    accuracy: Option<Accuracy>,
+    #[asn1(default = "Default::default")]
+    ordering: bool,
+    nonce: Option<IntRef<'a>>,
+    #[asn1(context_specific = "0", optional = "true")]
+    tsa: Option<GeneralName>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    extensions: Option<Extensions>,
+}
+
+/// ```text
+/// MessageImprint ::= SEQUENCE  {
+///    hashAlgorithm                AlgorithmIdentifier,
+///    hashedMessage                OCTET STRING  }
+/// ```
+#[derive(Sequence)]
+struct MessageImprint<'a> {
+    hash_algorithm: AlgorithmIdentifierOwned,
+    hashed_message: OctetStringRef<'a>,
+}
+
+/// ```text
+/// Accuracy ::= SEQUENCE {
+///    seconds        INTEGER              OPTIONAL,
+///    millis     [0] INTEGER  (1..999)    OPTIONAL,
+///    micros     [1] INTEGER  (1..999)    OPTIONAL  }
+/// ```
+#[derive(Sequence)]
+struct Accuracy {
+    seconds: Option<u32>,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    millis: Option<u16>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    micros: Option<u16>,
+}
+
+/// ```text
+/// SigningCertificate ::=  SEQUENCE {
+///    certs        SEQUENCE OF ESSCertID,
+///    policies     SEQUENCE OF PolicyInformation OPTIONAL }
+/// ```
+#[derive(Sequence)]
+struct SigningCertificate {
+    certs: Vec<EssCertId>,
+    policies: Option<Vec<Any>>,
+}
+
+/// ```text
+/// SigningCertificateV2 ::=  SEQUENCE {
+///    certs        SEQUENCE OF ESSCertIDv2,
+///    policies     SEQUENCE OF PolicyInformation OPTIONAL }
+/// ```
+#[derive(Sequence)]
+struct SigningCertificateV2 {
+    certs: Vec<EssCertIdV2>,
+    policies: Option<Vec<Any>>,
+}
+
+/// ```text
+/// ESSCertID ::=  SEQUENCE {
+///    certHash                 Hash,
+///    issuerSerial             IssuerSerial OPTIONAL }
+/// ```
+#[derive(Sequence)]
+struct EssCertId {
+    cert_hash: OctetString,
+    issuer_serial: Option<IssuerSerial>,
+}
+
+/// ```text
+/// ESSCertIDv2 ::=  SEQUENCE {
+///    hashAlgorithm            AlgorithmIdentifier
+///                             DEFAULT {algorithm id-sha256},
+///    certHash                 Hash,
+///    issuerSerial             IssuerSerial OPTIONAL }
+/// ```
+#[derive(Sequence)]
+struct EssCertIdV2 {
+    hash_algorithm: Option<AlgorithmIdentifierOwned>,
+    cert_hash: OctetString,
+    issuer_serial: Option<IssuerSerial>,
+}
+
+/// ```text
+/// IssuerSerial ::= SEQUENCE {
+///    issuer                   GeneralNames,
+///    serialNumber             CertificateSerialNumber }
+/// ```
+#[derive(Sequence)]
+struct IssuerSerial {
+    issuer: GeneralNames,
+    serial_number: SerialNumber,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, signed_data::SignedDataVerifier, MemCertificateStore};
+    use core::time::Duration;
+    use der::DecodePem;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const MANIFEST: &[u8] = include_bytes!("../testdata/manifest.txt");
+    const TOKEN: &[u8] = include_bytes!("../testdata/manifest.tst");
+    const TOKEN_CODE_SIGNING: &[u8] = include_bytes!("../testdata/manifest-codesigning.tst");
+    const TOKEN_FRACTION: &[u8] = include_bytes!("../testdata/manifest-fraction.tst");
+    const TOKEN_NO_ESS: &[u8] = include_bytes!("../testdata/manifest-noess.tst");
+    const TOKEN_SWAPPED: &[u8] = include_bytes!("../testdata/manifest-swapped.tst");
+    const SIGNED: &[u8] = include_bytes!("../testdata/manifest-timestamped.p7s");
+
+    /// 2027-01-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_798_761_600);
+
+    /// 2026-10-18T12:00:00Z
+    const GEN_TIME: Duration = Duration::from_secs(1_792_324_800);
+
+    fn store() -> MemCertificateStore {
+        let mut store = MemCertificateStore::new();
+        store
+            .insert::<Error>(Certificate::from_pem(ROOT).unwrap())
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn tokens() {
+        let store = store();
+        let timestamp = store.verify_timestamp(TOKEN, MANIFEST, NOW).unwrap();
+        assert_eq!(timestamp.gen_time, GEN_TIME);
+        assert_eq!(timestamp.accuracy, Some(Duration::from_millis(1_500)));
+        assert_eq!(timestamp.serial_number, [1]);
+
+        assert!(matches!(
+            store.verify_timestamp(TOKEN, b"tampered manifest\n", NOW),
+            Err(Error::Verification)
+        ));
+        // genTime is 20261018120000.25Z
+        let timestamp = store
+            .verify_timestamp(TOKEN_FRACTION, MANIFEST, NOW)
+            .unwrap();
+        assert_eq!(timestamp.gen_time, GEN_TIME);
+        assert_eq!(timestamp.serial_number, [3]);
+        // Signed by a code signing certificate rather than a timestamp authority
+        assert!(matches!(
+            store.verify_timestamp(TOKEN_CODE_SIGNING, MANIFEST, NOW),
+            Err(Error::InvalidPurpose)
+        ));
+    }
+
+    #[test]
+    fn signing_certificate() {
+        let store = store();
+        // No signingCertificate or signingCertificateV2 attribute
+        assert!(matches!(
+            store.verify_timestamp(TOKEN_NO_ESS, MANIFEST, NOW),
+            Err(Error::Verification)
+        ));
+        // Embeds another certificate for the same key, which the attribute does not identify
+        assert!(matches!(
+            store.verify_timestamp(TOKEN_SWAPPED, MANIFEST, NOW),
+            Err(Error::Verification)
+        ));
+    }
+
+    #[test]
+    fn timestamped_signature() {
+        let store = store();
+        // The signer's certificate expired in 2034, the timestamp authority's expires in 2037
+        let later = Duration::from_secs(2_051_222_400);
+        let verifier = SignedDataVerifier::new(&store, later);
+        assert!(matches!(verifier.verify(SIGNED, None), Err(Error::Expired)));
+
+        let verified = verifier.use_timestamps(true).verify(SIGNED, None).unwrap();
+        let signer = &verified.signers[0];
+        assert_eq!(signer.validation_time, GEN_TIME);
+        assert_eq!(
+            signer.timestamp.as_ref().map(|t| t.gen_time),
+            Some(GEN_TIME)
+        );
+    }
+}
@@ -0,0 +1,462 @@
+//! [rustls] certificate verifiers backed by a [`SharedCertificateStore`]
+//!
+//! [`ServerVerifier`] and [`ClientVerifier`] validate the peer's chain against the certificate
+//! store, check the `serverAuth` or `clientAuth` extended key usage and verify the TLS handshake
+//! signatures. Only the signature schemes supported by `x509-verify` are offered: Ed25519, ECDSA
+//! on P-256 and P-384, and RSA PKCS#1 v1.5 (TLS 1.2 only). RSA-PSS is not supported, so TLS 1.3
+//! peers with RSA keys can not be verified.
+
+use crate::{
+    error::Error,
+    loader::Loader,
+    ocsp::{self, OcspStatus},
+    SharedCertificateStore,
+};
+use alloc::{sync::Arc, vec::Vec};
+use const_oid::db::{
+    rfc5280::{ID_KP_CLIENT_AUTH, ID_KP_SERVER_AUTH},
+    rfc5912::{
+        ECDSA_WITH_SHA_256, ECDSA_WITH_SHA_384, SECP_256_R_1, SECP_384_R_1,
+        SHA_256_WITH_RSA_ENCRYPTION, SHA_384_WITH_RSA_ENCRYPTION, SHA_512_WITH_RSA_ENCRYPTION,
+    },
+    rfc8410::ID_ED_25519,
+};
+use core::time::Duration;
+use der::{
+    asn1::{Any, ObjectIdentifier},
+    referenced::OwnedToRef,
+    Decode, Encode,
+};
+use rustls::{
+    client::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    server::{ClientCertVerified, ClientCertVerifier},
+    CertificateError, DigitallySignedStruct, DistinguishedName, PeerMisbehaved, ServerName,
+    SignatureScheme,
+};
+use std::time::SystemTime;
+use x509_path::{verify_identity, CommonNameFallback, IdentityError, ReferenceId};
+use x509_verify::{
+    spki::AlgorithmIdentifierOwned,
+    x509_cert::{name::Name, Certificate},
+    Message, Signature, VerifyInfo, VerifyingKey,
+};
+
+/// Signature schemes which can be verified, in order of preference
+const SCHEMES: &[SignatureScheme] = &[
+    SignatureScheme::ED25519,
+    SignatureScheme::ECDSA_NISTP256_SHA256,
+    SignatureScheme::ECDSA_NISTP384_SHA384,
+    SignatureScheme::RSA_PKCS1_SHA256,
+    SignatureScheme::RSA_PKCS1_SHA384,
+    SignatureScheme::RSA_PKCS1_SHA512,
+];
+
+/// [`ServerCertVerifier`] which validates server certificates against a
+/// [`SharedCertificateStore`].
+///
+/// The end-entity certificate must be valid for `serverAuth` and one of its `subjectAltName`
+/// entries must match the server name. Stapled OCSP responses are checked on a best-effort
+/// basis: a verifiable response reporting the certificate as revoked fails the handshake, while
+/// an unverifiable or stale response is ignored.
+pub struct ServerVerifier<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    store: Arc<SharedCertificateStore<Id, L>>,
+}
+
+impl<Id, L> ServerVerifier<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Creates a [`ServerVerifier`] which verifies against the current snapshot of `store`
+    pub fn new(store: Arc<SharedCertificateStore<Id, L>>) -> Self {
+        Self { store }
+    }
+}
+
+impl<Id, L> ServerCertVerifier for ServerVerifier<Id, L>
+where
+    L: Loader<Id> + Clone + Send + Sync + 'static,
+    Id: Clone + Eq + Send + Sync + 'static + for<'a> TryFrom<&'a Certificate>,
+    Id: for<'a> TryFrom<&'a Name>,
+    Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+{
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::Certificate,
+        intermediates: &[rustls::Certificate],
+        server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let time = unix_time(now)?;
+        let cert = decode(end_entity)?;
+        let chain = intermediates
+            .iter()
+            .map(decode)
+            .collect::<Result<Vec<_>, _>>()?;
+        let chain: Vec<_> = chain.iter().collect();
+        let path = self
+            .store
+            .verify_at(&cert, &chain, time)
+            .map_err(certificate_error)?;
+        path.check_purpose(ID_KP_SERVER_AUTH)
+            .map_err(certificate_error)?;
+        check_server_name(&cert, server_name)?;
+        if let (false, Some(issuer)) = (ocsp_response.is_empty(), path.certificates().get(1)) {
+            if let Ok(OcspStatus::Revoked) =
+                ocsp::check_response(ocsp_response, &cert, issuer, time)
+            {
+                return Err(certificate_error(Error::Revoked));
+            }
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_handshake(message, cert, dss.scheme, dss.signature(), false)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_handshake(message, cert, dss.scheme, dss.signature(), true)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        SCHEMES.to_vec()
+    }
+
+    fn request_scts(&self) -> bool {
+        false
+    }
+}
+
+/// [`ClientCertVerifier`] which validates client certificates against a
+/// [`SharedCertificateStore`].
+///
+/// The end-entity certificate must be valid for `clientAuth`. The subjects sent to clients as
+/// acceptable certificate authorities are taken from the store when the verifier is created and
+/// are not refreshed by later updates to the store.
+pub struct ClientVerifier<Id, L>
+where
+    L: Loader<Id>,
+    Id: Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    store: Arc<SharedCertificateStore<Id, L>>,
+    subjects: Vec<DistinguishedName>,
+    mandatory: bool,
+}
+
+impl<Id, L> ClientVerifier<Id, L>
+where
+    L: Loader<Id> + Clone,
+    Id: Clone + Eq + for<'a> TryFrom<&'a Certificate>,
+{
+    /// Creates a [`ClientVerifier`] which verifies against the current snapshot of `store`.
+    /// Client authentication is mandatory.
+    pub fn new(store: Arc<SharedCertificateStore<Id, L>>) -> Result<Self, Error> {
+        let subjects = store
+            .snapshot()
+            .iter()
+            .map(|(_, c)| Ok(DistinguishedName::from(c.tbs_certificate.subject.to_der()?)))
+            .collect::<Result<_, Error>>()?;
+        Ok(Self {
+            store,
+            subjects,
+            mandatory: true,
+        })
+    }
+
+    /// Allows clients to connect without presenting a certificate. Certificates which are
+    /// presented are still verified.
+    pub fn allow_unauthenticated(mut self) -> Self {
+        self.mandatory = false;
+        self
+    }
+}
+
+impl<Id, L> ClientCertVerifier for ClientVerifier<Id, L>
+where
+    L: Loader<Id> + Clone + Send + Sync + 'static,
+    Id: Clone + Eq + Send + Sync + 'static + for<'a> TryFrom<&'a Certificate>,
+    Id: for<'a> TryFrom<&'a Name>,
+    Error: for<'a> From<<Id as TryFrom<&'a Name>>::Error>,
+{
+    fn client_auth_mandatory(&self) -> bool {
+        self.mandatory
+    }
+
+    fn client_auth_root_subjects(&self) -> &[DistinguishedName] {
+        &self.subjects
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &rustls::Certificate,
+        intermediates: &[rustls::Certificate],
+        now: SystemTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        let time = unix_time(now)?;
+        let cert = decode(end_entity)?;
+        let chain = intermediates
+            .iter()
+            .map(decode)
+            .collect::<Result<Vec<_>, _>>()?;
+        let chain: Vec<_> = chain.iter().collect();
+        let path = self
+            .store
+            .verify_at(&cert, &chain, time)
+            .map_err(certificate_error)?;
+        path.check_purpose(ID_KP_CLIENT_AUTH)
+            .map_err(certificate_error)?;
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_handshake(message, cert, dss.scheme, dss.signature(), false)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::Certificate,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_handshake(message, cert, dss.scheme, dss.signature(), true)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        SCHEMES.to_vec()
+    }
+}
+
+/// Verifies a handshake signature made by the key of `cert`. TLS 1.3 forbids RSA PKCS#1 v1.5 and
+/// binds each ECDSA scheme to a single curve.
+fn verify_handshake(
+    message: &[u8],
+    cert: &rustls::Certificate,
+    scheme: SignatureScheme,
+    signature: &[u8],
+    tls13: bool,
+) -> Result<HandshakeSignatureValid, rustls::Error> {
+    let unsupported =
+        rustls::Error::PeerMisbehaved(PeerMisbehaved::SignedHandshakeWithUnadvertisedSigScheme);
+    let bad_signature = || rustls::Error::InvalidCertificate(CertificateError::BadSignature);
+    let (oid, curve) = match scheme {
+        SignatureScheme::ED25519 => (ID_ED_25519, None),
+        SignatureScheme::ECDSA_NISTP256_SHA256 => (ECDSA_WITH_SHA_256, Some(SECP_256_R_1)),
+        SignatureScheme::ECDSA_NISTP384_SHA384 => (ECDSA_WITH_SHA_384, Some(SECP_384_R_1)),
+        SignatureScheme::RSA_PKCS1_SHA256 if !tls13 => (SHA_256_WITH_RSA_ENCRYPTION, None),
+        SignatureScheme::RSA_PKCS1_SHA384 if !tls13 => (SHA_384_WITH_RSA_ENCRYPTION, None),
+        SignatureScheme::RSA_PKCS1_SHA512 if !tls13 => (SHA_512_WITH_RSA_ENCRYPTION, None),
+        _ => return Err(unsupported),
+    };
+    let cert = decode(cert)?;
+    let spki = &cert.tbs_certificate.subject_public_key_info;
+    if let (true, Some(curve)) = (tls13, curve) {
+        let params = spki
+            .algorithm
+            .parameters
+            .as_ref()
+            .and_then(|p| p.decode_as::<ObjectIdentifier>().ok());
+        if params != Some(curve) {
+            return Err(unsupported);
+        }
+    }
+    let algorithm = AlgorithmIdentifierOwned {
+        oid,
+        // RSA PKCS#1 v1.5 signature algorithms carry explicit NULL parameters
+        parameters: if oid == ID_ED_25519 || curve.is_some() {
+            None
+        } else {
+            Some(Any::null())
+        },
+    };
+    let key = VerifyingKey::try_from(spki.owned_to_ref()).map_err(|_| bad_signature())?;
+    key.verify(VerifyInfo::new(
+        Message::from(message.to_vec()),
+        Signature::new(&algorithm, signature),
+    ))
+    .map_err(|_| bad_signature())?;
+    Ok(HandshakeSignatureValid::assertion())
+}
+
+/// Checks the end-entity certificate against the server name. The subject common name is never
+/// used.
+fn check_server_name(cert: &Certificate, server_name: &ServerName) -> Result<(), rustls::Error> {
+    let reference = match server_name {
+        ServerName::DnsName(name) => ReferenceId::Dns(name.as_ref()),
+        ServerName::IpAddress(addr) => ReferenceId::from(*addr),
+        _ => {
+            return Err(rustls::Error::InvalidCertificate(
+                CertificateError::NotValidForName,
+            ))
+        }
+    };
+    verify_identity(cert, &reference, CommonNameFallback::Disabled).map_err(|e| match e {
+        IdentityError::Certificate(_) => {
+            rustls::Error::InvalidCertificate(CertificateError::BadEncoding)
+        }
+        _ => rustls::Error::InvalidCertificate(CertificateError::NotValidForName),
+    })
+}
+
+fn decode(cert: &rustls::Certificate) -> Result<Certificate, rustls::Error> {
+    Certificate::from_der(&cert.0)
+        .map_err(|_| rustls::Error::InvalidCertificate(CertificateError::BadEncoding))
+}
+
+fn unix_time(now: SystemTime) -> Result<Duration, rustls::Error> {
+    now.duration_since(SystemTime::UNIX_EPOCH)
+        .map_err(|_| rustls::Error::FailedToGetCurrentTime)
+}
+
+/// Maps a verification error to the closest rustls certificate error
+fn certificate_error(e: Error) -> rustls::Error {
+    rustls::Error::InvalidCertificate(match e {
+        Error::Verification => CertificateError::UnknownIssuer,
+        Error::Expired => CertificateError::Expired,
+        Error::NotYetValid => CertificateError::NotValidYet,
+        Error::InvalidPurpose => CertificateError::InvalidPurpose,
+        Error::Revoked => CertificateError::Revoked,
+        Error::Certificate(_) | Error::Der(_) => CertificateError::BadEncoding,
+        e => CertificateError::Other(Arc::new(e)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_handshake, ClientVerifier, ServerVerifier};
+    use crate::{
+        error::Error, loader::MemLoader, name::NameBytes, MemCertificateStore,
+        SharedCertificateStore,
+    };
+    use alloc::sync::Arc;
+    use core::time::Duration;
+    use der::{DecodePem, Encode};
+    use rustls::{
+        client::ServerCertVerifier,
+        server::ClientCertVerifier,
+        CertificateError, PeerMisbehaved, ServerName,
+        SignatureScheme::{ED25519, RSA_PKCS1_SHA256},
+    };
+    use std::time::SystemTime;
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf.pem");
+    const REVOKED: &[u8] = include_bytes!("../testdata/ocsp-revoked.der");
+
+    /// 2027-01-01T00:00:00Z
+    const NOW: Duration = Duration::from_secs(1_798_761_600);
+
+    /// Ed25519 signature of `b"rscs handshake transcript"` by the leaf key
+    const SIGNATURE: &str = "43ab06b7c1a096a2bb373db3c6abffba57b661fbe4eacb5e343713a3fccbe2a7\
+                             9f3e1c6ec36f8515e743a62ed5aa3fcf9b7518edb4e35e0637714a4836b1100e";
+
+    fn store() -> Arc<SharedCertificateStore<NameBytes, MemLoader>> {
+        let mut store = MemCertificateStore::new();
+        store
+            .insert::<Error>(Certificate::from_pem(ROOT).unwrap())
+            .unwrap();
+        Arc::new(SharedCertificateStore::new(store))
+    }
+
+    fn rustls_cert(pem: &[u8]) -> rustls::Certificate {
+        rustls::Certificate(Certificate::from_pem(pem).unwrap().to_der().unwrap())
+    }
+
+    fn verify_server(name: &str, ocsp: &[u8]) -> Result<(), rustls::Error> {
+        let verifier = ServerVerifier::new(store());
+        verifier
+            .verify_server_cert(
+                &rustls_cert(LEAF),
+                &[rustls_cert(INTERMEDIATE)],
+                &ServerName::try_from(name).unwrap(),
+                &mut core::iter::empty(),
+                ocsp,
+                SystemTime::UNIX_EPOCH + NOW,
+            )
+            .map(|_| ())
+    }
+
+    #[test]
+    fn server_names() {
+        assert!(verify_server("leaf.example.com", &[]).is_ok());
+        assert!(verify_server("LEAF.example.com.", &[]).is_ok());
+        assert!(verify_server("www.leaf.example.com", &[]).is_ok());
+        assert!(verify_server("192.0.2.1", &[]).is_ok());
+        for name in ["example.com", "a.b.leaf.example.com", "192.0.2.2"] {
+            assert_eq!(
+                verify_server(name, &[]),
+                Err(rustls::Error::InvalidCertificate(
+                    CertificateError::NotValidForName
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn server_stapled_ocsp() {
+        assert_eq!(
+            verify_server("leaf.example.com", REVOKED),
+            Err(rustls::Error::InvalidCertificate(CertificateError::Revoked))
+        );
+        // Unparsable responses are ignored
+        assert!(verify_server("leaf.example.com", &[0x30, 0x00]).is_ok());
+    }
+
+    #[test]
+    fn client_purpose() {
+        let verifier = ClientVerifier::new(store()).unwrap();
+        assert_eq!(verifier.client_auth_root_subjects().len(), 1);
+        assert_eq!(
+            verifier
+                .verify_client_cert(
+                    &rustls_cert(LEAF),
+                    &[rustls_cert(INTERMEDIATE)],
+                    SystemTime::UNIX_EPOCH + NOW,
+                )
+                .map(|_| ()),
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::InvalidPurpose
+            ))
+        );
+    }
+
+    #[test]
+    fn handshake_signatures() {
+        let leaf = rustls_cert(LEAF);
+        let message = b"rscs handshake transcript";
+        let signature = hex::decode(SIGNATURE).unwrap();
+        for tls13 in [false, true] {
+            assert!(verify_handshake(message, &leaf, ED25519, &signature, tls13).is_ok());
+        }
+        assert!(verify_handshake(b"another", &leaf, ED25519, &signature, true).is_err());
+        assert_eq!(
+            verify_handshake(message, &leaf, RSA_PKCS1_SHA256, &signature, true).map(|_| ()),
+            Err(rustls::Error::PeerMisbehaved(
+                PeerMisbehaved::SignedHandshakeWithUnadvertisedSigScheme
+            ))
+        );
+    }
+}
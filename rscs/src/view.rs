@@ -0,0 +1,249 @@
+//! Borrowed certificate view
+//!
+//! A [`CertificateView`] borrows the DER encoding of a certificate and only locates the fields of
+//! the `TBSCertificate`. Fields are decoded on demand, which keeps large stores cheap to load and
+//! to hold in memory.
+
+use crate::error::Error;
+use const_oid::{
+    db::rfc5280::{ID_CE_AUTHORITY_KEY_IDENTIFIER, ID_CE_SUBJECT_KEY_IDENTIFIER},
+    ObjectIdentifier,
+};
+use der::{
+    asn1::{AnyRef, IntRef, OctetStringRef},
+    Decode, Reader, Sequence, SliceReader, Tag, TagNumber, Tagged,
+};
+use x509_path::{AuthorityKeyIdentifierRef, KeyIdentifier, SubjectKeyIdentifierRef};
+use x509_verify::{
+    spki::SubjectPublicKeyInfoRef,
+    x509_cert::{name::Name, time::Validity, Certificate},
+};
+
+/// Borrowed, lazily-parsed view of a DER-encoded certificate
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CertificateView<'a> {
+    der: &'a [u8],
+    tbs: &'a [u8],
+    serial_number: &'a [u8],
+    issuer: &'a [u8],
+    validity: &'a [u8],
+    subject: &'a [u8],
+    spki: &'a [u8],
+    extensions: Option<&'a [u8]>,
+}
+
+impl<'a> CertificateView<'a> {
+    /// Creates a view of the DER-encoded certificate. Only the structure of the certificate and
+    /// of its `TBSCertificate` is checked: the fields themselves are not decoded.
+    pub fn new(der: &'a [u8]) -> Result<Self, Error> {
+        let mut reader = SliceReader::new(der)?;
+        let tbs = reader.sequence(|cert| {
+            let tbs = cert.tlv_bytes()?;
+            // signatureAlgorithm and signatureValue
+            cert.tlv_bytes()?;
+            cert.tlv_bytes()?;
+            Ok(tbs)
+        })?;
+        let tbs = reader.finish(tbs)?;
+
+        let mut reader = SliceReader::new(tbs)?;
+        let view = reader.sequence(|fields| {
+            if fields.peek_tag()? == context_specific(TagNumber::N0) {
+                fields.tlv_bytes()?;
+            }
+            let serial_number = IntRef::decode(fields)?.as_bytes();
+            // signature
+            fields.tlv_bytes()?;
+            let issuer = fields.tlv_bytes()?;
+            let validity = fields.tlv_bytes()?;
+            let subject = fields.tlv_bytes()?;
+            let spki = fields.tlv_bytes()?;
+            let mut extensions = None;
+            while !fields.is_finished() {
+                let field = AnyRef::decode(fields)?;
+                if field.tag() == context_specific(TagNumber::N3) {
+                    extensions = Some(field.value());
+                }
+            }
+            Ok(Self {
+                der,
+                tbs,
+                serial_number,
+                issuer,
+                validity,
+                subject,
+                spki,
+                extensions,
+            })
+        })?;
+        Ok(reader.finish(view)?)
+    }
+
+    /// Returns the DER encoding of the certificate
+    pub fn der(&self) -> &'a [u8] {
+        self.der
+    }
+
+    /// Returns the DER encoding of the `TBSCertificate`, which the signature is computed over
+    pub fn tbs_der(&self) -> &'a [u8] {
+        self.tbs
+    }
+
+    /// Returns the serial number as a big-endian integer
+    pub fn serial_number(&self) -> &'a [u8] {
+        self.serial_number
+    }
+
+    /// Returns the DER encoding of the issuer
+    pub fn issuer_der(&self) -> &'a [u8] {
+        self.issuer
+    }
+
+    /// Returns the DER encoding of the subject
+    pub fn subject_der(&self) -> &'a [u8] {
+        self.subject
+    }
+
+    /// Decodes the issuer
+    pub fn issuer(&self) -> Result<Name, Error> {
+        Ok(Name::from_der(self.issuer)?)
+    }
+
+    /// Decodes the subject
+    pub fn subject(&self) -> Result<Name, Error> {
+        Ok(Name::from_der(self.subject)?)
+    }
+
+    /// Decodes the validity period
+    pub fn validity(&self) -> Result<Validity, Error> {
+        Ok(Validity::from_der(self.validity)?)
+    }
+
+    /// Decodes the subject public key info, borrowing the key itself
+    pub fn subject_public_key_info(&self) -> Result<SubjectPublicKeyInfoRef<'a>, Error> {
+        Ok(SubjectPublicKeyInfoRef::from_der(self.spki)?)
+    }
+
+    /// Returns the criticality and the DER-encoded value of the extension identified by `oid`
+    pub fn extension(&self, oid: ObjectIdentifier) -> Result<Option<(bool, &'a [u8])>, Error> {
+        let extensions = match self.extensions {
+            Some(extensions) => extensions,
+            None => return Ok(None),
+        };
+        let mut reader = SliceReader::new(extensions)?;
+        let found = reader.sequence(|seq| {
+            let mut found = None;
+            while !seq.is_finished() {
+                let extension = ExtensionRef::decode(seq)?;
+                if extension.extn_id == oid && found.is_none() {
+                    found = Some((extension.critical, extension.extn_value.as_bytes()));
+                }
+            }
+            Ok(found)
+        })?;
+        Ok(reader.finish(found)?)
+    }
+
+    /// Returns the subject key identifier extension, if any
+    pub fn subject_key_identifier(&self) -> Result<Option<KeyIdentifier<'a>>, Error> {
+        match self.extension(ID_CE_SUBJECT_KEY_IDENTIFIER)? {
+            Some((_, value)) => Ok(Some(SubjectKeyIdentifierRef::from_der(value)?.into())),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the `keyIdentifier` of the authority key identifier extension, if any
+    pub fn authority_key_identifier(&self) -> Result<Option<KeyIdentifier<'a>>, Error> {
+        match self.extension(ID_CE_AUTHORITY_KEY_IDENTIFIER)? {
+            Some((_, value)) => Ok(AuthorityKeyIdentifierRef::from_der(value)?.key_id()),
+            None => Ok(None),
+        }
+    }
+
+    /// Fully decodes the certificate
+    pub fn to_certificate(&self) -> Result<Certificate, Error> {
+        Ok(Certificate::from_der(self.der)?)
+    }
+}
+
+/// Constructed context-specific tag, as used by the explicitly tagged `TBSCertificate` fields
+fn context_specific(number: TagNumber) -> Tag {
+    Tag::ContextSpecific {
+        constructed: true,
+        number,
+    }
+}
+
+/// ```text
+/// Extension  ::=  SEQUENCE  {
+///      extnID      OBJECT IDENTIFIER,
+///      critical    BOOLEAN DEFAULT FALSE,
+///      extnValue   OCTET STRING  }
+/// ```
+#[derive(Sequence)]
+struct ExtensionRef<'a> {
+    extn_id: ObjectIdentifier,
+    #[asn1(default = "Default::default")]
+    critical: bool,
+    extn_value: OctetStringRef<'a>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CertificateView;
+    use der::{DecodePem, Encode};
+    use x509_verify::x509_cert::{
+        ext::pkix::{AuthorityKeyIdentifier, SubjectKeyIdentifier},
+        Certificate,
+    };
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const LEAF: &[u8] = include_bytes!("../testdata/leaf.pem");
+
+    #[test]
+    fn view_fields() {
+        for pem in [ROOT, LEAF] {
+            let cert = Certificate::from_pem(pem).unwrap();
+            let der = cert.to_der().unwrap();
+            let view = CertificateView::new(&der).unwrap();
+            let tbs = &cert.tbs_certificate;
+            assert_eq!(view.tbs_der(), tbs.to_der().unwrap());
+            assert_eq!(view.serial_number(), tbs.serial_number.as_bytes());
+            assert_eq!(view.subject().unwrap(), tbs.subject);
+            assert_eq!(view.issuer_der(), tbs.issuer.to_der().unwrap());
+            assert_eq!(view.validity().unwrap(), tbs.validity);
+            assert_eq!(
+                view.subject_public_key_info().unwrap().to_der().unwrap(),
+                tbs.subject_public_key_info.to_der().unwrap()
+            );
+            let ski = tbs.get::<SubjectKeyIdentifier>().unwrap().map(|(_, s)| s);
+            assert_eq!(
+                view.subject_key_identifier()
+                    .unwrap()
+                    .map(|k| k.as_bytes().to_vec()),
+                ski.map(|s| s.0.as_bytes().to_vec())
+            );
+            let aki = tbs
+                .get::<AuthorityKeyIdentifier>()
+                .unwrap()
+                .and_then(|(_, a)| a.key_identifier);
+            assert_eq!(
+                view.authority_key_identifier()
+                    .unwrap()
+                    .map(|k| k.as_bytes().to_vec()),
+                aki.map(|k| k.as_bytes().to_vec())
+            );
+            assert_eq!(view.to_certificate().unwrap(), cert);
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        let der = Certificate::from_pem(LEAF).unwrap().to_der().unwrap();
+        assert!(CertificateView::new(&der[..der.len() - 1]).is_err());
+        let mut trailing = der.clone();
+        trailing.push(0);
+        assert!(CertificateView::new(&trailing).is_err());
+        assert!(CertificateView::new(&der[4..]).is_err());
+    }
+}
@@ -0,0 +1,412 @@
+//! Trust directory watcher
+//!
+//! Polls a trust directory, or a single bundle file, for changes and applies them to a
+//! [`SharedCertificateStore`]. No platform notification API is required.
+
+use crate::{error::Error, loader::Loader, shared::SharedCertificateStore};
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::time::Duration;
+use der::Decode;
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::SystemTime,
+};
+use x509_verify::x509_cert::Certificate;
+
+/// Change observed by a [`TrustWatcher`]
+#[derive(Debug)]
+pub enum WatchEvent<'a> {
+    /// A certificate was added to the store
+    Added {
+        /// File the certificate was loaded from
+        path: &'a Path,
+
+        /// Added certificate
+        certificate: &'a Certificate,
+    },
+
+    /// A certificate was removed from the store
+    Removed {
+        /// File the certificate was loaded from
+        path: &'a Path,
+
+        /// Removed certificate
+        certificate: &'a Certificate,
+    },
+
+    /// A file could not be read or parsed. Certificates previously loaded from the file are kept.
+    /// The failure is reported once, until the file changes again.
+    Failed {
+        /// Offending file
+        path: &'a Path,
+
+        /// Reason for the failure
+        error: &'a Error,
+    },
+}
+
+/// State of a single watched file
+#[derive(Clone, Debug)]
+struct FileState {
+    modified: Option<SystemTime>,
+    len: u64,
+    digest: [u8; 32],
+    certificates: Vec<Certificate>,
+    failed: bool,
+}
+
+/// Watches a trust directory, or a single bundle file, and hot-reloads the certificates it
+/// contains into a [`SharedCertificateStore`].
+///
+/// Every regular, non-hidden file is read as either a PEM bundle or a single DER certificate.
+/// Files are only re-parsed when their modification time or size changed and their content hash
+/// differs from the previous poll. All changes of a poll are applied to the store in a single
+/// batch.
+///
+/// Several files may hold certificates with the same identifier, such as the same subject name.
+/// The store keeps the one of the file which sorts first by path, and falls back to the next one
+/// when that file no longer provides it.
+#[derive(Clone, Debug)]
+pub struct TrustWatcher {
+    source: PathBuf,
+    files: BTreeMap<PathBuf, FileState>,
+}
+
+impl TrustWatcher {
+    /// Creates a [`TrustWatcher`] for a trust directory or bundle file. Nothing is loaded until
+    /// the first [`TrustWatcher::poll`].
+    pub fn new(source: impl Into<PathBuf>) -> Self {
+        Self {
+            source: source.into(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the watched directory or bundle file
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// Checks the source for changes and applies them to `store`. `on_event` is called for every
+    /// certificate added to or removed from the store once the batch has been published, and for
+    /// every file which failed. Returns the number of changed certificates.
+    ///
+    /// Returns an error only when the source itself can not be listed.
+    pub fn poll<Id, L, F>(
+        &mut self,
+        store: &SharedCertificateStore<Id, L>,
+        mut on_event: F,
+    ) -> Result<usize, Error>
+    where
+        L: Loader<Id> + Clone,
+        Id: Clone + Eq + for<'a> TryFrom<&'a Certificate, Error = Error>,
+        F: FnMut(WatchEvent<'_>),
+    {
+        let mut failed: Vec<(PathBuf, Error)> = Vec::new();
+        let mut next = BTreeMap::new();
+        for path in self.list()? {
+            let previous = self.files.get(&path);
+            match read_file(&path, previous) {
+                Ok(state) => {
+                    next.insert(path, state);
+                }
+                Err(error) => {
+                    // Keep the previous certificates and remember the failed version of the file
+                    let (modified, len) =
+                        fs::metadata(&path).map_or((None, 0), |m| (m.modified().ok(), m.len()));
+                    let state = match previous {
+                        Some(previous) => FileState {
+                            modified,
+                            len,
+                            failed: true,
+                            ..previous.clone()
+                        },
+                        None => FileState {
+                            modified,
+                            len,
+                            digest: [0; 32],
+                            certificates: Vec::new(),
+                            failed: true,
+                        },
+                    };
+                    next.insert(path.clone(), state);
+                    failed.push((path, error));
+                }
+            }
+        }
+
+        // Identifiers of the certificates which appeared or disappeared
+        let mut changed: Vec<Id> = Vec::new();
+        for (files, other) in [(&self.files, &next), (&next, &self.files)] {
+            for cert in files.values().flat_map(|state| &state.certificates) {
+                if find(other, |c| c == cert).is_none() {
+                    let id = Id::try_from(cert)?;
+                    if !changed.contains(&id) {
+                        changed.push(id);
+                    }
+                }
+            }
+        }
+
+        // The first file providing an identifier wins. Events reflect what the store held
+        // before and after the batch.
+        let (removed, added) = match changed.is_empty() {
+            true => (Vec::new(), Vec::new()),
+            false => store.try_update(|s| {
+                let mut removed: Vec<(&Path, &Certificate)> = Vec::new();
+                let mut added: Vec<(&Path, &Certificate)> = Vec::new();
+                for id in &changed {
+                    let wanted = find(&next, |c| Id::try_from(c).map_or(false, |i| i == *id));
+                    let current = s.get(id);
+                    if current == wanted.map(|(_, c)| c) {
+                        continue;
+                    }
+                    // Certificates the store got from elsewhere are only replaced, never removed
+                    let previous = current.and_then(|current| find(&self.files, |c| c == current));
+                    match wanted {
+                        Some((path, cert)) => {
+                            s.insert::<Error>(cert.clone())?;
+                            added.push((path, cert));
+                        }
+                        None if previous.is_some() => {
+                            s.remove(id);
+                        }
+                        None => continue,
+                    }
+                    removed.extend(previous);
+                }
+                Ok::<_, Error>((removed, added))
+            })?,
+        };
+
+        for (path, certificate) in &removed {
+            on_event(WatchEvent::Removed { path, certificate });
+        }
+        for (path, certificate) in &added {
+            on_event(WatchEvent::Added { path, certificate });
+        }
+        for (path, error) in &failed {
+            on_event(WatchEvent::Failed { path, error });
+        }
+
+        let changes = removed.len() + added.len();
+        self.files = next;
+        Ok(changes)
+    }
+
+    /// Polls the source every `interval` until `stop` is set. Errors listing the source are
+    /// reported through `on_error` and do not stop the watcher.
+    pub fn run<Id, L, F, G>(
+        &mut self,
+        store: &SharedCertificateStore<Id, L>,
+        interval: Duration,
+        stop: &AtomicBool,
+        mut on_event: F,
+        mut on_error: G,
+    ) where
+        L: Loader<Id> + Clone,
+        Id: Clone + Eq + for<'a> TryFrom<&'a Certificate, Error = Error>,
+        F: FnMut(WatchEvent<'_>),
+        G: FnMut(Error),
+    {
+        while !stop.load(Ordering::Relaxed) {
+            if let Err(e) = self.poll(store, &mut on_event) {
+                on_error(e);
+            }
+            thread::sleep(interval);
+        }
+    }
+
+    /// Lists the files which make up the source
+    fn list(&self) -> Result<Vec<PathBuf>, Error> {
+        if !fs::metadata(&self.source)?.is_dir() {
+            return Ok(alloc::vec![self.source.clone()]);
+        }
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(&self.source)? {
+            let entry = entry?;
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if !hidden && entry.file_type()?.is_file() {
+                paths.push(entry.path());
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+/// Parses certificates from either a PEM bundle or a single DER certificate. With the `pkcs7`
+/// feature, PEM and DER PKCS#7 bundles are also accepted.
+pub fn parse_certificates(data: &[u8]) -> Result<Vec<Certificate>, Error> {
+    let start = data
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(data.len());
+    if data[start..].starts_with(b"-----BEGIN") {
+        #[cfg(feature = "pkcs7")]
+        if data[start..].starts_with(b"-----BEGIN PKCS7")
+            || data[start..].starts_with(b"-----BEGIN CMS")
+        {
+            return Ok(crate::pkcs7::Pkcs7Bundle::from_pem(data)?.certificates);
+        }
+        Ok(Certificate::load_pem_chain(data)?)
+    } else {
+        match Certificate::from_der(data) {
+            Ok(cert) => Ok(alloc::vec![cert]),
+            #[cfg(feature = "pkcs7")]
+            Err(e) => crate::pkcs7::Pkcs7Bundle::from_der(data)
+                .map(|bundle| bundle.certificates)
+                .map_err(|_| Error::Der(e)),
+            #[cfg(not(feature = "pkcs7"))]
+            Err(e) => Err(Error::Der(e)),
+        }
+    }
+}
+
+/// Reads `path` unless neither its modification time, its size nor its content changed. An
+/// unchanged file which previously failed is returned as is, without reporting it again.
+fn read_file(path: &Path, previous: Option<&FileState>) -> Result<FileState, Error> {
+    let metadata = fs::metadata(path)?;
+    let (modified, len) = (metadata.modified().ok(), metadata.len());
+    if let Some(previous) = previous {
+        if previous.modified.is_some() && previous.modified == modified && previous.len == len {
+            return Ok(previous.clone());
+        }
+    }
+    let data = fs::read(path)?;
+    let digest: [u8; 32] = Sha256::digest(&data).into();
+    if let Some(previous) = previous {
+        if previous.digest == digest && !previous.failed {
+            return Ok(FileState {
+                modified,
+                len,
+                ..previous.clone()
+            });
+        }
+    }
+    let certificates = parse_certificates(&data)?;
+    Ok(FileState {
+        modified,
+        len,
+        digest,
+        certificates,
+        failed: false,
+    })
+}
+
+/// Returns the first certificate of `files` matching `predicate`, and the file it is in
+fn find(
+    files: &BTreeMap<PathBuf, FileState>,
+    mut predicate: impl FnMut(&Certificate) -> bool,
+) -> Option<(&Path, &Certificate)> {
+    files.iter().find_map(|(path, state)| {
+        let cert = state.certificates.iter().find(|c| predicate(c))?;
+        Some((path.as_path(), cert))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TrustWatcher, WatchEvent};
+    use crate::{loader::MemLoader, name::NameBytes, MemCertificateStore, SharedCertificateStore};
+    use der::DecodePem;
+    use std::{fs, vec::Vec};
+    use x509_verify::x509_cert::Certificate;
+
+    const ROOT: &[u8] = include_bytes!("../testdata/root.pem");
+    const INTERMEDIATE: &[u8] = include_bytes!("../testdata/intermediate.pem");
+    const INTERMEDIATE_2: &[u8] = include_bytes!("../testdata/intermediate2.pem");
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Seen {
+        Added,
+        Removed,
+        Failed,
+    }
+
+    fn poll(
+        watcher: &mut TrustWatcher,
+        store: &SharedCertificateStore<NameBytes, MemLoader>,
+    ) -> Vec<Seen> {
+        let mut seen = Vec::new();
+        watcher
+            .poll(store, |e| {
+                seen.push(match e {
+                    WatchEvent::Added { .. } => Seen::Added,
+                    WatchEvent::Removed { .. } => Seen::Removed,
+                    WatchEvent::Failed { .. } => Seen::Failed,
+                })
+            })
+            .unwrap();
+        seen
+    }
+
+    #[test]
+    fn watch_directory() {
+        let dir = std::env::temp_dir().join(std::format!("rscs-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("root.pem"), ROOT).unwrap();
+
+        let store = SharedCertificateStore::new(MemCertificateStore::new());
+        let mut watcher = TrustWatcher::new(&dir);
+        assert_eq!(poll(&mut watcher, &store), [Seen::Added]);
+        assert_eq!(poll(&mut watcher, &store), []);
+        assert_eq!(store.snapshot().iter().count(), 1);
+
+        fs::write(dir.join("broken.pem"), b"-----BEGIN CERTIFICATE-----\n").unwrap();
+        fs::write(dir.join("intermediate.pem"), INTERMEDIATE).unwrap();
+        fs::remove_file(dir.join("root.pem")).unwrap();
+        let seen = poll(&mut watcher, &store);
+        assert!(seen.contains(&Seen::Added));
+        assert!(seen.contains(&Seen::Removed));
+        assert!(seen.contains(&Seen::Failed));
+        assert_eq!(store.snapshot().iter().count(), 1);
+
+        // The broken file is only reported again once it changes
+        assert_eq!(poll(&mut watcher, &store), []);
+        fs::write(dir.join("broken.pem"), b"-----BEGIN CERTIFICATE-----\n\n").unwrap();
+        assert_eq!(poll(&mut watcher, &store), [Seen::Failed]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn same_subject_files() {
+        let dir = std::env::temp_dir().join(std::format!("rscs-watch-same-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.pem"), INTERMEDIATE).unwrap();
+        fs::write(dir.join("b.pem"), INTERMEDIATE_2).unwrap();
+
+        let store = SharedCertificateStore::new(MemCertificateStore::new());
+        let mut watcher = TrustWatcher::new(&dir);
+        let intermediate = Certificate::from_pem(INTERMEDIATE).unwrap();
+        let intermediate_2 = Certificate::from_pem(INTERMEDIATE_2).unwrap();
+        let holds = |cert: &Certificate| store.snapshot().iter().map(|(_, c)| c).eq([cert]);
+        // Only the certificate of the first file is added
+        assert_eq!(poll(&mut watcher, &store), [Seen::Added]);
+        assert!(holds(&intermediate));
+
+        // Deleting the file which was not used changes nothing
+        fs::remove_file(dir.join("b.pem")).unwrap();
+        assert_eq!(poll(&mut watcher, &store), []);
+        assert!(holds(&intermediate));
+        fs::write(dir.join("b.pem"), INTERMEDIATE_2).unwrap();
+        assert_eq!(poll(&mut watcher, &store), []);
+
+        // Deleting the used file lets the other certificate in
+        fs::remove_file(dir.join("a.pem")).unwrap();
+        assert_eq!(poll(&mut watcher, &store), [Seen::Removed, Seen::Added]);
+        assert!(holds(&intermediate_2));
+
+        // A file sorting first takes over
+        fs::write(dir.join("0.pem"), INTERMEDIATE).unwrap();
+        assert_eq!(poll(&mut watcher, &store), [Seen::Removed, Seen::Added]);
+        assert!(holds(&intermediate));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
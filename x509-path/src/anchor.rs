@@ -33,6 +33,49 @@ pub struct TrustAnchor<'a> {
     extensions: Option<Vec<&'a Extension>>,
 }
 
+impl<'a> TrustAnchor<'a> {
+    /// Returns the subject name of the trust anchor.
+    pub fn name(&self) -> &'a Name {
+        self.name
+    }
+
+    /// Returns the public key of the trust anchor.
+    pub fn public_key(&self) -> &SubjectPublicKeyInfoRef<'a> {
+        &self.pub_key
+    }
+
+    /// Returns the key identifier of the trust anchor. This is either the subject key identifier
+    /// extension or, in its absence, the SHA-1 digest of the public key.
+    pub fn key_id(&self) -> &KeyIdentifier<'a> {
+        &self.key_id
+    }
+
+    /// Returns the certificate policies of the trust anchor, if any.
+    pub fn policy_set(&self) -> Option<&PolicySet> {
+        self.policy_set.as_ref()
+    }
+
+    /// Returns the policy flags of the trust anchor, if any.
+    pub fn policy_flags(&self) -> Option<&PolicyFlags> {
+        self.policy_flags.as_ref()
+    }
+
+    /// Returns the name constraints of the trust anchor, if any.
+    pub fn name_constraints(&self) -> Option<&NameConstraints<'a, 'a>> {
+        self.name_constraints.as_ref()
+    }
+
+    /// Returns the path length constraint of the trust anchor, if any.
+    pub fn path_len_constraint(&self) -> Option<u32> {
+        self.path_len_constraint
+    }
+
+    /// Returns the extensions which are not processed into any of the other fields, if any.
+    pub fn extensions(&self) -> Option<&[&'a Extension]> {
+        self.extensions.as_deref()
+    }
+}
+
 impl<'a> TryFrom<&'a Certificate> for TrustAnchor<'a> {
     type Error = CertificateError;
 
@@ -1,9 +1,35 @@
 //! Validation target
 
-use crate::{CertificateError, PolicySet};
+use crate::{AuthorityKeyIdentifierRef, CertificateError, PolicySet};
 use const_oid::db::rfc5912::ID_CE_CERTIFICATE_POLICIES;
-use der::Decode;
+use der::{referenced::OwnedToRef, Decode};
 use x509_cert::{ext::pkix::CertificatePolicies, Certificate};
+use x509_verify::VerifyingKey;
+
+/// Returns `true` if the subject and issuer of `cert` are the same name.
+///
+/// Self-issued certificates include CA key rollover certificates, where a CA certifies its new
+/// key with the old one or vice versa. RFC 5280 does not count self-issued intermediates towards
+/// path length constraints and does not apply name constraints to them.
+pub fn is_self_issued(cert: &Certificate) -> bool {
+    cert.tbs_certificate.subject == cert.tbs_certificate.issuer
+}
+
+/// Returns `true` if `cert` is self-issued and its signature verifies with its own public key. The
+/// signature is only checked if the authority key identifier, when present, matches the key.
+pub fn is_self_signed(cert: &Certificate) -> bool {
+    is_self_issued(cert)
+        && match AuthorityKeyIdentifierRef::from_certificate(cert) {
+            Ok(Some(aki)) => aki.matches(cert).unwrap_or(false),
+            Ok(None) => true,
+            Err(_) => false,
+        }
+        && match VerifyingKey::try_from(cert.tbs_certificate.subject_public_key_info.owned_to_ref())
+        {
+            Ok(key) => key.verify(cert).is_ok(),
+            Err(_) => false,
+        }
+}
 
 /// Certificate target for path validation
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -39,6 +39,41 @@ impl From<der::Error> for CertificateError {
     }
 }
 
+/// Identity verification error types
+#[derive(Clone, Debug)]
+pub enum IdentityError {
+    /// The reference identifier is not syntactically valid
+    InvalidReference,
+
+    /// None of the certificate's identifiers match the reference identifier
+    Mismatch,
+
+    /// The certificate's extended key usage does not permit the identity's purpose
+    InvalidPurpose,
+
+    /// Certificate parsing errors
+    Certificate(CertificateError),
+}
+
+impl fmt::Display for IdentityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            IdentityError::InvalidReference => write!(f, "Invalid reference identifier"),
+            IdentityError::Mismatch => write!(f, "Certificate does not match the identifier"),
+            IdentityError::InvalidPurpose => {
+                write!(f, "Certificate is not valid for the identity's purpose")
+            }
+            IdentityError::Certificate(e) => write!(f, "Certificate error: {}", e),
+        }
+    }
+}
+
+impl From<CertificateError> for IdentityError {
+    fn from(other: CertificateError) -> Self {
+        Self::Certificate(other)
+    }
+}
+
 /// Path validation error types
 #[derive(Debug)]
 pub enum Error {
@@ -0,0 +1,591 @@
+//! Service identity verification as defined in [RFC 6125] and [RFC 9525]
+//!
+//! [RFC 6125]: https://datatracker.ietf.org/doc/html/rfc6125
+//! [RFC 9525]: https://datatracker.ietf.org/doc/html/rfc9525
+
+use crate::{
+    name::{GeneralNameRef, GeneralNameRefs},
+    CertificateError, IdentityError,
+};
+use alloc::{string::String, vec::Vec};
+use const_oid::db::{
+    rfc4519::CN,
+    rfc5280::{ANY_EXTENDED_KEY_USAGE, ID_CE_SUBJECT_ALT_NAME, ID_KP_EMAIL_PROTECTION},
+    rfc5912::ID_CE_EXT_KEY_USAGE,
+};
+use der::{
+    asn1::{Ia5StringRef, ObjectIdentifier, Utf8StringRef},
+    Decode, Tag, Tagged,
+};
+use x509_cert::{ext::pkix::ExtendedKeyUsage, name::Name, Certificate};
+
+/// `id-on-dnsSRV` as defined in [RFC 4985]
+///
+/// [RFC 4985]: https://datatracker.ietf.org/doc/html/rfc4985
+pub const ID_ON_DNS_SRV: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.8.7");
+
+/// `id-on-SmtpUTF8Mailbox` as defined in [RFC 8398]
+///
+/// [RFC 8398]: https://datatracker.ietf.org/doc/html/rfc8398
+pub const ID_ON_SMTP_UTF8_MAILBOX: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.8.9");
+
+/// Maximum length of a DNS domain name, without the trailing dot
+const MAX_DNS_NAME: usize = 253;
+
+/// Maximum length of a DNS label
+const MAX_DNS_LABEL: usize = 63;
+
+/// Punycode parameters from RFC 3492 Section 5
+const PUNYCODE_BASE: u32 = 36;
+const PUNYCODE_T_MIN: u32 = 1;
+const PUNYCODE_T_MAX: u32 = 26;
+const PUNYCODE_SKEW: u32 = 38;
+const PUNYCODE_DAMP: u32 = 700;
+
+/// Reference identifier of the service a client intends to connect to.
+///
+/// DNS domain names may be given either in A-label (`xn--`) form or with U-labels. U-labels are
+/// lowercased and converted to A-labels, but no other IDNA mapping or normalization is applied;
+/// names should be normalized by the caller beforehand.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ReferenceId<'a> {
+    /// DNS-ID. Matched against `dNSName` entries, which may contain a wildcard.
+    Dns(&'a str),
+
+    /// IPv4 address. Matched against `iPAddress` entries.
+    Ipv4([u8; 4]),
+
+    /// IPv6 address. Matched against `iPAddress` entries.
+    Ipv6([u8; 16]),
+
+    /// URI-ID. Matched against `uniformResourceIdentifier` entries with the same scheme and host.
+    Uri {
+        /// URI scheme, such as `sip`
+        scheme: &'a str,
+
+        /// DNS domain name of the host
+        host: &'a str,
+    },
+
+    /// SRV-ID. Matched against `SRVName` other names.
+    Srv {
+        /// Service name, with or without the leading underscore, such as `imap`
+        service: &'a str,
+
+        /// DNS domain name of the host
+        host: &'a str,
+    },
+}
+
+#[cfg(feature = "std")]
+impl From<std::net::IpAddr> for ReferenceId<'_> {
+    fn from(addr: std::net::IpAddr) -> Self {
+        match addr {
+            std::net::IpAddr::V4(addr) => Self::Ipv4(addr.octets()),
+            std::net::IpAddr::V6(addr) => Self::Ipv6(addr.octets()),
+        }
+    }
+}
+
+/// Whether the subject common name may be used as a DNS-ID.
+///
+/// RFC 9525 forbids the fallback, which is why it is disabled by default. When enabled, the
+/// common name is only considered if the certificate does not carry any DNS-ID, SRV-ID or URI-ID
+/// and the subject holds exactly one common name. The common name is then matched like a
+/// `dNSName`, including wildcards.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum CommonNameFallback {
+    /// Only the subject alternative names are considered
+    #[default]
+    Disabled,
+
+    /// The subject common name is considered when no DNS-ID, SRV-ID or URI-ID is present
+    Enabled,
+}
+
+/// Verifies that `cert` is valid for the service identified by `reference`.
+///
+/// Returns `IdentityError::InvalidReference` if `reference` is not a valid identifier and
+/// `IdentityError::Mismatch` if none of the certificate's identifiers match.
+pub fn verify_identity(
+    cert: &Certificate,
+    reference: &ReferenceId<'_>,
+    fallback: CommonNameFallback,
+) -> Result<(), IdentityError> {
+    let names = subject_alt_names(cert)?;
+    let reference = Reference::new(reference)?;
+    if names.iter().any(|n| reference.matches(n)) {
+        return Ok(());
+    }
+    if fallback == CommonNameFallback::Enabled && !has_application_ids(&names) {
+        if let (Reference::Dns(dns), Some(cn)) =
+            (&reference, common_name(&cert.tbs_certificate.subject))
+        {
+            if dns_id_matches(cn, dns, true) {
+                return Ok(());
+            }
+        }
+    }
+    Err(IdentityError::Mismatch)
+}
+
+/// Verifies that `cert` is valid for protecting mail sent to or from `mailbox`.
+///
+/// The mailbox is matched against `rfc822Name` entries and `SmtpUTF8Mailbox` other names. As
+/// required by RFC 5321, the local part is compared exactly while the domain is compared
+/// case-insensitively, after converting any U-labels to A-labels. The subject `emailAddress`
+/// attribute is not considered.
+///
+/// If the certificate carries the extended key usage extension, it must permit
+/// `id-kp-emailProtection`. Otherwise, `IdentityError::InvalidPurpose` is returned.
+pub fn verify_email(cert: &Certificate, mailbox: &str) -> Result<(), IdentityError> {
+    if let Some(extns) = &cert.tbs_certificate.extensions {
+        if let Some(e) = extns.iter().find(|e| e.extn_id == ID_CE_EXT_KEY_USAGE) {
+            let usage = ExtendedKeyUsage::from_der(e.extn_value.as_bytes())
+                .map_err(CertificateError::from)?;
+            if !usage.0.contains(&ID_KP_EMAIL_PROTECTION)
+                && !usage.0.contains(&ANY_EXTENDED_KEY_USAGE)
+            {
+                return Err(IdentityError::InvalidPurpose);
+            }
+        }
+    }
+    let (local, domain) = split_mailbox(mailbox).ok_or(IdentityError::InvalidReference)?;
+    let domain = normalize_dns_name(domain)?;
+    match subject_alt_names(cert)?
+        .iter()
+        .any(|n| mailbox_matches(n, local, &domain))
+    {
+        true => Ok(()),
+        false => Err(IdentityError::Mismatch),
+    }
+}
+
+/// Matches a presented mailbox against a local part and a normalized domain
+fn mailbox_matches(name: &GeneralNameRef<'_>, local: &str, domain: &str) -> bool {
+    let (l, d) = match name {
+        GeneralNameRef::Rfc822Name(n) => match split_mailbox(n.as_str()) {
+            Some(mailbox) => mailbox,
+            None => return false,
+        },
+        GeneralNameRef::OtherName(n) if n.type_id == ID_ON_SMTP_UTF8_MAILBOX => {
+            match n.value.decode_as::<Utf8StringRef<'_>>() {
+                Ok(value) => match split_mailbox(value.as_str()) {
+                    Some(mailbox) => mailbox,
+                    None => return false,
+                },
+                Err(_) => return false,
+            }
+        }
+        _ => return false,
+    };
+    l == local && normalize_dns_name(d).map_or(false, |d| d == domain)
+}
+
+/// Splits a mailbox into its local part and domain
+fn split_mailbox(mailbox: &str) -> Option<(&str, &str)> {
+    match mailbox.rsplit_once('@') {
+        Some((local, domain)) if !local.is_empty() && !domain.is_empty() => Some((local, domain)),
+        _ => None,
+    }
+}
+
+/// Returns the subject alternative names of `cert`
+pub(crate) fn subject_alt_names(
+    cert: &Certificate,
+) -> Result<GeneralNameRefs<'_>, CertificateError> {
+    match &cert.tbs_certificate.extensions {
+        Some(extns) => match extns.iter().find(|e| e.extn_id == ID_CE_SUBJECT_ALT_NAME) {
+            Some(e) => Ok(GeneralNameRefs::from_der(e.extn_value.as_bytes())?),
+            None => Ok(Vec::new()),
+        },
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Returns `true` if any DNS-ID, SRV-ID or URI-ID is present
+fn has_application_ids(names: &[GeneralNameRef<'_>]) -> bool {
+    names.iter().any(|n| match n {
+        GeneralNameRef::DnsName(_) | GeneralNameRef::UniformResourceIdentifier(_) => true,
+        GeneralNameRef::OtherName(n) => n.type_id == ID_ON_DNS_SRV,
+        _ => false,
+    })
+}
+
+/// Returns the single common name of `subject`. Names with zero or several common names, or
+/// whose common name is not a `UTF8String`, `PrintableString` or `IA5String`, return `None`.
+fn common_name(subject: &Name) -> Option<&str> {
+    let mut values = subject
+        .0
+        .iter()
+        .flat_map(|rdn| rdn.0.iter())
+        .filter(|atv| atv.oid == CN)
+        .map(|atv| &atv.value);
+    let value = values.next()?;
+    if values.next().is_some() {
+        return None;
+    }
+    match value.tag() {
+        Tag::Utf8String | Tag::PrintableString | Tag::Ia5String => {
+            core::str::from_utf8(value.value()).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Reference identifier with its DNS domain names normalized to lowercase A-labels
+enum Reference<'a> {
+    Dns(String),
+    Ip(&'a [u8]),
+    Uri { scheme: &'a str, host: String },
+    Srv { service: &'a str, host: String },
+}
+
+impl<'a> Reference<'a> {
+    fn new(reference: &'a ReferenceId<'_>) -> Result<Self, IdentityError> {
+        Ok(match reference {
+            ReferenceId::Dns(name) => Self::Dns(normalize_dns_name(name)?),
+            ReferenceId::Ipv4(addr) => Self::Ip(addr),
+            ReferenceId::Ipv6(addr) => Self::Ip(addr),
+            ReferenceId::Uri { scheme, host } => {
+                if scheme.is_empty() {
+                    return Err(IdentityError::InvalidReference);
+                }
+                Self::Uri {
+                    scheme,
+                    host: normalize_dns_name(host)?,
+                }
+            }
+            ReferenceId::Srv { service, host } => {
+                let service = service.strip_prefix('_').unwrap_or(service);
+                if service.is_empty() {
+                    return Err(IdentityError::InvalidReference);
+                }
+                Self::Srv {
+                    service,
+                    host: normalize_dns_name(host)?,
+                }
+            }
+        })
+    }
+
+    fn matches(&self, name: &GeneralNameRef<'_>) -> bool {
+        match (self, name) {
+            (Self::Dns(dns), GeneralNameRef::DnsName(n)) => dns_id_matches(n.as_str(), dns, true),
+            (Self::Ip(addr), GeneralNameRef::IpAddress(n)) => n.as_bytes() == *addr,
+            (Self::Uri { scheme, host }, GeneralNameRef::UniformResourceIdentifier(n)) => {
+                match uri_scheme_and_host(n.as_str()) {
+                    Some((s, h)) => {
+                        s.eq_ignore_ascii_case(scheme) && dns_id_matches(h, host, false)
+                    }
+                    None => false,
+                }
+            }
+            (Self::Srv { service, host }, GeneralNameRef::OtherName(n))
+                if n.type_id == ID_ON_DNS_SRV =>
+            {
+                let value = match n.value.decode_as::<Ia5StringRef<'_>>() {
+                    Ok(value) => value,
+                    Err(_) => return false,
+                };
+                match value
+                    .as_str()
+                    .strip_prefix('_')
+                    .and_then(|v| v.split_once('.'))
+                {
+                    Some((s, h)) => {
+                        s.eq_ignore_ascii_case(service) && dns_id_matches(h, host, false)
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Matches a presented DNS domain name against a normalized reference. A wildcard is only
+/// accepted as the complete left-most label of a name with at least three labels, and matches
+/// exactly one non-empty label.
+fn dns_id_matches(presented: &str, reference: &str, wildcards: bool) -> bool {
+    let presented = presented.strip_suffix('.').unwrap_or(presented);
+    match presented.strip_prefix("*.") {
+        Some(suffix) if wildcards => {
+            if !suffix.contains('.') || suffix.contains('*') {
+                return false;
+            }
+            match reference.split_once('.') {
+                Some((label, rest)) => !label.is_empty() && rest.eq_ignore_ascii_case(suffix),
+                None => false,
+            }
+        }
+        Some(_) => false,
+        None => !presented.contains('*') && presented.eq_ignore_ascii_case(reference),
+    }
+}
+
+/// Splits a URI into its scheme and host. URIs without an authority or whose host is an IP
+/// literal return `None`.
+pub(crate) fn uri_scheme_and_host(uri: &str) -> Option<(&str, &str)> {
+    let (scheme, rest) = uri.split_once(':')?;
+    let authority = rest.strip_prefix("//")?.split(['/', '?', '#']).next()?;
+    let host = match authority.rsplit_once('@') {
+        Some((_, host)) => host,
+        None => authority,
+    };
+    if host.starts_with('[') {
+        return None;
+    }
+    let host = host.split(':').next()?;
+    match host.is_empty() {
+        true => None,
+        false => Some((scheme, host)),
+    }
+}
+
+/// Lowercases a DNS domain name, converts its U-labels to A-labels and checks its syntax
+fn normalize_dns_name(name: &str) -> Result<String, IdentityError> {
+    let name = name.strip_suffix('.').unwrap_or(name);
+    let mut normalized = String::with_capacity(name.len());
+    for (i, label) in name.split('.').enumerate() {
+        if i > 0 {
+            normalized.push('.');
+        }
+        if label.is_ascii() {
+            normalized.push_str(&label.to_ascii_lowercase());
+        } else {
+            let label: Vec<char> = label.chars().flat_map(char::to_lowercase).collect();
+            normalized.push_str("xn--");
+            normalized.push_str(&punycode(&label).ok_or(IdentityError::InvalidReference)?);
+        }
+    }
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= MAX_DNS_LABEL
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+    };
+    if normalized.len() > MAX_DNS_NAME || !normalized.split('.').all(valid_label) {
+        return Err(IdentityError::InvalidReference);
+    }
+    Ok(normalized)
+}
+
+/// Encodes a label with Punycode as defined in [RFC 3492]
+///
+/// [RFC 3492]: https://datatracker.ietf.org/doc/html/rfc3492
+fn punycode(input: &[char]) -> Option<String> {
+    let digit = |d: u32| match d {
+        0..=25 => char::from(b'a' + d as u8),
+        _ => char::from(b'0' + (d - 26) as u8),
+    };
+    let mut output: String = input.iter().filter(|c| c.is_ascii()).collect();
+    let basic = output.len() as u32;
+    if basic > 0 {
+        output.push('-');
+    }
+    let (mut n, mut delta, mut bias, mut handled) = (0x80u32, 0u32, 72u32, basic);
+    while (handled as usize) < input.len() {
+        let m = input.iter().map(|&c| c as u32).filter(|&c| c >= n).min()?;
+        delta = delta.checked_add((m - n).checked_mul(handled + 1)?)?;
+        n = m;
+        for c in input.iter().map(|&c| c as u32) {
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = PUNYCODE_BASE;
+                loop {
+                    let t = if k <= bias {
+                        PUNYCODE_T_MIN
+                    } else if k >= bias + PUNYCODE_T_MAX {
+                        PUNYCODE_T_MAX
+                    } else {
+                        k - bias
+                    };
+                    if q < t {
+                        break;
+                    }
+                    output.push(digit(t + (q - t) % (PUNYCODE_BASE - t)));
+                    q = (q - t) / (PUNYCODE_BASE - t);
+                    k += PUNYCODE_BASE;
+                }
+                output.push(digit(q));
+                bias = punycode_adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta = delta.checked_add(1)?;
+        n = n.checked_add(1)?;
+    }
+    Some(output)
+}
+
+/// Punycode bias adaptation
+fn punycode_adapt(delta: u32, points: u32, first: bool) -> u32 {
+    let mut delta = if first {
+        delta / PUNYCODE_DAMP
+    } else {
+        delta / 2
+    };
+    delta += delta / points;
+    let mut k = 0;
+    while delta > ((PUNYCODE_BASE - PUNYCODE_T_MIN) * PUNYCODE_T_MAX) / 2 {
+        delta /= PUNYCODE_BASE - PUNYCODE_T_MIN;
+        k += PUNYCODE_BASE;
+    }
+    k + ((PUNYCODE_BASE - PUNYCODE_T_MIN + 1) * delta) / (delta + PUNYCODE_SKEW)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        common_name, dns_id_matches, mailbox_matches, normalize_dns_name, punycode,
+        uri_scheme_and_host, Reference, ReferenceId, ID_ON_DNS_SRV, ID_ON_SMTP_UTF8_MAILBOX,
+    };
+    use crate::name::{GeneralNameRef, OtherNameRef};
+    use alloc::vec::Vec;
+    use core::str::FromStr;
+    use der::{
+        asn1::{Ia5StringRef, OctetStringRef, Utf8StringRef},
+        AnyRef, Encode,
+    };
+    use x509_cert::name::Name;
+
+    fn matches(reference: ReferenceId<'_>, name: GeneralNameRef<'_>) -> bool {
+        Reference::new(&reference).unwrap().matches(&name)
+    }
+
+    fn dns(name: &str) -> GeneralNameRef<'_> {
+        GeneralNameRef::DnsName(Ia5StringRef::new(name).unwrap())
+    }
+
+    #[test]
+    fn dns_names() {
+        assert!(matches(
+            ReferenceId::Dns("WWW.Example.com."),
+            dns("www.example.COM")
+        ));
+        assert!(matches(
+            ReferenceId::Dns("www.example.com"),
+            dns("*.example.com")
+        ));
+        assert!(!matches(
+            ReferenceId::Dns("example.com"),
+            dns("*.example.com")
+        ));
+        assert!(!matches(
+            ReferenceId::Dns("a.b.example.com"),
+            dns("*.example.com")
+        ));
+        assert!(!matches(
+            ReferenceId::Dns("www.example.com"),
+            dns("w*.example.com")
+        ));
+        assert!(!matches(
+            ReferenceId::Dns("www.example.com"),
+            dns("www.*.com")
+        ));
+        assert!(!matches(ReferenceId::Dns("example.com"), dns("*.com")));
+        assert!(dns_id_matches("*.example.com", "www.example.com", true));
+        assert!(!dns_id_matches("*.example.com", "www.example.com", false));
+        assert!(Reference::new(&ReferenceId::Dns("-bad.example.com")).is_err());
+        assert!(Reference::new(&ReferenceId::Dns("bad..example.com")).is_err());
+    }
+
+    #[test]
+    fn idna_labels() {
+        let label: Vec<char> = "bücher".chars().collect();
+        assert_eq!(punycode(&label).unwrap(), "bcher-kva");
+        assert_eq!(
+            normalize_dns_name("Bücher.Example").unwrap(),
+            "xn--bcher-kva.example"
+        );
+        assert!(matches(
+            ReferenceId::Dns("www.bücher.example"),
+            dns("*.xn--bcher-kva.example")
+        ));
+    }
+
+    #[test]
+    fn ip_addresses() {
+        let v4 = [192, 0, 2, 1];
+        let mut v6 = [0u8; 16];
+        v6[..4].copy_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+        let ip = |b| GeneralNameRef::IpAddress(OctetStringRef::new(b).unwrap());
+        assert!(matches(ReferenceId::Ipv4(v4), ip(&v4)));
+        assert!(!matches(ReferenceId::Ipv4([192, 0, 2, 2]), ip(&v4)));
+        assert!(matches(ReferenceId::Ipv6(v6), ip(&v6)));
+        assert!(!matches(ReferenceId::Ipv4(v4), ip(&v6)));
+        assert!(!matches(ReferenceId::Dns("192.0.2.1"), ip(&v4)));
+    }
+
+    #[test]
+    fn uri_and_srv_ids() {
+        assert_eq!(
+            uri_scheme_and_host("sip://user@voice.example.com:5060/path"),
+            Some(("sip", "voice.example.com"))
+        );
+        assert_eq!(uri_scheme_and_host("urn:example"), None);
+        let uri = GeneralNameRef::UniformResourceIdentifier(
+            Ia5StringRef::new("sip://voice.example.com").unwrap(),
+        );
+        let reference = |scheme, host| ReferenceId::Uri { scheme, host };
+        assert!(matches(reference("SIP", "voice.example.com"), uri.clone()));
+        assert!(!matches(
+            reference("https", "voice.example.com"),
+            uri.clone()
+        ));
+        assert!(!matches(reference("sip", "www.example.com"), uri));
+
+        let value = Ia5StringRef::new("_imap.mail.example.com")
+            .unwrap()
+            .to_der()
+            .unwrap();
+        let srv = GeneralNameRef::OtherName(OtherNameRef {
+            type_id: ID_ON_DNS_SRV,
+            value: AnyRef::try_from(value.as_slice()).unwrap(),
+        });
+        let reference = |service, host| ReferenceId::Srv { service, host };
+        assert!(matches(reference("imap", "mail.example.com"), srv.clone()));
+        assert!(matches(reference("_IMAP", "mail.example.com"), srv.clone()));
+        assert!(!matches(reference("pop3", "mail.example.com"), srv));
+    }
+
+    #[test]
+    fn common_names() {
+        let name = Name::from_str("CN=www.example.com,O=Example").unwrap();
+        assert_eq!(common_name(&name), Some("www.example.com"));
+        let name = Name::from_str("CN=a.example.com,CN=b.example.com").unwrap();
+        assert_eq!(common_name(&name), None);
+        let name = Name::from_str("O=Example").unwrap();
+        assert_eq!(common_name(&name), None);
+    }
+
+    #[test]
+    fn mailboxes() {
+        let rfc822 = GeneralNameRef::Rfc822Name(Ia5StringRef::new("User@Example.COM").unwrap());
+        assert!(mailbox_matches(&rfc822, "User", "example.com"));
+        assert!(!mailbox_matches(&rfc822, "user", "example.com"));
+        assert!(!mailbox_matches(&rfc822, "User", "mail.example.com"));
+
+        let value = Utf8StringRef::new("用户@Bücher.example")
+            .unwrap()
+            .to_der()
+            .unwrap();
+        let utf8 = GeneralNameRef::OtherName(OtherNameRef {
+            type_id: ID_ON_SMTP_UTF8_MAILBOX,
+            value: AnyRef::try_from(value.as_slice()).unwrap(),
+        });
+        let domain = normalize_dns_name("bücher.EXAMPLE").unwrap();
+        assert!(mailbox_matches(&utf8, "用户", &domain));
+        assert!(!mailbox_matches(&utf8, "用户", "example"));
+        assert!(!mailbox_matches(&rfc822, "用户", &domain));
+    }
+}
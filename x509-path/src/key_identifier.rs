@@ -1,14 +1,24 @@
-//! Subject key identifier representation
+//! Subject and authority key identifier representations
 
-use der::{asn1::OctetStringRef, referenced::OwnedToRef};
+use crate::{
+    name::{GeneralNameRef, GeneralNameRefs},
+    CertificateError,
+};
+use const_oid::db::rfc5280::{ID_CE_AUTHORITY_KEY_IDENTIFIER, ID_CE_SUBJECT_KEY_IDENTIFIER};
+use der::{asn1::OctetStringRef, referenced::OwnedToRef, Decode, Encode, Sequence};
 use digest::Digest;
+#[cfg(feature = "sha1")]
 use sha1::Sha1;
+use sha2::{Sha256, Sha384, Sha512};
 use spki::{SubjectPublicKeyInfoOwned, SubjectPublicKeyInfoRef};
-use x509_cert::impl_newtype;
+use x509_cert::{impl_newtype, serial_number::SerialNumber, Certificate};
 
-/// SHA-1 digest output size
+/// Size of the truncated key identifiers, which is the SHA-1 digest output size
 const OUTPUT_SIZE: usize = 20;
 
+/// SHA-512 digest output size, which is the largest key identifier computed
+const MAX_OUTPUT_SIZE: usize = 64;
+
 /// Referenced subject key identifier
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct SubjectKeyIdentifierRef<'a>(pub OctetStringRef<'a>);
@@ -21,8 +31,125 @@ pub enum KeyIdentifier<'a> {
     /// Referenced from subject key identifier extension
     Referenced(&'a [u8]),
 
+    /// Digest of the subject public key, see [`KeyIdMethod`]. Only the first `usize` bytes are
+    /// part of the key identifier.
+    Owned([u8; MAX_OUTPUT_SIZE], usize),
+}
+
+impl KeyIdentifier<'_> {
+    /// Returns the key identifier as bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Referenced(id) => id,
+            Self::Owned(id, len) => &id[..*len],
+        }
+    }
+
+    /// Returns the method deriving this key identifier from `spki`, if any.
+    pub fn method(
+        &self,
+        spki: SubjectPublicKeyInfoRef<'_>,
+    ) -> Result<Option<KeyIdMethod>, CertificateError> {
+        for method in KeyIdMethod::ALL {
+            if KeyIdentifier::new(spki.clone(), *method)?.as_bytes() == self.as_bytes() {
+                return Ok(Some(*method));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl KeyIdentifier<'static> {
+    /// Computes the key identifier of `spki` using `method`.
+    pub fn new(
+        spki: SubjectPublicKeyInfoRef<'_>,
+        method: KeyIdMethod,
+    ) -> Result<Self, CertificateError> {
+        let key = spki.subject_public_key.raw_bytes();
+        Ok(match method {
+            #[cfg(feature = "sha1")]
+            KeyIdMethod::Sha1 => digest::<Sha1>(key, OUTPUT_SIZE),
+            KeyIdMethod::Sha256 => digest::<Sha256>(key, OUTPUT_SIZE),
+            KeyIdMethod::Sha384 => digest::<Sha384>(key, OUTPUT_SIZE),
+            KeyIdMethod::Sha512 => digest::<Sha512>(key, OUTPUT_SIZE),
+            KeyIdMethod::SpkiSha256 => digest::<Sha256>(&spki.to_der()?, 32),
+            KeyIdMethod::SpkiSha384 => digest::<Sha384>(&spki.to_der()?, 48),
+            KeyIdMethod::SpkiSha512 => digest::<Sha512>(&spki.to_der()?, 64),
+        })
+    }
+}
+
+/// Method deriving a key identifier from a public key, as defined in [RFC 5280 Section 4.2.1.2]
+/// and [RFC 7093 Section 2].
+///
+/// [RFC 5280 Section 4.2.1.2]: https://datatracker.ietf.org/doc/html/rfc5280#section-4.2.1.2
+/// [RFC 7093 Section 2]: https://datatracker.ietf.org/doc/html/rfc7093#section-2
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum KeyIdMethod {
     /// SHA-1 digest of the subject public key
-    Owned([u8; OUTPUT_SIZE]),
+    #[cfg(feature = "sha1")]
+    Sha1,
+
+    /// Leftmost 160 bits of the SHA-256 digest of the subject public key
+    Sha256,
+
+    /// Leftmost 160 bits of the SHA-384 digest of the subject public key
+    Sha384,
+
+    /// Leftmost 160 bits of the SHA-512 digest of the subject public key
+    Sha512,
+
+    /// SHA-256 digest of the DER-encoded subject public key info
+    SpkiSha256,
+
+    /// SHA-384 digest of the DER-encoded subject public key info
+    SpkiSha384,
+
+    /// SHA-512 digest of the DER-encoded subject public key info
+    SpkiSha512,
+}
+
+impl KeyIdMethod {
+    /// Every available method, in the order they are tried when matching key identifiers
+    pub const ALL: &'static [Self] = &[
+        #[cfg(feature = "sha1")]
+        Self::Sha1,
+        Self::Sha256,
+        Self::Sha384,
+        Self::Sha512,
+        Self::SpkiSha256,
+        Self::SpkiSha384,
+        Self::SpkiSha512,
+    ];
+}
+
+/// Defaults to [`KeyIdMethod::Sha1`], or to [`KeyIdMethod::Sha256`] without the `sha1` feature.
+impl Default for KeyIdMethod {
+    fn default() -> Self {
+        #[cfg(feature = "sha1")]
+        return Self::Sha1;
+        #[cfg(not(feature = "sha1"))]
+        return Self::Sha256;
+    }
+}
+
+/// Returns the first `len` bytes of the digest of `data`
+fn digest<D: Digest>(data: &[u8], len: usize) -> KeyIdentifier<'static> {
+    let mut output = [0u8; MAX_OUTPUT_SIZE];
+    output[..len].copy_from_slice(&D::digest(data)[..len]);
+    KeyIdentifier::Owned(output, len)
+}
+
+/// Serializes as a lowercase hex string in human-readable formats and as bytes otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for KeyIdentifier<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hex::encode(self.as_bytes()))
+        } else {
+            serializer.serialize_bytes(self.as_bytes())
+        }
+    }
 }
 
 impl<'a> From<SubjectKeyIdentifierRef<'a>> for KeyIdentifier<'a> {
@@ -31,12 +158,14 @@ impl<'a> From<SubjectKeyIdentifierRef<'a>> for KeyIdentifier<'a> {
     }
 }
 
+/// Computes the key identifier using the default [`KeyIdMethod`].
 impl From<SubjectPublicKeyInfoRef<'_>> for KeyIdentifier<'static> {
     fn from(other: SubjectPublicKeyInfoRef<'_>) -> Self {
-        let mut output = [0u8; OUTPUT_SIZE];
-        Sha1::new_with_prefix(other.subject_public_key.raw_bytes())
-            .finalize_into((&mut output).into());
-        Self::Owned(output)
+        let key = other.subject_public_key.raw_bytes();
+        #[cfg(feature = "sha1")]
+        return digest::<Sha1>(key, OUTPUT_SIZE);
+        #[cfg(not(feature = "sha1"))]
+        return digest::<Sha256>(key, OUTPUT_SIZE);
     }
 }
 
@@ -45,3 +174,166 @@ impl From<&SubjectPublicKeyInfoOwned> for KeyIdentifier<'static> {
         Self::from(other.owned_to_ref())
     }
 }
+
+/// Referenced authority key identifier as defined in [RFC 5280 Section 4.2.1.1].
+///
+/// ```text
+/// AuthorityKeyIdentifier ::= SEQUENCE {
+///     keyIdentifier             [0] KeyIdentifier           OPTIONAL,
+///     authorityCertIssuer       [1] GeneralNames            OPTIONAL,
+///     authorityCertSerialNumber [2] CertificateSerialNumber OPTIONAL  }
+/// ```
+///
+/// [RFC 5280 Section 4.2.1.1]: https://datatracker.ietf.org/doc/html/rfc5280#section-4.2.1.1
+#[derive(Clone, Debug, PartialEq, Eq, Sequence)]
+#[allow(missing_docs)]
+pub struct AuthorityKeyIdentifierRef<'a> {
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    pub key_identifier: Option<OctetStringRef<'a>>,
+
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    pub authority_cert_issuer: Option<GeneralNameRefs<'a>>,
+
+    #[asn1(context_specific = "2", tag_mode = "IMPLICIT", optional = "true")]
+    pub authority_cert_serial_number: Option<SerialNumber>,
+}
+
+impl<'a> AuthorityKeyIdentifierRef<'a> {
+    /// Returns the authority key identifier extension of `cert`, if any.
+    pub fn from_certificate(cert: &'a Certificate) -> Result<Option<Self>, CertificateError> {
+        match extension(cert, ID_CE_AUTHORITY_KEY_IDENTIFIER) {
+            Some(value) => Ok(Some(Self::from_der(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the `keyIdentifier`, if any.
+    pub fn key_id(&self) -> Option<KeyIdentifier<'a>> {
+        self.key_identifier
+            .map(|id| KeyIdentifier::Referenced(id.as_bytes()))
+    }
+
+    /// Returns whether `issuer` is the certificate identified by this authority key identifier.
+    ///
+    /// The `keyIdentifier`, when present, must match either the subject key identifier of
+    /// `issuer` or its public key under any of the [`KeyIdMethod::ALL`] derivations. The `authorityCertIssuer` and
+    /// `authorityCertSerialNumber`, when both present, must match the issuer name and the
+    /// serial number of `issuer`.
+    pub fn matches(&self, issuer: &Certificate) -> Result<bool, CertificateError> {
+        if let Some(key_id) = self.key_id() {
+            let subject_key_id = match extension(issuer, ID_CE_SUBJECT_KEY_IDENTIFIER) {
+                Some(value) => Some(KeyIdentifier::from(SubjectKeyIdentifierRef::from_der(
+                    value,
+                )?)),
+                None => None,
+            };
+            if subject_key_id.map(|id| id.as_bytes() == key_id.as_bytes()) != Some(true)
+                && key_id
+                    .method(
+                        issuer
+                            .tbs_certificate
+                            .subject_public_key_info
+                            .owned_to_ref(),
+                    )?
+                    .is_none()
+            {
+                return Ok(false);
+            }
+        }
+        if let (Some(names), Some(serial)) = (
+            &self.authority_cert_issuer,
+            &self.authority_cert_serial_number,
+        ) {
+            let name = GeneralNameRef::DirectoryName(issuer.tbs_certificate.issuer.clone());
+            if serial != &issuer.tbs_certificate.serial_number || !names.contains(&name) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Returns the value of the extension identified by `oid`, if any.
+fn extension(cert: &Certificate, oid: der::asn1::ObjectIdentifier) -> Option<&[u8]> {
+    cert.tbs_certificate
+        .extensions
+        .iter()
+        .flatten()
+        .find(|e| e.extn_id == oid)
+        .map(|e| e.extn_value.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthorityKeyIdentifierRef, KeyIdMethod, KeyIdentifier};
+    use crate::name::GeneralNameRef;
+    use alloc::vec;
+    use const_oid::db::rfc8410::ID_ED_25519;
+    use core::str::FromStr;
+    use der::{
+        asn1::{BitStringRef, OctetString},
+        Decode, Encode,
+    };
+    use sha2::{Digest, Sha256, Sha512};
+    use spki::{AlgorithmIdentifier, SubjectPublicKeyInfoRef};
+    use x509_cert::{
+        ext::pkix::{name::GeneralName, AuthorityKeyIdentifier},
+        name::Name,
+        serial_number::SerialNumber,
+    };
+
+    #[test]
+    fn authority_key_identifier() {
+        let name = Name::from_str("CN=Test Root,O=Test").unwrap();
+        let der = AuthorityKeyIdentifier {
+            key_identifier: Some(OctetString::new([1, 2, 3]).unwrap()),
+            authority_cert_issuer: Some(vec![GeneralName::DirectoryName(name.clone())]),
+            authority_cert_serial_number: Some(SerialNumber::new(&[2]).unwrap()),
+        }
+        .to_der()
+        .unwrap();
+        let aki = AuthorityKeyIdentifierRef::from_der(&der).unwrap();
+        assert_eq!(aki.key_id().unwrap().as_bytes(), [1, 2, 3]);
+        assert_eq!(
+            aki.authority_cert_issuer,
+            Some(vec![GeneralNameRef::DirectoryName(name)])
+        );
+        assert_eq!(
+            aki.authority_cert_serial_number,
+            Some(SerialNumber::new(&[2]).unwrap())
+        );
+        assert_eq!(aki.to_der().unwrap(), der);
+
+        let empty = AuthorityKeyIdentifierRef::from_der(&[0x30, 0x00]).unwrap();
+        assert!(empty.key_id().is_none());
+    }
+
+    #[test]
+    fn key_id_methods() {
+        let key = [7u8; 32];
+        let spki = SubjectPublicKeyInfoRef {
+            algorithm: AlgorithmIdentifier {
+                oid: ID_ED_25519,
+                parameters: None,
+            },
+            subject_public_key: BitStringRef::from_bytes(&key).unwrap(),
+        };
+        let id = KeyIdentifier::new(spki.clone(), KeyIdMethod::Sha256).unwrap();
+        assert_eq!(id.as_bytes(), &Sha256::digest(key)[..20]);
+        let id = KeyIdentifier::new(spki.clone(), KeyIdMethod::SpkiSha512).unwrap();
+        assert_eq!(
+            id.as_bytes(),
+            Sha512::digest(spki.to_der().unwrap()).as_slice()
+        );
+        assert_eq!(
+            KeyIdentifier::from(spki.clone()),
+            KeyIdentifier::new(spki.clone(), KeyIdMethod::default()).unwrap()
+        );
+        for method in KeyIdMethod::ALL {
+            let id = KeyIdentifier::new(spki.clone(), *method).unwrap();
+            assert_eq!(id.method(spki.clone()).unwrap(), Some(*method));
+        }
+        let other = KeyIdentifier::Referenced(&[1, 2, 3]);
+        assert_eq!(other.method(spki).unwrap(), None);
+    }
+}
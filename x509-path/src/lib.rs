@@ -10,6 +10,7 @@
     unused_lifetimes,
     unused_qualifications
 )]
+#![cfg_attr(test, allow(clippy::unwrap_used))]
 
 extern crate alloc;
 
@@ -19,14 +20,21 @@ extern crate std;
 mod anchor;
 mod cert;
 mod error;
+mod identity;
 mod key_identifier;
 mod policy;
 
 pub mod name;
 
 pub use anchor::TrustAnchor;
-pub use cert::CertTarget;
-pub use error::{CertificateError, Error};
-pub use key_identifier::{KeyIdentifier, SubjectKeyIdentifierRef};
+pub use cert::{is_self_issued, is_self_signed, CertTarget};
+pub use error::{CertificateError, Error, IdentityError};
+pub use identity::{
+    verify_email, verify_identity, CommonNameFallback, ReferenceId, ID_ON_DNS_SRV,
+    ID_ON_SMTP_UTF8_MAILBOX,
+};
+pub use key_identifier::{
+    AuthorityKeyIdentifierRef, KeyIdMethod, KeyIdentifier, SubjectKeyIdentifierRef,
+};
 pub use name::{NameConstraints, Names};
 pub use policy::{PolicyFlags, PolicySet};
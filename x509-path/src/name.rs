@@ -1,8 +1,12 @@
 //! Certificate name requirements
 
-use crate::CertificateError;
+use crate::{
+    identity::{subject_alt_names, uri_scheme_and_host},
+    CertificateError,
+};
 use alloc::{slice::Iter, vec::Vec};
 use der::Sequence;
+use x509_cert::{name::Name, Certificate};
 
 mod dirstr;
 mod dp;
@@ -23,7 +27,21 @@ pub use other::OtherNameRef;
 #[allow(missing_docs)]
 pub struct GeneralSubtreeRef<'a> {
     pub base: GeneralNameRef<'a>,
+
+    #[asn1(
+        context_specific = "0",
+        tag_mode = "IMPLICIT",
+        constructed = "false",
+        default = "Default::default"
+    )]
     pub minimum: u32,
+
+    #[asn1(
+        context_specific = "1",
+        tag_mode = "IMPLICIT",
+        constructed = "false",
+        optional = "true"
+    )]
     pub maximum: Option<u32>,
 }
 
@@ -36,7 +54,10 @@ pub type GeneralSubtreeRefs<'a> = Vec<GeneralSubtreeRef<'a>>;
 #[derive(Clone, Debug, Eq, PartialEq, Sequence)]
 #[allow(missing_docs)]
 pub struct NameConstraintsRef<'a> {
+    #[asn1(context_specific = "0", optional = "true", tag_mode = "IMPLICIT")]
     pub permitted_subtrees: Option<GeneralSubtreeRefs<'a>>,
+
+    #[asn1(context_specific = "1", optional = "true", tag_mode = "IMPLICIT")]
     pub excluded_subtrees: Option<GeneralSubtreeRefs<'a>>,
 }
 
@@ -137,15 +158,60 @@ impl<'a> AsRef<Vec<GeneralNameRef<'a>>> for Names<'a> {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Names<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
 /// List of allowed and prohibited naming conventions. Used in X.509 to restrict the `subject` and
 /// `subjectAltName` fields.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[allow(missing_docs)]
 pub struct NameConstraints<'a, 'b> {
     permitted_subtrees: Names<'a>,
     excluded_subtrees: Names<'b>,
 }
 
+impl<'a, 'b> NameConstraints<'a, 'b> {
+    /// Returns the permitted subtrees.
+    pub fn permitted_subtrees(&self) -> &Names<'a> {
+        &self.permitted_subtrees
+    }
+
+    /// Returns the excluded subtrees.
+    pub fn excluded_subtrees(&self) -> &Names<'b> {
+        &self.excluded_subtrees
+    }
+
+    /// Returns `true` if `name` is outside every excluded subtree and, when there are permitted
+    /// subtrees of the same name type, within one of them. Names of a type with no permitted
+    /// subtrees are permitted, as required by RFC 5280.
+    pub fn permits(&self, name: &GeneralNameRef<'_>) -> bool {
+        if self.excluded_subtrees.iter().any(|base| within(name, base)) {
+            return false;
+        }
+        let mut permitted = self
+            .permitted_subtrees
+            .iter()
+            .filter(|base| core::mem::discriminant(*base) == core::mem::discriminant(name))
+            .peekable();
+        permitted.peek().is_none() || permitted.any(|base| within(name, base))
+    }
+
+    /// Returns `true` if the subject of `cert`, unless it is empty, and each of its subject
+    /// alternative names are permitted. The subject `emailAddress` attribute is not considered.
+    pub fn permits_certificate(&self, cert: &Certificate) -> Result<bool, CertificateError> {
+        let subject = &cert.tbs_certificate.subject;
+        if !subject.0.is_empty() && !self.permits(&GeneralNameRef::DirectoryName(subject.clone())) {
+            return Ok(false);
+        }
+        Ok(subject_alt_names(cert)?.iter().all(|n| self.permits(n)))
+    }
+}
+
 impl<'a> TryFrom<NameConstraintsRef<'a>> for NameConstraints<'a, 'a> {
     type Error = CertificateError;
 
@@ -183,9 +249,80 @@ impl<'a> TryFrom<NameConstraintsRef<'a>> for NameConstraints<'a, 'a> {
     }
 }
 
+/// Returns `true` if `name` is within the subtree rooted at `base`, following the matching rules
+/// of RFC 5280 Section 4.2.1.10.
+fn within(name: &GeneralNameRef<'_>, base: &GeneralNameRef<'_>) -> bool {
+    match (name, base) {
+        (GeneralNameRef::DnsName(n), GeneralNameRef::DnsName(b)) => {
+            dns_within(n.as_str(), b.as_str())
+        }
+        (GeneralNameRef::Rfc822Name(n), GeneralNameRef::Rfc822Name(b)) => {
+            let (local, host) = match n.as_str().rsplit_once('@') {
+                Some(mailbox) => mailbox,
+                None => return false,
+            };
+            let b = b.as_str();
+            match b.rsplit_once('@') {
+                Some((l, h)) => l == local && h.eq_ignore_ascii_case(host),
+                None => match b.strip_prefix('.') {
+                    Some(domain) => subdomain_of(host, domain),
+                    None => host.eq_ignore_ascii_case(b),
+                },
+            }
+        }
+        (
+            GeneralNameRef::UniformResourceIdentifier(n),
+            GeneralNameRef::UniformResourceIdentifier(b),
+        ) => match uri_scheme_and_host(n.as_str()) {
+            Some((_, host)) => match b.as_str().strip_prefix('.') {
+                Some(domain) => subdomain_of(host, domain),
+                None => host.eq_ignore_ascii_case(b.as_str()),
+            },
+            None => false,
+        },
+        (GeneralNameRef::IpAddress(n), GeneralNameRef::IpAddress(b)) => {
+            let (n, b) = (n.as_bytes(), b.as_bytes());
+            let (addr, mask) = b.split_at(b.len() / 2);
+            n.len() == addr.len()
+                && n.iter()
+                    .zip(addr.iter().zip(mask))
+                    .all(|(n, (a, m))| n & m == a & m)
+        }
+        (GeneralNameRef::DirectoryName(n), GeneralNameRef::DirectoryName(b)) => dn_within(n, b),
+        (n, b) => n == b,
+    }
+}
+
+/// Returns `true` if the DNS name `name` equals `base` or is one of its subdomains. A leading
+/// period in `base` only matches subdomains.
+fn dns_within(name: &str, base: &str) -> bool {
+    let name = name.strip_suffix('.').unwrap_or(name);
+    let base = base.strip_suffix('.').unwrap_or(base);
+    match base.strip_prefix('.') {
+        Some(domain) => subdomain_of(name, domain),
+        None => base.is_empty() || name.eq_ignore_ascii_case(base) || subdomain_of(name, base),
+    }
+}
+
+/// Returns `true` if `host` has at least one more label than `domain` and ends with it
+fn subdomain_of(host: &str, domain: &str) -> bool {
+    if host.len() <= domain.len() + 1 {
+        return false;
+    }
+    let split = host.len() - domain.len();
+    host.is_char_boundary(split)
+        && host.as_bytes()[split - 1] == b'.'
+        && host[split..].eq_ignore_ascii_case(domain)
+}
+
+/// Returns `true` if the relative distinguished names of `base` are a prefix of those of `name`
+fn dn_within(name: &Name, base: &Name) -> bool {
+    name.0.len() >= base.0.len() && name.0.iter().zip(base.0.iter()).all(|(n, b)| n == b)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{name::GeneralNameRef, names, Names};
+    use crate::{name::GeneralNameRef, NameConstraints, Names};
     use core::str::FromStr;
     use der::asn1::Ia5String;
     use x509_cert::{ext::pkix::name::GeneralName, name::Name};
@@ -227,4 +364,77 @@ mod tests {
         assert_eq!(empty, Names::new());
         assert_eq!(&empty, &Names::new());
     }
+
+    #[test]
+    fn constraint_matching() {
+        use der::asn1::OctetString;
+
+        let dns = |n: &str| GeneralName::DnsName(Ia5String::new(n).unwrap());
+        let email = |n: &str| GeneralName::Rfc822Name(Ia5String::new(n).unwrap());
+        let uri = |n: &str| GeneralName::UniformResourceIdentifier(Ia5String::new(n).unwrap());
+        let ip = |n: &[u8]| GeneralName::IpAddress(OctetString::new(n).unwrap());
+        let dn = |n: &str| GeneralName::DirectoryName(Name::from_str(n).unwrap());
+
+        let permitted = [
+            dns("example.com"),
+            email(".example.com"),
+            uri(".example.com"),
+            ip(&[192, 168, 0, 0, 255, 255, 0, 0]),
+            dn("O=Example,C=US"),
+        ];
+        let excluded = [dns("secret.example.com"), email("root@mail.example.com")];
+        let mut constraints = NameConstraints::default();
+        for name in &permitted {
+            constraints
+                .permitted_subtrees
+                .insert(GeneralNameRef::from(name));
+        }
+        for name in &excluded {
+            constraints
+                .excluded_subtrees
+                .insert(GeneralNameRef::from(name));
+        }
+        let permits = |n: GeneralName| constraints.permits(&GeneralNameRef::from(&n));
+
+        assert!(permits(dns("example.com")));
+        assert!(permits(dns("WWW.Example.com.")));
+        assert!(!permits(dns("badexample.com")));
+        assert!(!permits(dns("example.org")));
+        assert!(!permits(dns("a.secret.example.com")));
+        assert!(permits(email("user@mail.example.com")));
+        assert!(!permits(email("user@example.com")));
+        assert!(!permits(email("root@mail.example.com")));
+        assert!(permits(uri("https://www.example.com:8443/path")));
+        assert!(!permits(uri("https://example.com/")));
+        assert!(!permits(uri("urn:example:com")));
+        assert!(permits(ip(&[192, 168, 10, 1])));
+        assert!(!permits(ip(&[10, 0, 0, 1])));
+        assert!(!permits(ip(&[0; 16])));
+        assert!(permits(dn("CN=Leaf,O=Example,C=US")));
+        assert!(!permits(dn("CN=Leaf,O=Other,C=US")));
+        assert!(permits(GeneralName::RegisteredId(
+            der::asn1::ObjectIdentifier::new_unwrap("1.2.3")
+        )));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn names_serde() {
+        use der::asn1::OctetString;
+
+        let name = GeneralName::DirectoryName(Name::from_str("C=US,O=Evil Corp.").unwrap());
+        let dns = GeneralName::DnsName(Ia5String::new(".example.com").unwrap());
+        let ip = GeneralName::IpAddress(
+            OctetString::new([192, 168, 0, 0, 255, 255, 0, 0].as_slice()).unwrap(),
+        );
+        let tree = names![
+            GeneralNameRef::from(&name),
+            GeneralNameRef::from(&dns),
+            GeneralNameRef::from(&ip),
+        ];
+        assert_eq!(
+            serde_json::to_string(&tree).unwrap(),
+            r#"[{"directoryName":"C=US,O=Evil Corp."},{"dNSName":".example.com"},{"iPAddress":"192.168.0.0/255.255.0.0"}]"#
+        );
+    }
 }
@@ -56,3 +56,63 @@ impl<'a> From<&'a GeneralName> for GeneralNameRef<'a> {
         }
     }
 }
+
+/// Serializes as a single-entry map keyed by the `GeneralName` choice, as named in RFC 5280.
+/// Directory names are written as RFC 4514 strings and IP addresses, or address ranges of name
+/// constraints, in their textual notation. Other and EDI party names are written as hex DER.
+#[cfg(feature = "serde")]
+impl serde::Serialize for GeneralNameRef<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use alloc::string::ToString;
+        use der::Encode;
+        use serde::ser::Error;
+
+        const NAME: &str = "GeneralName";
+        let (index, variant, value) = match self {
+            Self::OtherName(n) => (
+                0,
+                "otherName",
+                hex::encode(n.to_der().map_err(S::Error::custom)?),
+            ),
+            Self::Rfc822Name(n) => (1, "rfc822Name", n.to_string()),
+            Self::DnsName(n) => (2, "dNSName", n.to_string()),
+            Self::DirectoryName(n) => (4, "directoryName", n.to_string()),
+            Self::EdiPartyName(n) => (
+                5,
+                "ediPartyName",
+                hex::encode(n.to_der().map_err(S::Error::custom)?),
+            ),
+            Self::UniformResourceIdentifier(n) => (6, "uniformResourceIdentifier", n.to_string()),
+            Self::IpAddress(n) => (7, "iPAddress", ip_address(n.as_bytes())),
+            Self::RegisteredId(n) => (8, "registeredID", n.to_string()),
+        };
+        serializer.serialize_newtype_variant(NAME, index, variant, &value)
+    }
+}
+
+/// Formats an IPv4 or IPv6 address, or an address and mask pair as found in name constraints.
+#[cfg(feature = "serde")]
+fn ip_address(bytes: &[u8]) -> alloc::string::String {
+    use alloc::{format, string::String, vec::Vec};
+
+    fn address(bytes: &[u8]) -> String {
+        if bytes.len() == 4 {
+            format!("{}.{}.{}.{}", bytes[0], bytes[1], bytes[2], bytes[3])
+        } else {
+            let groups: Vec<String> = bytes
+                .chunks(2)
+                .map(|g| format!("{:x}", u16::from_be_bytes([g[0], g[1]])))
+                .collect();
+            groups.join(":")
+        }
+    }
+
+    match bytes.len() {
+        4 | 16 => address(bytes),
+        8 | 32 => {
+            let (addr, mask) = bytes.split_at(bytes.len() / 2);
+            format!("{}/{}", address(addr), address(mask))
+        }
+        _ => hex::encode(bytes),
+    }
+}
@@ -1,12 +1,18 @@
 //! Certificate policy requirements
 
 use crate::CertificateError;
+#[cfg(feature = "serde")]
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 use der::asn1::ObjectIdentifier;
 use hashbrown::{hash_set::Iter, HashSet};
 use x509_cert::ext::pkix::CertificatePolicies;
 
 /// Policy flags to define the behavior of the policy tree during path validation.
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PolicyFlags {
     /// `inhibitPolicyMapping` indicates if policy mapping is allowed in the certification path. When
     /// set to `true`, policy mapping is not permitted.
@@ -89,6 +95,11 @@ impl PolicySet {
     pub fn iter(&self) -> Iter<'_, ObjectIdentifier> {
         self.0.iter()
     }
+
+    /// Returns a new `PolicySet` containing only the policy OIDs present in both sets.
+    pub fn intersection(&self, other: &PolicySet) -> PolicySet {
+        Self(self.0.intersection(&other.0).copied().collect())
+    }
 }
 
 impl AsRef<HashSet<ObjectIdentifier>> for PolicySet {
@@ -112,9 +123,33 @@ impl TryFrom<CertificatePolicies> for PolicySet {
     }
 }
 
+/// Serializes as a sequence of dotted OID strings, sorted for stable output.
+#[cfg(feature = "serde")]
+impl serde::Serialize for PolicySet {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut policies: Vec<&ObjectIdentifier> = self.0.iter().collect();
+        policies.sort();
+        serializer.collect_seq(policies.iter().map(|p| p.to_string()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PolicySet {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut policies = PolicySet::new();
+        for policy in Vec::<String>::deserialize(deserializer)? {
+            let oid = ObjectIdentifier::new(&policy).map_err(serde::de::Error::custom)?;
+            if !policies.insert(oid) {
+                return Err(serde::de::Error::custom("duplicate policy"));
+            }
+        }
+        Ok(policies)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{policy_set, PolicySet};
+    use crate::PolicySet;
     use const_oid::db::{
         rfc5280::{ID_CE_BASIC_CONSTRAINTS, ID_CE_NAME_CONSTRAINTS},
         rfc5912::ID_CE_CERTIFICATE_POLICIES,
@@ -147,6 +182,17 @@ mod tests {
         assert!(policies.contains(&ID_CE_BASIC_CONSTRAINTS));
     }
 
+    #[test]
+    fn policy_set_intersection() {
+        let left = policy_set![ID_CE_BASIC_CONSTRAINTS, ID_CE_NAME_CONSTRAINTS];
+        let right = policy_set![ID_CE_NAME_CONSTRAINTS, ID_CE_CERTIFICATE_POLICIES];
+        assert_eq!(
+            left.intersection(&right),
+            policy_set![ID_CE_NAME_CONSTRAINTS]
+        );
+        assert!(left.intersection(&policy_set![]).is_empty());
+    }
+
     #[test]
     fn empty_policy_set() {
         let empty = policy_set![];
@@ -155,4 +201,15 @@ mod tests {
         assert_eq!(empty, PolicySet::new());
         assert_eq!(&empty, &PolicySet::new());
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn policy_set_serde() {
+        let policies = policy_set![ID_CE_NAME_CONSTRAINTS, ID_CE_BASIC_CONSTRAINTS];
+        let json = serde_json::to_string(&policies).unwrap();
+        assert_eq!(json, r#"["2.5.29.19","2.5.29.30"]"#);
+        assert_eq!(serde_json::from_str::<PolicySet>(&json).unwrap(), policies);
+        assert!(serde_json::from_str::<PolicySet>(r#"["2.5.29.19","2.5.29.19"]"#).is_err());
+        assert!(serde_json::from_str::<PolicySet>(r#"["not an oid"]"#).is_err());
+    }
 }